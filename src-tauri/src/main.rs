@@ -2,13 +2,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use reqwest;
-use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, Mutex};
-use tokio::sync::oneshot;
+use tauri::Manager;
 mod platforms;
 mod proxy;
-use platforms::common::{DouyinDanmakuState, FollowHttpClient, HuyaDanmakuState};
+use platforms::common::types_rust::SupportedPlatformRust;
+use platforms::common::danmaku::{BatchOptions, DanmakuBatch, DanmakuChannel};
+use platforms::common::{DanmakuListenerRegistry, FollowHttpClient};
 use platforms::douyin::danmu::signature::generate_douyin_ms_token;
 use platforms::douyin::fetch_douyin_partition_rooms;
 use platforms::douyin::fetch_douyin_room_info;
@@ -23,15 +23,6 @@ use platforms::huya::stop_huya_danmaku_listener;
 use platforms::huya::{fetch_huya_live_list, start_huya_danmaku_listener};
 // use platforms::huya::get_huya_stream_url_with_quality; // removed in favor of unified cmd
 
-#[derive(Default, Clone)]
-pub struct StreamUrlStore {
-    pub url: Arc<Mutex<String>>,
-}
-
-// State for managing Douyu danmaku listener handles (stop signals)
-#[derive(Default, Clone)]
-pub struct DouyuDanmakuHandles(Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>);
-
 #[tauri::command]
 async fn get_stream_url_cmd(room_id: String) -> Result<String, String> {
     // Call the actual function to fetch the stream URL from the new location
@@ -68,43 +59,35 @@ async fn get_stream_url_with_quality_cmd(
 
 // Legacy Huya stream URL command removed in favor of unified command
 
-// This is the command that should be used for setting stream URL if it interacts with StreamUrlStore
-#[tauri::command]
-async fn set_stream_url_cmd(
-    url: String,
-    state: tauri::State<'_, StreamUrlStore>,
-) -> Result<(), String> {
-    let mut current_url = state.url.lock().unwrap();
-    *current_url = url;
-    Ok(())
-}
-
 // Command to start Douyu danmaku listener
 #[tauri::command]
 async fn start_danmaku_listener(
     room_id: String,
+    cookie: Option<String>,
+    channel: Option<tauri::ipc::Channel<DanmakuBatch>>,
+    batch: Option<BatchOptions>,
     window: tauri::Window,
-    danmaku_handles: tauri::State<'_, DouyuDanmakuHandles>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
-    // If a listener for this room_id already exists, stop it first.
-    if let Some(existing_sender) = danmaku_handles.0.lock().unwrap().remove(&room_id) {
-        let _ = existing_sender.send(());
-    }
-
-    let (stop_tx, stop_rx) = oneshot::channel();
-    danmaku_handles
-        .0
-        .lock()
-        .unwrap()
-        .insert(room_id.clone(), stop_tx);
+    let delivery = DanmakuChannel::open(window.app_handle(), channel, batch);
+    // 同一房间的旧监听会在注册时被停止，其它房间不受影响
+    let (listener_id, stop_rx) = registry.register(SupportedPlatformRust::Douyu, &room_id);
 
     let window_clone = window.clone();
     let room_id_clone = room_id.clone();
     tokio::spawn(async move {
         let mut client = platforms::douyu::danmu_start::DanmakuClient::new(
             &room_id_clone,
-            window_clone,
+            window_clone.clone(),
             stop_rx, // Pass the receiver part of the oneshot channel
+            delivery,
+            listener_id,
+        )
+        // 带登录 cookie 时以用户身份登录，可通过 send_douyu_danmaku 发言
+        .with_auth(
+            cookie
+                .as_deref()
+                .and_then(platforms::douyu::connection::DouyuAuth::from_cookie),
         );
         if let Err(e) = client.start().await {
             eprintln!(
@@ -112,6 +95,9 @@ async fn start_danmaku_listener(
                 room_id_clone, e
             );
         }
+        window_clone
+            .state::<DanmakuListenerRegistry>()
+            .finish(SupportedPlatformRust::Douyu, &room_id_clone, listener_id);
     });
 
     Ok(())
@@ -121,25 +107,16 @@ async fn start_danmaku_listener(
 #[tauri::command]
 async fn stop_danmaku_listener(
     room_id: String,
-    danmaku_handles: tauri::State<'_, DouyuDanmakuHandles>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
-    if let Some(sender) = danmaku_handles.0.lock().unwrap().remove(&room_id) {
-        match sender.send(()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(format!(
-                "Failed to stop Douyu danmaku listener for room {}: receiver dropped.",
-                room_id
-            )),
-        }
-    } else {
-        Ok(())
-    }
+    registry.stop(SupportedPlatformRust::Douyu, &room_id);
+    Ok(())
 }
 
 // search_anchor seems fine, assuming douyu::search_anchor is correct
 #[tauri::command]
 async fn search_anchor(keyword: String) -> Result<String, String> {
-    platforms::douyu::perform_anchor_search(&keyword)
+    platforms::douyu::perform_anchor_search(&keyword, 1)
         .await
         .map_err(|e| e.to_string())
 }
@@ -158,17 +135,22 @@ fn main() {
         .plugin(tauri_plugin_os::init())
         .manage(client) // Manage the reqwest client
         .manage(follow_http_client) // 专用关注刷新客户端，避免占用默认连接池
-        .manage(DouyuDanmakuHandles::default()) // Manage new DouyuDanmakuHandles
-        .manage(DouyinDanmakuState::default()) // Manage DouyinDanmakuState
-        .manage(HuyaDanmakuState::default()) // Manage HuyaDanmakuState
-        .manage(platforms::common::BilibiliDanmakuState::default()) // Manage BilibiliDanmakuState
-        .manage(StreamUrlStore::default())
-        .manage(proxy::ProxyServerHandle::default())
+        .manage(DanmakuListenerRegistry::default()) // 按 (平台, 房间) 管理所有弹幕监听
+        .manage(platforms::common::danmaku::DanmakuStats::default())
+        .manage(platforms::douyu::connection::DouyuConnections::default()) // 斗鱼弹幕端口健康度与发言通道
+        .manage(proxy::StreamProxy::default()) // 多路直播流代理，按会话路由
         .manage(platforms::bilibili::state::BilibiliState::default())
+        .setup(|app| {
+            // 弹幕录制写入应用数据目录，所有平台的监听共用一个写入任务
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(platforms::common::danmaku::DanmakuRecorder::spawn(&data_dir));
+            app.manage(platforms::common::danmaku::DanmakuFilter::load(&data_dir));
+            platforms::common::danmaku::DanmakuStats::spawn_emitter(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_stream_url_cmd,
             get_stream_url_with_quality_cmd,
-            set_stream_url_cmd,
             search_anchor,
             start_danmaku_listener,      // Douyu danmaku start
             stop_danmaku_listener,       // Douyu danmaku stop
@@ -177,8 +159,9 @@ fn main() {
             stop_huya_danmaku_listener,  // Added Huya danmaku stop command
             platforms::bilibili::danmaku::start_bilibili_danmaku_listener,
             platforms::bilibili::danmaku::stop_bilibili_danmaku_listener,
-            proxy::start_proxy,
-            proxy::stop_proxy,
+            proxy::register_stream,
+            proxy::unregister_stream,
+            proxy::list_proxy_streams,
             proxy::start_static_proxy_server,
             fetch_categories,
             fetch_live_list,
@@ -200,8 +183,21 @@ fn main() {
             platforms::bilibili::streamer_info::fetch_bilibili_streamer_info,
             platforms::bilibili::cookie::get_bilibili_cookie,
             platforms::bilibili::cookie::bootstrap_bilibili_cookie,
+            platforms::bilibili::send::send_bilibili_danmaku,
+            platforms::douyu::connection::send_douyu_danmaku,
             platforms::bilibili::search::search_bilibili_rooms,
             platforms::huya::search::search_huya_anchors,
+            platforms::common::live_platform::live_platform_request,
+            platforms::common::danmaku::registry::list_active_danmaku_listeners,
+            platforms::common::danmaku::registry::stop_room_danmaku_listener,
+            platforms::common::danmaku::registry::stop_all_danmaku_listeners,
+            platforms::common::danmaku::recorder::list_danmaku_sessions,
+            platforms::common::danmaku::recorder::open_danmaku_session,
+            platforms::common::danmaku::recorder::purge_danmaku_sessions,
+            platforms::common::danmaku::export::export_danmaku_session,
+            platforms::common::danmaku::filter::get_danmaku_filter_rules,
+            platforms::common::danmaku::filter::set_danmaku_filter_rules,
+            platforms::common::danmaku::stats::get_danmaku_stats,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
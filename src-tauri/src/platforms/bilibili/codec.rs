@@ -0,0 +1,373 @@
+// B 站直播弹幕协议的封包 / 解包
+//
+// 每个包由 16 字节大端头部 + body 组成：
+//   pack_len(u32) | header_len(u16) | ver(u16) | operation(u32) | seq_id(u32)
+// 一个 WebSocket 帧里可能拼接多个包；ver 2 (zlib) / ver 3 (brotli) 的 body
+// 解压后又是一串完整的包，需要递归拆分。
+use std::io::Read;
+
+use serde_json::Value;
+
+use super::models::MsgHead;
+
+pub const HEADER_LEN: usize = 16;
+// 压缩包里不会再套压缩包，留一点余量防止恶意数据无限递归
+const MAX_NESTING: usize = 4;
+// 正常的压缩包解压后只有几十 KB，超过上限视为异常数据，避免解压炸弹占满内存
+const MAX_DECOMPRESSED_LEN: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Heartbeat = 2,
+    HeartbeatReply = 3,
+    Message = 5,
+    Auth = 7,
+    AuthReply = 8,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error("packet truncated: need {needed} bytes, got {available}")]
+    Truncated { needed: usize, available: usize },
+    #[error("invalid packet header: pack_len={pack_len}, header_len={header_len}")]
+    InvalidHeader { pack_len: u32, header_len: u16 },
+    #[error("unsupported protocol version {0}")]
+    UnsupportedVersion(u16),
+    #[error("failed to decompress body: {0}")]
+    Decompress(#[from] std::io::Error),
+    #[error("invalid json body: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("compressed packets nested too deeply")]
+    TooDeep,
+    #[error("decompressed body exceeds {0} bytes")]
+    TooLarge(usize),
+}
+
+/// 解出的单个包
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    /// op=3 心跳回复，body 为人气值
+    Popularity(u32),
+    /// op=8 认证回复，例如 {"code":0}
+    AuthReply(Value),
+    /// op=5 业务消息（DANMU_MSG / SEND_GIFT 等）
+    Message(Value),
+    /// 其它未处理的 operation
+    Unknown { operation: u32 },
+}
+
+/// 构造发往服务器的包（认证 / 心跳），pack_len 按实际 body 字节数计算
+pub fn encode_packet(operation: Operation, body: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(HEADER_LEN + body.len());
+    res.extend_from_slice(&((HEADER_LEN + body.len()) as u32).to_be_bytes());
+    res.extend_from_slice(&(HEADER_LEN as u16).to_be_bytes());
+    res.extend_from_slice(&1u16.to_be_bytes());
+    res.extend_from_slice(&(operation as u32).to_be_bytes());
+    res.extend_from_slice(&1u32.to_be_bytes());
+    res.extend_from_slice(body);
+    res
+}
+
+pub fn read_header(buf: &[u8]) -> Result<MsgHead, CodecError> {
+    if buf.len() < HEADER_LEN {
+        return Err(CodecError::Truncated {
+            needed: HEADER_LEN,
+            available: buf.len(),
+        });
+    }
+    let u32_at = |i: usize| u32::from_be_bytes([buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]);
+    let u16_at = |i: usize| u16::from_be_bytes([buf[i], buf[i + 1]]);
+    Ok(MsgHead {
+        pack_len: u32_at(0),
+        raw_header_size: u16_at(4),
+        ver: u16_at(6),
+        operation: u32_at(8),
+        seq_id: u32_at(12),
+    })
+}
+
+/// 一个 WebSocket 帧的解析结果。坏包会被跳过并记在 `errors` 中，不影响同一帧里的其它包
+#[derive(Debug, Default)]
+pub struct DecodedFrame {
+    pub packets: Vec<Packet>,
+    pub errors: Vec<CodecError>,
+}
+
+/// 解析一个 WebSocket 帧中的全部包
+pub fn decode_frame(data: &[u8]) -> DecodedFrame {
+    let mut frame = DecodedFrame::default();
+    decode_into(data, 0, &mut frame);
+    frame
+}
+
+fn decode_into(data: &[u8], depth: usize, frame: &mut DecodedFrame) {
+    if depth > MAX_NESTING {
+        frame.errors.push(CodecError::TooDeep);
+        return;
+    }
+    let mut offset = 0;
+    while offset < data.len() {
+        let rest = &data[offset..];
+        // 头部损坏时无法定位下一个包，只能放弃本段剩余数据
+        let head = match read_header(rest) {
+            Ok(head) => head,
+            Err(e) => {
+                frame.errors.push(e);
+                return;
+            }
+        };
+        let pack_len = head.pack_len as usize;
+        let header_len = head.raw_header_size as usize;
+        if header_len < HEADER_LEN || pack_len < header_len {
+            frame.errors.push(CodecError::InvalidHeader {
+                pack_len: head.pack_len,
+                header_len: head.raw_header_size,
+            });
+            return;
+        }
+        if pack_len > rest.len() {
+            frame.errors.push(CodecError::Truncated {
+                needed: pack_len,
+                available: rest.len(),
+            });
+            return;
+        }
+        // 包体解析失败（JSON 错误、解压失败等）只跳过这一个包
+        if let Err(e) = decode_packet(head, &rest[header_len..pack_len], depth, frame) {
+            frame.errors.push(e);
+        }
+        offset += pack_len;
+    }
+}
+
+fn read_capped(reader: impl Read) -> Result<Vec<u8>, CodecError> {
+    let mut decoded = Vec::new();
+    reader
+        .take(MAX_DECOMPRESSED_LEN as u64 + 1)
+        .read_to_end(&mut decoded)?;
+    if decoded.len() > MAX_DECOMPRESSED_LEN {
+        return Err(CodecError::TooLarge(MAX_DECOMPRESSED_LEN));
+    }
+    Ok(decoded)
+}
+
+fn decode_packet(
+    head: MsgHead,
+    body: &[u8],
+    depth: usize,
+    frame: &mut DecodedFrame,
+) -> Result<(), CodecError> {
+    match head.ver {
+        // 0: JSON 明文，1: 心跳/认证等未压缩的二进制或 JSON
+        0 | 1 => {}
+        2 => {
+            let decoded = read_capped(flate2::read::ZlibDecoder::new(body))?;
+            decode_into(&decoded, depth + 1, frame);
+            return Ok(());
+        }
+        3 => {
+            let decoded = read_capped(brotlic::DecompressorReader::new(body))?;
+            decode_into(&decoded, depth + 1, frame);
+            return Ok(());
+        }
+        v => return Err(CodecError::UnsupportedVersion(v)),
+    }
+
+    let packet = match head.operation {
+        op if op == Operation::HeartbeatReply as u32 => {
+            if body.len() < 4 {
+                return Err(CodecError::Truncated {
+                    needed: 4,
+                    available: body.len(),
+                });
+            }
+            Packet::Popularity(u32::from_be_bytes([body[0], body[1], body[2], body[3]]))
+        }
+        op if op == Operation::AuthReply as u32 => Packet::AuthReply(serde_json::from_slice(body)?),
+        op if op == Operation::Message as u32 => Packet::Message(serde_json::from_slice(body)?),
+        operation => Packet::Unknown { operation },
+    };
+    frame.packets.push(packet);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn packet(ver: u16, operation: u32, body: &[u8]) -> Vec<u8> {
+        let mut res = Vec::with_capacity(HEADER_LEN + body.len());
+        res.extend_from_slice(&((HEADER_LEN + body.len()) as u32).to_be_bytes());
+        res.extend_from_slice(&(HEADER_LEN as u16).to_be_bytes());
+        res.extend_from_slice(&ver.to_be_bytes());
+        res.extend_from_slice(&operation.to_be_bytes());
+        res.extend_from_slice(&1u32.to_be_bytes());
+        res.extend_from_slice(body);
+        res
+    }
+
+    fn message(cmd: &str) -> Vec<u8> {
+        packet(
+            0,
+            Operation::Message as u32,
+            json!({ "cmd": cmd }).to_string().as_bytes(),
+        )
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut encoder = brotlic::CompressorWriter::new(Vec::new());
+        encoder.write_all(data).unwrap();
+        let Ok(compressed) = encoder.into_inner() else {
+            panic!("failed to finish brotli stream");
+        };
+        compressed
+    }
+
+    fn cmds(frame: &DecodedFrame) -> Vec<&str> {
+        frame
+            .packets
+            .iter()
+            .filter_map(|p| match p {
+                Packet::Message(v) => v["cmd"].as_str(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decodes_plain_json_packet() {
+        let frame = decode_frame(&message("DANMU_MSG"));
+        assert!(frame.errors.is_empty());
+        assert_eq!(cmds(&frame), vec!["DANMU_MSG"]);
+    }
+
+    #[test]
+    fn decodes_uncompressed_replies() {
+        let mut data = packet(1, Operation::HeartbeatReply as u32, &1234u32.to_be_bytes());
+        data.extend(packet(1, Operation::AuthReply as u32, br#"{"code":0}"#));
+        let frame = decode_frame(&data);
+        assert!(frame.errors.is_empty());
+        assert_eq!(
+            frame.packets,
+            vec![
+                Packet::Popularity(1234),
+                Packet::AuthReply(json!({ "code": 0 }))
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_concatenated_packets() {
+        let mut data = message("A");
+        data.extend(message("B"));
+        data.extend(packet(1, 99, b""));
+        let frame = decode_frame(&data);
+        assert!(frame.errors.is_empty());
+        assert_eq!(cmds(&frame), vec!["A", "B"]);
+        assert_eq!(frame.packets[2], Packet::Unknown { operation: 99 });
+    }
+
+    #[test]
+    fn decodes_zlib_packets() {
+        let mut inner = message("A");
+        inner.extend(message("B"));
+        let frame = decode_frame(&packet(2, Operation::Message as u32, &zlib(&inner)));
+        assert!(frame.errors.is_empty());
+        assert_eq!(cmds(&frame), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn decodes_brotli_packets() {
+        let mut inner = message("A");
+        inner.extend(message("B"));
+        let frame = decode_frame(&packet(3, Operation::Message as u32, &brotli(&inner)));
+        assert!(frame.errors.is_empty());
+        assert_eq!(cmds(&frame), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn bad_packet_keeps_siblings() {
+        let mut inner = message("A");
+        inner.extend(packet(0, Operation::Message as u32, b"{not json"));
+        inner.extend(message("B"));
+        let mut data = packet(2, Operation::Message as u32, &zlib(&inner));
+        data.extend(packet(2, Operation::Message as u32, b"not zlib"));
+        data.extend(packet(7, Operation::Message as u32, b"{}"));
+        data.extend(message("C"));
+
+        let frame = decode_frame(&data);
+        assert_eq!(cmds(&frame), vec!["A", "B", "C"]);
+        assert_eq!(frame.errors.len(), 3);
+        assert!(matches!(frame.errors[0], CodecError::Json(_)));
+        assert!(matches!(frame.errors[1], CodecError::Decompress(_)));
+        assert!(matches!(frame.errors[2], CodecError::UnsupportedVersion(7)));
+    }
+
+    #[test]
+    fn truncated_header_keeps_earlier_packets() {
+        let mut data = message("A");
+        data.extend_from_slice(&[0, 0, 0, 32, 0, 16]);
+        let frame = decode_frame(&data);
+        assert_eq!(cmds(&frame), vec!["A"]);
+        assert!(matches!(
+            frame.errors[..],
+            [CodecError::Truncated {
+                needed: HEADER_LEN,
+                available: 6
+            }]
+        ));
+    }
+
+    #[test]
+    fn oversized_pack_len_is_truncated() {
+        let mut data = message("A");
+        let mut bad = message("B");
+        bad[..4].copy_from_slice(&1_000_000u32.to_be_bytes());
+        data.extend(bad);
+        let frame = decode_frame(&data);
+        assert_eq!(cmds(&frame), vec!["A"]);
+        assert!(matches!(
+            frame.errors[..],
+            [CodecError::Truncated {
+                needed: 1_000_000,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn invalid_header_len_is_rejected() {
+        let mut data = message("A");
+        data[4..6].copy_from_slice(&8u16.to_be_bytes());
+        let frame = decode_frame(&data);
+        assert!(frame.packets.is_empty());
+        assert!(matches!(
+            frame.errors[..],
+            [CodecError::InvalidHeader { header_len: 8, .. }]
+        ));
+    }
+
+    #[test]
+    fn decompression_is_capped() {
+        let bomb = zlib(&vec![0u8; MAX_DECOMPRESSED_LEN + 1]);
+        let mut data = packet(2, Operation::Message as u32, &bomb);
+        data.extend(message("A"));
+        let frame = decode_frame(&data);
+        assert_eq!(cmds(&frame), vec!["A"]);
+        assert!(matches!(
+            frame.errors[..],
+            [CodecError::TooLarge(MAX_DECOMPRESSED_LEN)]
+        ));
+    }
+}
@@ -1,116 +1,337 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tauri::Emitter;
-use tokio::sync::mpsc as tokio_mpsc;
+use tauri::ipc::Channel;
+use tauri::Manager;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
 
-use crate::platforms::bilibili::models::BiliMessage;
+use crate::platforms::bilibili::models::{BiliMessage, BiliUser};
 use crate::platforms::bilibili::websocket::BiliLiveClient;
+use crate::platforms::common::danmaku::event::rgb_to_hex;
+use crate::platforms::common::danmaku::reconnect::{wait_or_shutdown, STABLE_SESSION};
+use crate::platforms::common::danmaku::{
+    Backoff, BatchOptions, DanmakuBatch, DanmakuChannel, DanmakuEvent, DanmakuEventKind,
+    DanmakuListenerRegistry, DanmakuSink, DanmakuStatus, DanmakuUser,
+};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+// 金瓜子 -> 元
+const GOLD_PER_CNY: f64 = 1000.0;
+
+fn guard_name(level: u8) -> &'static str {
+    match level {
+        1 => "总督",
+        2 => "提督",
+        _ => "舰长",
+    }
+}
+
+fn to_danmaku_user(user: BiliUser) -> DanmakuUser {
+    DanmakuUser {
+        nickname: user.name,
+        user_id: Some(user.uid.to_string()).filter(|_| user.uid > 0),
+        avatar: user.face,
+        user_level: 0,
+        medal_name: user.medal_name,
+        fans_club_level: user.medal_level,
+        noble_level: 0,
+    }
+}
+
+// WATCHED_CHANGE / ONLINE_RANK_COUNT / LIKE_INFO_V3_UPDATE 是周期推送的计数，
+// 合并成一份房间状态，只在数值变化时推送
+#[derive(Default, Clone, PartialEq)]
+struct RoomCounters {
+    online_rank: Option<u64>,
+    watched: Option<u64>,
+    watched_text: Option<String>,
+    likes: Option<u64>,
+}
+
+impl RoomCounters {
+    fn update(&mut self, next: RoomCounters, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+        if next == *self {
+            return None;
+        }
+        *self = next;
+        let mut parts = Vec::new();
+        if let Some(count) = self.online_rank {
+            parts.push(format!("高能用户 {}", count));
+        }
+        if let Some(text) = self
+            .watched_text
+            .clone()
+            .or_else(|| self.watched.map(|num| format!("{} 人看过", num)))
+        {
+            parts.push(text);
+        }
+        if let Some(likes) = self.likes {
+            parts.push(format!("{} 人点赞", likes));
+        }
+        Some(sink.event(
+            DanmakuEventKind::RoomStats {
+                online: self.online_rank,
+                total_viewers: self.watched,
+                likes: self.likes,
+                display: self.watched_text.clone(),
+            },
+            DanmakuUser::default(),
+            parts.join("，"),
+        ))
+    }
+}
+
+// 将 B 站消息转换为统一弹幕事件；不需要展示的消息返回 None
+fn to_danmaku_event(
+    sink: &DanmakuSink,
+    counters: &mut RoomCounters,
+    msg: BiliMessage,
+) -> Option<DanmakuEvent> {
+    let event = match msg {
+        BiliMessage::Danmu {
+            user,
+            text,
+            user_level,
+            color,
+            emoticon,
+            ..
+        } => {
+            let user = DanmakuUser {
+                user_level,
+                ..to_danmaku_user(user)
+            };
+            // 白色为默认颜色，不单独标注
+            let color = (color != 0 && color != 0xFF_FF_FF).then(|| rgb_to_hex(color));
+            sink.event(DanmakuEventKind::Chat, user, text)
+                .with_color(color)
+                .with_emoticon(emoticon.map(|e| e.url))
+        }
+        BiliMessage::Gift {
+            user,
+            gift_id,
+            gift_name,
+            num,
+            price,
+            coin_type,
+        } => {
+            let content = format!("送出 {} x{}", gift_name, num);
+            // 银瓜子礼物不计入价值
+            let gift_price =
+                (coin_type == "gold" && price > 0).then(|| price as f64 / GOLD_PER_CNY);
+            sink.event(
+                DanmakuEventKind::Gift {
+                    gift_id: Some(gift_id.to_string()),
+                    gift_name,
+                    gift_count: num,
+                    gift_price,
+                    combo_count: None,
+                },
+                to_danmaku_user(user),
+                content,
+            )
+        }
+        BiliMessage::SuperChat {
+            user,
+            message,
+            price,
+            duration,
+            background_color,
+        } => sink
+            .event(
+                DanmakuEventKind::SuperChat {
+                    price: price as f64,
+                    duration,
+                },
+                to_danmaku_user(user),
+                message,
+            )
+            .with_color(background_color),
+        BiliMessage::GuardBuy {
+            user,
+            guard_level,
+            gift_name,
+            num,
+            price,
+        } => {
+            let name = guard_name(guard_level);
+            let content = format!("开通了 {} x{}", name, num);
+            sink.event(
+                DanmakuEventKind::GuardBuy {
+                    guard_level,
+                    guard_name: if gift_name.is_empty() {
+                        name.to_string()
+                    } else {
+                        gift_name
+                    },
+                    count: num,
+                    price: price as f64 / GOLD_PER_CNY,
+                },
+                to_danmaku_user(user),
+                content,
+            )
+        }
+        BiliMessage::Interact { user, msg_type } => {
+            let user = to_danmaku_user(user);
+            match msg_type {
+                1 => {
+                    let content = format!("{} 进入了直播间", user.nickname);
+                    sink.event(DanmakuEventKind::Enter, user, content)
+                }
+                2 | 4 | 5 => {
+                    let content = format!("{} 关注了主播", user.nickname);
+                    sink.event(DanmakuEventKind::Follow, user, content)
+                }
+                _ => return None,
+            }
+        }
+        BiliMessage::WatchedChange { num, text } => {
+            let next = RoomCounters {
+                watched: Some(num),
+                watched_text: Some(text).filter(|t| !t.is_empty()),
+                ..counters.clone()
+            };
+            return counters.update(next, sink);
+        }
+        BiliMessage::OnlineRankCount { count } => {
+            let next = RoomCounters {
+                online_rank: Some(count),
+                ..counters.clone()
+            };
+            return counters.update(next, sink);
+        }
+        BiliMessage::LikeInfoUpdate { click_count } => {
+            let next = RoomCounters {
+                likes: Some(click_count),
+                ..counters.clone()
+            };
+            return counters.update(next, sink);
+        }
+        BiliMessage::Unsupported { .. } => return None,
+    };
+    Some(event)
+}
 
 #[tauri::command]
 pub async fn start_bilibili_danmaku_listener(
     payload: crate::platforms::common::GetStreamUrlPayload,
     cookie: Option<String>,
+    channel: Option<Channel<DanmakuBatch>>,
+    batch: Option<BatchOptions>,
     app_handle: tauri::AppHandle,
-    state: tauri::State<'_, crate::platforms::common::BilibiliDanmakuState>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
     let room_id = payload.args.room_id_str.clone();
+    let delivery = DanmakuChannel::open(&app_handle, channel, batch);
 
-    // stop previous listener if exists
-    let previous_tx = {
-        let mut lock = state.inner().0.lock().unwrap();
-        lock.take()
-    };
-    if let Some(tx) = previous_tx {
-        if tx.send(()).await.is_err() {
-            eprintln!("[Bilibili Danmaku] 旧任务关闭失败，可能已退出。");
-        }
-    }
-
-    let (tx_shutdown, mut rx_shutdown) = tokio_mpsc::channel::<()>(1);
-    {
-        let mut lock = state.inner().0.lock().unwrap();
-        *lock = Some(tx_shutdown);
-    }
+    // 同一房间的旧监听会在注册时被停止
+    let (listener_id, rx_shutdown) = registry.register(SupportedPlatformRust::Bilibili, &room_id);
 
     let app_handle_clone = app_handle.clone();
-    let room_id_clone = room_id.clone();
-    let cookie_clone = cookie.clone();
+    tokio::spawn(async move {
+        let sink = DanmakuSink::new(
+            app_handle_clone.clone(),
+            SupportedPlatformRust::Bilibili,
+            &room_id,
+        )
+        .with_channel(delivery)
+        .with_listener_id(listener_id);
+        run_bilibili_listener(&app_handle_clone, &sink, cookie.as_deref(), rx_shutdown).await;
+        app_handle_clone.state::<DanmakuListenerRegistry>().finish(
+            SupportedPlatformRust::Bilibili,
+            &room_id,
+            listener_id,
+        );
+    });
 
-    // Use atomic flag to signal std::thread to stop
-    let stop_flag = Arc::new(AtomicBool::new(false));
-    let stop_flag_for_thread = stop_flag.clone();
+    Ok(())
+}
 
-    // Spawn std thread to run sync BiliLiveClient loop
-    std::thread::spawn(move || {
-        let mut client = match cookie_clone.as_ref() {
-            Some(c) => BiliLiveClient::new_with_cookie(c.as_str(), room_id_clone.as_str()),
-            None => BiliLiveClient::new_without_cookie(room_id_clone.as_str()),
-        };
-        client.send_auth();
+async fn run_bilibili_listener(
+    app_handle: &tauri::AppHandle,
+    sink: &DanmakuSink,
+    cookie: Option<&str>,
+    mut rx_shutdown: oneshot::Receiver<()>,
+) {
+    let room_id = sink.room_id();
+    let http = app_handle.state::<reqwest::Client>().inner().clone();
+    let mut backoff = Backoff::default();
 
-        loop {
-            if stop_flag_for_thread.load(Ordering::Relaxed) {
-                break;
-            }
-            if let Some(msg) = client.read_once() {
-                match msg {
-                    BiliMessage::Danmu { user, text } => {
-                        let _ = app_handle_clone.emit(
-                            "danmaku-message",
-                            crate::platforms::common::DanmakuFrontendPayload {
-                                room_id: room_id_clone.clone(),
-                                user,
-                                content: text,
-                                user_level: 0,
-                                fans_club_level: 0,
-                            },
-                        );
-                    }
-                    BiliMessage::Gift { user, gift } => {
-                        let _ = app_handle_clone.emit(
-                            "danmaku-message",
-                            crate::platforms::common::DanmakuFrontendPayload {
-                                room_id: room_id_clone.clone(),
-                                user,
-                                content: format!("[礼物] {}", gift),
-                                user_level: 0,
-                                fans_club_level: 0,
-                            },
-                        );
-                    }
-                    BiliMessage::Unsupported { .. } => {
-                        // ignore
-                    }
+    loop {
+        sink.status(DanmakuStatus::Connecting);
+        let started = Instant::now();
+        let result = tokio::select! {
+            _ = &mut rx_shutdown => break,
+            res = run_bilibili_session(sink, &http, cookie, room_id) => res,
+        };
+        if started.elapsed() >= STABLE_SESSION {
+            backoff.reset();
+        }
+        let err = result
+            .err()
+            .unwrap_or_else(|| "connection closed".to_string());
+        match backoff.next_delay() {
+            Some(delay) => {
+                eprintln!(
+                    "[Bilibili Danmaku] Room {} disconnected: {}. Reconnecting in {:?} (attempt {})",
+                    room_id,
+                    err,
+                    delay,
+                    backoff.attempt()
+                );
+                sink.status(DanmakuStatus::Reconnecting {
+                    attempt: backoff.attempt(),
+                });
+                if wait_or_shutdown(delay, &mut rx_shutdown).await {
+                    break;
                 }
             }
-            std::thread::sleep(std::time::Duration::from_millis(50));
+            None => {
+                eprintln!(
+                    "[Bilibili Danmaku] Room {} failed after {} attempts: {}",
+                    room_id,
+                    backoff.attempt(),
+                    err
+                );
+                sink.status(DanmakuStatus::Failed { reason: err });
+                return;
+            }
         }
-    });
-
-    // Spawn a tokio task to listen for shutdown and set stop flag
-    let stop_flag_for_task = stop_flag.clone();
-    tokio::spawn(async move {
-        let _ = rx_shutdown.recv().await; // wait for shutdown signal
-        stop_flag_for_task.store(true, Ordering::Relaxed);
-    });
+    }
+    sink.status(DanmakuStatus::Stopped);
+    println!("[Bilibili Danmaku] Listener for room {} stopped", room_id);
+}
 
-    Ok(())
+async fn run_bilibili_session(
+    sink: &DanmakuSink,
+    http: &reqwest::Client,
+    cookie: Option<&str>,
+    room_id: &str,
+) -> Result<(), String> {
+    let client = BiliLiveClient::connect(http, cookie, room_id).await?;
+    println!("[Bilibili Danmaku] Connected to room {}", room_id);
+    sink.status(DanmakuStatus::Connected);
+    // 每次连接重新开始计数
+    let mut counters = RoomCounters::default();
+    client
+        .run(|msg| {
+            if let Some(event) = to_danmaku_event(sink, &mut counters, msg) {
+                sink.emit(event);
+            }
+        })
+        .await
 }
 
+/// `room_id` 为空时停止所有 B 站房间的监听（兼容旧前端调用）
 #[tauri::command]
 pub async fn stop_bilibili_danmaku_listener(
-    state: tauri::State<'_, crate::platforms::common::BilibiliDanmakuState>,
+    room_id: Option<String>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
-    let previous_tx = {
-        let mut lock = state.inner().0.lock().unwrap();
-        lock.take()
-    };
-    if let Some(tx) = previous_tx {
-        match tx.send(()).await {
-            Ok(()) => Ok(()),
-            Err(_) => Err("停止Bilibili弹幕监听失败：接收方已关闭".to_string()),
+    match room_id {
+        Some(room_id) => {
+            registry.stop(SupportedPlatformRust::Bilibili, &room_id);
+        }
+        None => {
+            registry.stop_all(Some(SupportedPlatformRust::Bilibili));
         }
-    } else {
-        Ok(())
     }
+    Ok(())
 }
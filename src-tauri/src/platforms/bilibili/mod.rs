@@ -6,6 +6,9 @@ pub mod stream_url;
 pub mod streamer_info;
 // 新增模块声明
 pub mod auth;
+pub mod codec;
 pub mod models;
 pub mod search;
+pub mod send;
 pub mod websocket;
+pub mod platform;
@@ -1,6 +1,5 @@
 // src/models.rs
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -23,6 +22,7 @@ impl Default for DanmuServer {
 }
 
 #[derive(Copy, Clone, Debug)]
+#[allow(dead_code)]
 pub struct MsgHead {
     pub pack_len: u32,
     pub raw_header_size: u16,
@@ -42,21 +42,93 @@ pub struct AuthMessage {
 }
 
 impl AuthMessage {
-    pub fn from(map: &HashMap<String, String>) -> AuthMessage {
+    pub fn new(uid: u64, roomid: u64, key: String) -> AuthMessage {
         AuthMessage {
-            uid: map.get("uid").unwrap().parse::<u64>().unwrap(),
-            roomid: map.get("room_id").unwrap().parse::<u64>().unwrap(),
+            uid,
+            roomid,
             protover: 3,
             platform: "web".to_string(),
             type_: 2,
-            key: map.get("token").unwrap().to_string(),
+            key,
         }
     }
 }
 
+// 弹幕/礼物等消息中携带的用户信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BiliUser {
+    pub uid: u64,
+    pub name: String,
+    pub face: Option<String>,
+    pub medal_name: Option<String>,
+    pub medal_level: i32,
+}
+
+// 表情弹幕（info[0][13]），普通文字弹幕时为 None
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BiliEmoticon {
+    pub unique: String,
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BiliMessage {
-    Danmu { user: String, text: String },
-    Gift { user: String, gift: String },
-    Unsupported { cmd: String },
+    Danmu {
+        user: BiliUser,
+        text: String,
+        /// 用户等级（UL）
+        user_level: i64,
+        /// 0xRRGGBB
+        color: u32,
+        /// 1 滚动 / 4 底部 / 5 顶部
+        mode: u8,
+        emoticon: Option<BiliEmoticon>,
+    },
+    Gift {
+        user: BiliUser,
+        gift_id: u64,
+        gift_name: String,
+        num: u32,
+        /// 单价，金瓜子（1000 = 1 元）；银瓜子礼物为 0
+        price: u64,
+        coin_type: String,
+    },
+    SuperChat {
+        user: BiliUser,
+        message: String,
+        /// 元
+        price: u64,
+        /// 持续时间（秒）
+        duration: u64,
+        background_color: Option<String>,
+    },
+    GuardBuy {
+        user: BiliUser,
+        /// 1 总督 / 2 提督 / 3 舰长
+        guard_level: u8,
+        gift_name: String,
+        num: u32,
+        /// 金瓜子
+        price: u64,
+    },
+    /// INTERACT_WORD：msg_type 1 进场，2 关注，3 分享，4 特别关注，5 互相关注
+    Interact {
+        user: BiliUser,
+        msg_type: i64,
+    },
+    WatchedChange {
+        num: u64,
+        text: String,
+    },
+    OnlineRankCount {
+        count: u64,
+    },
+    LikeInfoUpdate {
+        click_count: u64,
+    },
+    Unsupported {
+        cmd: String,
+    },
 }
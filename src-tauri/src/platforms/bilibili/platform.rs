@@ -0,0 +1,211 @@
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::platforms::bilibili::live_list::fetch_bilibili_live_list;
+use crate::platforms::bilibili::search::search_bilibili_rooms;
+use crate::platforms::bilibili::state::BilibiliState;
+use crate::platforms::bilibili::stream_url::get_bilibili_live_stream_url_with_quality;
+use crate::platforms::bilibili::streamer_info::fetch_bilibili_streamer_info;
+use crate::platforms::common::http_client::HttpClient;
+use crate::platforms::common::live_platform::LivePlatform;
+use crate::platforms::common::types::GetStreamUrlArgs;
+use crate::platforms::common::types_rust::{
+    CommonLiveListRust, CommonPlatformCategoryRust, CommonRoomInfoRust, CommonStreamerRust,
+    SupportedPlatformRust,
+};
+use crate::platforms::common::{FollowHttpClient, GetStreamUrlPayload, LiveStreamInfo};
+use crate::proxy::StreamProxy;
+
+const AREA_LIST_URL: &str =
+    "https://api.live.bilibili.com/room/v1/Area/getList?need_entrance=1&parent_id=0";
+
+fn payload_for(room_id: &str) -> GetStreamUrlPayload {
+    GetStreamUrlPayload {
+        args: GetStreamUrlArgs {
+            room_id_str: room_id.to_string(),
+        },
+    }
+}
+
+fn value_to_string(v: Option<&Value>) -> String {
+    match v {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+pub struct BilibiliPlatform {
+    app_handle: AppHandle,
+    cookie: Option<String>,
+}
+
+impl BilibiliPlatform {
+    pub fn new(app_handle: AppHandle, cookie: Option<String>) -> Self {
+        Self { app_handle, cookie }
+    }
+}
+
+impl LivePlatform for BilibiliPlatform {
+    fn platform(&self) -> SupportedPlatformRust {
+        SupportedPlatformRust::Bilibili
+    }
+
+    async fn room_info(&self, room_id: &str) -> Result<CommonRoomInfoRust, String> {
+        let follow_http = self.app_handle.state::<FollowHttpClient>();
+        let info =
+            fetch_bilibili_streamer_info(payload_for(room_id), self.cookie.clone(), follow_http)
+                .await?;
+        if let Some(err) = info
+            .error_message
+            .as_ref()
+            .filter(|_| info.status.is_none())
+        {
+            return Err(err.clone());
+        }
+        Ok(CommonRoomInfoRust {
+            platform: self.platform(),
+            room_id: room_id.to_string(),
+            title: info.title,
+            anchor_name: info.anchor_name,
+            avatar: info.avatar,
+            // live_status: 0 未开播 / 1 直播中 / 2 轮播
+            is_live: info.status == Some(1),
+        })
+    }
+
+    async fn stream_url(
+        &self,
+        room_id: &str,
+        quality: &str,
+        _line: Option<&str>,
+    ) -> Result<LiveStreamInfo, String> {
+        get_bilibili_live_stream_url_with_quality(
+            self.app_handle.state::<StreamProxy>(),
+            payload_for(room_id),
+            quality.to_string(),
+            self.cookie.clone(),
+        )
+        .await
+    }
+
+    async fn search(&self, keyword: &str, page: u32) -> Result<Vec<CommonStreamerRust>, String> {
+        let items =
+            search_bilibili_rooms(keyword.to_string(), Some(page), self.cookie.clone()).await?;
+        Ok(items
+            .into_iter()
+            .map(|item| CommonStreamerRust {
+                room_id: item.room_id,
+                title: item.title,
+                nickname: item.anchor,
+                avatar: item.avatar,
+                room_cover: item.cover,
+                viewer_count_str: item.watching,
+                platform: SupportedPlatformRust::Bilibili,
+                is_live: Some(item.is_live),
+            })
+            .collect())
+    }
+
+    async fn categories(&self) -> Result<Vec<CommonPlatformCategoryRust>, String> {
+        let client = HttpClient::new_direct_connection()?;
+        let json: Value = client
+            .get_json(AREA_LIST_URL)
+            .await
+            .map_err(|e| format!("Failed to fetch Bilibili areas: {}", e))?;
+        if json.get("code").and_then(|c| c.as_i64()) != Some(0) {
+            return Err(format!(
+                "Bilibili area API error: {}",
+                value_to_string(json.get("message"))
+            ));
+        }
+
+        let mut categories = Vec::new();
+        let parents = json
+            .get("data")
+            .and_then(|d| d.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for parent in parents {
+            let parent_id = value_to_string(parent.get("id"));
+            categories.push(CommonPlatformCategoryRust {
+                id: parent_id.clone(),
+                name: value_to_string(parent.get("name")),
+                platform: self.platform(),
+                icon_url: None,
+                parent_id: None,
+            });
+            if let Some(children) = parent.get("list").and_then(|l| l.as_array()) {
+                for child in children {
+                    let pic = value_to_string(child.get("pic"));
+                    categories.push(CommonPlatformCategoryRust {
+                        id: value_to_string(child.get("id")),
+                        name: value_to_string(child.get("name")),
+                        platform: self.platform(),
+                        icon_url: if pic.is_empty() { None } else { Some(pic) },
+                        parent_id: Some(parent_id.clone()),
+                    });
+                }
+            }
+        }
+        Ok(categories)
+    }
+
+    async fn live_list(
+        &self,
+        category_id: &str,
+        parent_id: Option<&str>,
+        page: u32,
+    ) -> Result<CommonLiveListRust, String> {
+        let parent_area_id = parent_id
+            .ok_or_else(|| "Bilibili live list requires parent_id (parent_area_id)".to_string())?;
+        let text = fetch_bilibili_live_list(
+            category_id.to_string(),
+            parent_area_id.to_string(),
+            page,
+            self.app_handle.state::<BilibiliState>(),
+        )
+        .await?;
+        let json: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse Bilibili live list: {}", e))?;
+        if json.get("code").and_then(|c| c.as_i64()) != Some(0) {
+            return Err(format!(
+                "Bilibili live list API error: {}",
+                value_to_string(json.get("message"))
+            ));
+        }
+
+        let data = json.get("data");
+        let list = data
+            .and_then(|d| d.get("list"))
+            .and_then(|l| l.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let has_more = data
+            .and_then(|d| d.get("has_more"))
+            .and_then(|v| v.as_i64())
+            .map(|v| v == 1)
+            .unwrap_or(!list.is_empty());
+
+        let rooms = list
+            .iter()
+            .map(|item| CommonStreamerRust {
+                room_id: value_to_string(item.get("roomid")),
+                title: value_to_string(item.get("title")),
+                nickname: value_to_string(item.get("uname")),
+                avatar: value_to_string(item.get("face")),
+                room_cover: value_to_string(item.get("cover")),
+                viewer_count_str: value_to_string(
+                    item.get("watched_show").and_then(|w| w.get("num")),
+                ),
+                platform: SupportedPlatformRust::Bilibili,
+                is_live: Some(true),
+            })
+            .collect();
+        Ok(CommonLiveListRust {
+            rooms,
+            has_more,
+            page,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+// 以登录用户身份向 B 站直播间发送弹幕
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::auth::USER_AGENT;
+use super::cookie::get_bilibili_cookie;
+
+const SEND_URL: &str = "https://api.live.bilibili.com/msg/send";
+const DEFAULT_COLOR: u32 = 0xFF_FF_FF;
+// 1 滚动 / 4 底部 / 5 顶部
+const DEFAULT_MODE: u8 = 1;
+
+/// 发送失败的原因，序列化为 `{ "kind": "too_frequent", "message": ... }` 供前端区分提示
+#[derive(Debug, thiserror::Error, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum SendDanmakuError {
+    #[error("未登录或登录已失效")]
+    NotLoggedIn,
+    #[error("发送过于频繁")]
+    TooFrequent,
+    #[error("已被禁言: {0}")]
+    Banned(String),
+    /// 命中平台或主播设置的屏蔽词，弹幕不会显示
+    #[error("弹幕被屏蔽")]
+    Filtered,
+    #[error("弹幕内容无效: {0}")]
+    InvalidMessage(String),
+    #[error("B站返回错误 {code}: {message}")]
+    Api { code: i64, message: String },
+    #[error("请求失败: {0}")]
+    Network(String),
+}
+
+fn cookie_value<'a>(cookie: &'a str, name: &str) -> Option<&'a str> {
+    cookie
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v)
+        .filter(|v| !v.is_empty())
+}
+
+fn map_response(body: &Value) -> Result<(), SendDanmakuError> {
+    let code = body["code"].as_i64().unwrap_or(-1);
+    let message = body["message"]
+        .as_str()
+        .or_else(|| body["msg"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    match code {
+        // 命中屏蔽词时 code 仍为 0，message 为 "f"（全站）或 "k"（主播屏蔽词）
+        0 if message == "f" || message == "k" => Err(SendDanmakuError::Filtered),
+        0 => Ok(()),
+        -101 | -111 => Err(SendDanmakuError::NotLoggedIn),
+        10030 | 10031 => Err(SendDanmakuError::TooFrequent),
+        -403 | 1003 | 10024 => Err(SendDanmakuError::Banned(message)),
+        -400 | 1003212 => Err(SendDanmakuError::InvalidMessage(message)),
+        code => Err(SendDanmakuError::Api { code, message }),
+    }
+}
+
+/// 发送弹幕；未传 cookie 时从已登录的 webview 中读取
+#[tauri::command]
+pub async fn send_bilibili_danmaku(
+    room_id: String,
+    text: String,
+    color: Option<u32>,
+    mode: Option<u8>,
+    cookie: Option<String>,
+    app_handle: AppHandle,
+    client: tauri::State<'_, reqwest::Client>,
+) -> Result<(), SendDanmakuError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(SendDanmakuError::InvalidMessage("内容为空".to_string()));
+    }
+    let cookie = match cookie.filter(|c| !c.is_empty()) {
+        Some(cookie) => cookie,
+        None => get_bilibili_cookie(app_handle, None, None)
+            .await
+            .map_err(SendDanmakuError::Network)?
+            .cookie
+            .ok_or(SendDanmakuError::NotLoggedIn)?,
+    };
+    if cookie_value(&cookie, "SESSDATA").is_none() {
+        return Err(SendDanmakuError::NotLoggedIn);
+    }
+    let csrf = cookie_value(&cookie, "bili_jct").ok_or(SendDanmakuError::NotLoggedIn)?;
+
+    let color = (color.unwrap_or(DEFAULT_COLOR) & 0xFF_FF_FF).to_string();
+    let mode = mode.unwrap_or(DEFAULT_MODE).to_string();
+    let rnd = chrono::Utc::now().timestamp().to_string();
+    let form = [
+        ("bubble", "0"),
+        ("msg", text),
+        ("color", color.as_str()),
+        ("mode", mode.as_str()),
+        ("fontsize", "25"),
+        ("rnd", rnd.as_str()),
+        ("roomid", room_id.as_str()),
+        ("csrf", csrf),
+        ("csrf_token", csrf),
+    ];
+
+    let body: Value = client
+        .post(SEND_URL)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .header(reqwest::header::COOKIE, cookie.as_str())
+        .header("Origin", "https://live.bilibili.com")
+        .header("Referer", format!("https://live.bilibili.com/{}", room_id))
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| SendDanmakuError::Network(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| SendDanmakuError::Network(e.to_string()))?;
+
+    let result = map_response(&body);
+    match &result {
+        Ok(()) => println!("[Bilibili Danmaku] Sent danmaku to room {}", room_id),
+        Err(e) => eprintln!(
+            "[Bilibili Danmaku] Failed to send danmaku to room {}: {}",
+            room_id, e
+        ),
+    }
+    result
+}
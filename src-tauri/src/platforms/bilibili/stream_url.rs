@@ -1,16 +1,14 @@
 use reqwest::header::{HeaderMap, HeaderValue, COOKIE, REFERER, USER_AGENT};
 use serde_json::Value;
-use tauri::{command, AppHandle, State};
+use tauri::{command, State};
 
 use crate::platforms::common::types::StreamVariant;
-use crate::proxy::{start_proxy, ProxyServerHandle};
-use crate::StreamUrlStore;
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+use crate::proxy::StreamProxy;
 
 #[command]
 pub async fn get_bilibili_live_stream_url_with_quality(
-    app_handle: AppHandle,
-    stream_url_store: State<'_, StreamUrlStore>,
-    proxy_server_handle: State<'_, ProxyServerHandle>,
+    stream_proxy: State<'_, StreamProxy>,
     payload: crate::platforms::common::GetStreamUrlPayload,
     quality: String,
     cookie: Option<String>,
@@ -28,6 +26,7 @@ pub async fn get_bilibili_live_stream_url_with_quality(
             available_streams: None,
             normalized_room_id: None,
             web_rid: None,
+            proxy_session_id: None,
         });
     }
 
@@ -259,6 +258,7 @@ pub async fn get_bilibili_live_stream_url_with_quality(
             available_streams: None,
             normalized_room_id: None,
             web_rid: None,
+            proxy_session_id: None,
         });
     }
 
@@ -482,72 +482,46 @@ pub async fn get_bilibili_live_stream_url_with_quality(
                 available_streams: Some(variants_for_response),
                 normalized_room_id: None,
                 web_rid: None,
+                proxy_session_id: None,
             });
         }
     };
 
-    match selected_stream {
-        SelectedStream::Flv(real_url) => {
-            // FLV：写入到 Store 并启动代理
-            let proxied_url = {
-                {
-                    let mut current_url_in_store = stream_url_store.url.lock().unwrap();
-                    *current_url_in_store = real_url.clone();
-                }
-                match start_proxy(app_handle, proxy_server_handle, stream_url_store).await {
-                    Ok(proxy) => Some(proxy),
-                    Err(e) => {
-                        eprintln!("[Bilibili] Failed to start proxy: {}", e);
-                        None
-                    }
-                }
-            };
-
-            let final_error_message = if proxied_url.is_none() {
-                Some("代理启动失败".to_string())
-            } else {
-                None
-            };
-
-            Ok(crate::platforms::common::LiveStreamInfo {
-                title: init_json["data"]["title"].as_str().map(|s| s.to_string()),
-                anchor_name: init_json["data"]["uname"].as_str().map(|s| s.to_string()),
-                avatar: None,
-                stream_url: proxied_url,
-                status: Some(if final_error_message.is_some() { 2 } else { 1 }),
-                error_message: final_error_message,
-                upstream_url: Some(real_url),
-                available_streams: Some(variants_for_response.clone()),
-                normalized_room_id: None,
-                web_rid: None,
-            })
+    // FLV 与 HLS 都经由本地代理，带上 B 站要求的 UA 和 Referer；
+    // 替换该房间之前的代理会话，其它房间的代理不受影响
+    let (SelectedStream::Flv(real_url) | SelectedStream::Hls(real_url)) = selected_stream;
+    let session = match stream_proxy
+        .replace_room(SupportedPlatformRust::Bilibili, &room_id, &real_url)
+        .await
+    {
+        Ok(session) => Some(session),
+        Err(e) => {
+            eprintln!("[Bilibili] Failed to start proxy: {}", e);
+            None
         }
-        SelectedStream::Hls(real_url) => {
-            // HLS：无需本地代理，若存在旧的 FLV 代理则关闭并清空存储
-            {
-                let handle_to_stop = { proxy_server_handle.0.lock().unwrap().take() };
-                if let Some(handle) = handle_to_stop {
-                    handle.stop(false).await;
-                    eprintln!("[Bilibili] Stopped existing FLV proxy before using HLS stream");
-                }
-            }
-            {
-                let mut current_url_in_store = stream_url_store.url.lock().unwrap();
-                *current_url_in_store = String::new();
-            }
+    };
+    let (proxied_url, proxy_session_id) = match session {
+        Some(session) => (Some(session.url), Some(session.session_id)),
+        None => (None, None),
+    };
 
-            Ok(crate::platforms::common::LiveStreamInfo {
-                title: init_json["data"]["title"].as_str().map(|s| s.to_string()),
-                anchor_name: init_json["data"]["uname"].as_str().map(|s| s.to_string()),
-                avatar: None,
-                stream_url: Some(real_url.clone()),
-                status: Some(1),
-                error_message: None,
-                upstream_url: Some(real_url),
-                available_streams: Some(variants_for_response),
-                normalized_room_id: None,
-                web_rid: None,
-            })
-        }
-    }
+    let final_error_message = if proxied_url.is_none() {
+        Some("代理启动失败".to_string())
+    } else {
+        None
+    };
+
+    Ok(crate::platforms::common::LiveStreamInfo {
+        title: init_json["data"]["title"].as_str().map(|s| s.to_string()),
+        anchor_name: init_json["data"]["uname"].as_str().map(|s| s.to_string()),
+        avatar: None,
+        stream_url: proxied_url,
+        status: Some(if final_error_message.is_some() { 2 } else { 1 }),
+        error_message: final_error_message,
+        upstream_url: Some(real_url),
+        available_streams: Some(variants_for_response),
+        normalized_room_id: None,
+        web_rid: None,
+        proxy_session_id,
+    })
 }
@@ -118,6 +118,7 @@ pub async fn fetch_bilibili_streamer_info(
             available_streams: None,
             normalized_room_id: None,
             web_rid: None,
+            proxy_session_id: None,
         });
     }
 
@@ -173,6 +174,7 @@ pub async fn fetch_bilibili_streamer_info(
             available_streams: None,
             normalized_room_id: None,
             web_rid: None,
+            proxy_session_id: None,
         });
     }
     let j: Value = serde_json::from_str(&text)
@@ -198,5 +200,6 @@ pub async fn fetch_bilibili_streamer_info(
         available_streams: None,
         normalized_room_id: None,
         web_rid: None,
+        proxy_session_id: None,
     })
 }
@@ -1,15 +1,15 @@
 // src/websocket.rs
-use native_tls::TlsStream;
+use futures_util::{SinkExt, StreamExt};
 use serde_json::Value;
-use std::collections::VecDeque;
-use std::net::TcpStream;
 use std::sync::OnceLock;
-use std::time::{Duration, Instant};
-use tungstenite::{client, Message, WebSocket};
-use url::Url;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
-use super::auth::{init_server_no_cookie, init_server_with_cookie};
-use super::models::{BiliMessage, DanmuServer, MsgHead};
+use super::auth::init_server;
+use super::codec::{decode_frame, encode_packet, Operation, Packet};
+use super::models::{BiliEmoticon, BiliMessage, BiliUser, DanmuServer};
 
 static DEBUG_FLAG: OnceLock<bool> = OnceLock::new();
 
@@ -29,242 +29,119 @@ macro_rules! ws_debug {
     }
 }
 
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct BiliLiveClient {
-    ws: WebSocket<TlsStream<TcpStream>>,
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
     auth_msg: String,
-    // Keep server host list for reconnection
-    host_list: Value,
-    // Heartbeat scheduling
-    last_heartbeat: Instant,
-    heartbeat_interval: Duration,
-    // Pending messages parsed from current/previous frames
-    pending: VecDeque<BiliMessage>,
 }
 
 impl BiliLiveClient {
-    pub fn new_with_cookie(cookies: &str, room_id: &str) -> Self {
-        let (v, auth) = init_server_with_cookie(cookies, room_id);
+    /// 获取弹幕服务器信息并建立连接；`cookies` 为 None 时以游客身份（uid=0）连接
+    pub async fn connect(
+        http: &reqwest::Client,
+        cookies: Option<&str>,
+        room_id: &str,
+    ) -> Result<Self, String> {
+        let (v, auth) = init_server(http, cookies, room_id).await?;
         ws_debug!("[websocket] server_info host_list: {:?}", v["host_list"]);
-        let ws = connect(v["host_list"].clone());
-        ws_debug!("[websocket] connected via cookie for room {}", room_id);
-        BiliLiveClient {
-            ws,
-            auth_msg: serde_json::to_string(&auth).unwrap(),
-            host_list: v["host_list"].clone(),
-            last_heartbeat: Instant::now(),
-            heartbeat_interval: Duration::from_secs(30),
-            pending: VecDeque::new(),
-        }
+        let auth_msg = serde_json::to_string(&auth)
+            .map_err(|e| format!("Failed to serialize auth message: {}", e))?;
+        let ws = connect(&v["host_list"]).await?;
+        ws_debug!(
+            "[websocket] connected for room {} (cookie={})",
+            room_id,
+            cookies.is_some()
+        );
+        Ok(BiliLiveClient { ws, auth_msg })
     }
 
-    pub fn new_without_cookie(room_id: &str) -> Self {
-        let (v, auth) = init_server_no_cookie(room_id);
-        ws_debug!("[websocket] server_info host_list: {:?}", v["host_list"]);
-        let ws = connect(v["host_list"].clone());
-        ws_debug!("[websocket] connected without cookie for room {}", room_id);
-        BiliLiveClient {
-            ws,
-            auth_msg: serde_json::to_string(&auth).unwrap(),
-            host_list: v["host_list"].clone(),
-            last_heartbeat: Instant::now(),
-            heartbeat_interval: Duration::from_secs(30),
-            pending: VecDeque::new(),
-        }
-    }
+    /// 发送认证包并持续读取消息，直到连接断开或出错。
+    /// 停止监听时由调用方在 `select!` 中直接丢弃该 future。
+    pub async fn run<F>(self, mut on_message: F) -> Result<(), String>
+    where
+        F: FnMut(BiliMessage),
+    {
+        let (mut write, mut read) = self.ws.split();
 
-    pub fn send_auth(&mut self) {
-        let pkt = make_packet(self.auth_msg.as_str(), Operation::AUTH);
+        let pkt = encode_packet(Operation::Auth, self.auth_msg.as_bytes());
         ws_debug!("[websocket] sending auth packet, len={}", pkt.len());
-        let _ = self.ws.send(Message::Binary(pkt));
-    }
+        write
+            .send(Message::Binary(pkt))
+            .await
+            .map_err(|e| format!("Failed to send auth packet: {}", e))?;
 
-    pub fn send_heart_beat(&mut self) {
-        let pkt = make_packet("{}", Operation::HEARTBEAT);
-        ws_debug!("[websocket] sending heartbeat, len={}", pkt.len());
-        let _ = self.ws.send(Message::Binary(pkt));
-        // update heartbeat timestamp
-        self.last_heartbeat = Instant::now();
-    }
-
-    // Periodically send heartbeat to keep the connection alive
-    fn maybe_send_heartbeat(&mut self) {
-        if self.last_heartbeat.elapsed() >= self.heartbeat_interval {
-            ws_debug!("[websocket] periodic heartbeat due");
-            self.send_heart_beat();
-        }
-    }
-
-    // Try to reconnect using the cached host list, and re-authenticate
-    fn reconnect(&mut self) {
-        for attempt in 1..=2 {
-            ws_debug!("[websocket] attempting reconnect (attempt {attempt}/2)...");
-            match std::panic::catch_unwind({
-                let host_list = self.host_list.clone();
-                move || connect(host_list)
-            }) {
-                Ok(new_ws) => {
-                    self.ws = new_ws;
-                    ws_debug!(
-                        "[websocket] reconnect successful on attempt {attempt}, resending auth"
-                    );
-                    self.send_auth();
-                    return;
-                }
-                Err(_) => {
-                    ws_debug!("[websocket] reconnect attempt {attempt} failed");
-                }
-            }
-        }
-        ws_debug!("[websocket] reconnect failed after 2 attempts; will retry on next read cycle");
-    }
-
-    // Parse one frame and collect all messages into pending queue
-    pub fn parse_ws_message(&mut self, resv: Vec<u8>) -> Option<BiliMessage> {
-        ws_debug!("[websocket] parse_ws_message: total_len={}", resv.len());
-        let mut offset = 0;
-        let header = &resv[0..16];
-        let mut head_1 = get_msg_header(header);
-        ws_debug!(
-            "[websocket] header op={} ver={} pack_len={} seq={} hdr_size={}",
-            head_1.operation,
-            head_1.ver,
-            head_1.pack_len,
-            head_1.seq_id,
-            head_1.raw_header_size
-        );
-        if head_1.operation == 5 || head_1.operation == 8 {
+        let heartbeat = async {
+            let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
             loop {
-                let body: &[u8] = &resv[offset + 16..offset + (head_1.pack_len as usize)];
-                ws_debug!(
-                    "[websocket] chunk offset={} pack_len={} ver={} op={}",
-                    offset,
-                    head_1.pack_len,
-                    head_1.ver,
-                    head_1.operation
-                );
-                if let Some(msg) = self.parse_business_message(head_1, body) {
-                    // push and continue to collect more messages
-                    self.pending.push_back(msg);
+                ticker.tick().await;
+                let pkt = encode_packet(Operation::Heartbeat, b"{}");
+                ws_debug!("[websocket] sending heartbeat, len={}", pkt.len());
+                if let Err(e) = write.send(Message::Binary(pkt)).await {
+                    return Err::<(), String>(format!("Failed to send heartbeat: {}", e));
                 }
-                offset += head_1.pack_len as usize;
-                if offset >= resv.len() {
-                    break;
-                }
-                let temp_head = &resv[offset..(offset + 16)];
-                head_1 = get_msg_header(temp_head);
             }
-        } else if head_1.operation == 3 {
-            let mut body: [u8; 4] = [0, 0, 0, 0];
-            body[0] = resv[16];
-            body[1] = resv[17];
-            body[2] = resv[18];
-            body[3] = resv[19];
-            let _popularity = i32::from_be_bytes(body);
-            ws_debug!(
-                "[websocket] popularity message op=3; popularity={}",
-                _popularity
-            );
-        } else {
-            ws_debug!("[websocket] unknown op={}, ignoring", head_1.operation);
-        }
-        None
-    }
+        };
 
-    fn parse_business_message(&mut self, h: MsgHead, b: &[u8]) -> Option<BiliMessage> {
-        ws_debug!(
-            "[websocket] parse_business_message op={} ver={} body_len={} ",
-            h.operation,
-            h.ver,
-            b.len()
-        );
-        if h.operation == 5 {
-            if h.ver == 3 {
-                let res: Vec<u8> = match decompress(b) {
-                    Ok(r) => r,
-                    Err(e) => {
-                        ws_debug!("[websocket] decompress error: {:?}", e);
-                        return None;
+        let receive = async {
+            while let Some(msg) = read.next().await {
+                match msg.map_err(|e| format!("WebSocket read error: {}", e))? {
+                    Message::Binary(data) => {
+                        ws_debug!("[websocket] read frame bytes={} ", data.len());
+                        // 坏包只记录日志，同一帧里的其它包照常处理
+                        let frame = decode_frame(&data);
+                        for e in &frame.errors {
+                            eprintln!(
+                                "[Bilibili Danmaku] Skipped bad packet in frame ({} bytes): {}",
+                                data.len(),
+                                e
+                            );
+                        }
+                        for packet in frame.packets {
+                            match packet {
+                                Packet::Message(json) => {
+                                    ws_debug!(
+                                        "[websocket] business cmd={}",
+                                        json["cmd"].as_str().unwrap_or("<unknown>")
+                                    );
+                                    if let Some(m) = handle(json) {
+                                        on_message(m);
+                                    }
+                                }
+                                Packet::AuthReply(reply) => {
+                                    ws_debug!("[websocket] auth reply: {}", reply);
+                                    let code = reply["code"].as_i64().unwrap_or(0);
+                                    if code != 0 {
+                                        return Err(format!("Auth rejected with code {}", code));
+                                    }
+                                }
+                                Packet::Popularity(popularity) => {
+                                    ws_debug!("[websocket] popularity={}", popularity);
+                                }
+                                Packet::Unknown { operation } => {
+                                    ws_debug!("[websocket] unknown op={}, ignoring", operation);
+                                }
+                            }
+                        }
                     }
-                };
-                ws_debug!("[websocket] decompressed len={}", res.len());
-                return self.parse_ws_message(res);
-            } else if h.ver == 0 {
-                let s = match String::from_utf8(b.to_vec()) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        ws_debug!("[websocket] utf8 error: {:?}", e);
-                        return None;
+                    Message::Close(frame) => {
+                        ws_debug!("[websocket] server closed connection: {:?}", frame);
+                        break;
                     }
-                };
-                ws_debug!("[websocket] ver0 business json str len={}", s.len());
-                let res_json: Value = match serde_json::from_str(s.as_str()) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        ws_debug!("[websocket] json parse error: {:?}", e);
-                        return None;
-                    }
-                };
-                ws_debug!(
-                    "[websocket] business cmd={}",
-                    res_json["cmd"].as_str().unwrap_or("<unknown>")
-                );
-                if let Some(m) = handle(res_json) {
-                    // push into queue, but do not return immediately
-                    self.pending.push_back(m);
+                    _ => {}
                 }
-                None
-            } else {
-                ws_debug!("[websocket] unknown compression ver={}, skip", h.ver);
-                None
             }
-        } else if h.operation == 8 {
-            ws_debug!("[websocket] op=8 (auth reply), sending heartbeat");
-            self.send_heart_beat();
-            None
-        } else {
-            ws_debug!("[websocket] unsupported business op={}, skip", h.operation);
-            None
-        }
-    }
-
-    pub fn read_once(&mut self) -> Option<BiliMessage> {
-        // If we already have pending messages, deliver one immediately
-        if let Some(m) = self.pending.pop_front() {
-            return Some(m);
-        }
+            Err::<(), String>("WebSocket connection closed".to_string())
+        };
 
-        // ensure heartbeat keeps alive
-        self.maybe_send_heartbeat();
-
-        let readable = self.ws.can_read();
-        ws_debug!("[websocket] can_read={} ", readable);
-        if self.ws.can_read() {
-            let msg = self.ws.read();
-            match msg {
-                Ok(m) => {
-                    let res = m.into_data();
-                    ws_debug!("[websocket] read frame bytes={} ", res.len());
-                    if res.len() >= 16 {
-                        // parse and fill pending queue
-                        let _ = self.parse_ws_message(res);
-                        // return one
-                        return self.pending.pop_front();
-                    } else {
-                        ws_debug!("[websocket] frame too short (<16), ignore");
-                    }
-                }
-                Err(e) => {
-                    ws_debug!("[websocket] read error: {:?}", e);
-                    // try to reconnect on read error
-                    self.reconnect();
-                }
-            }
+        tokio::select! {
+            res = heartbeat => res,
+            res = receive => res,
         }
-        None
     }
 }
 
-pub fn gen_damu_list(list: &serde_json::Value) -> Vec<DanmuServer> {
+pub fn gen_damu_list(list: &Value) -> Vec<DanmuServer> {
     let mut res: Vec<DanmuServer> = Vec::new();
     if let Some(server_list) = list.as_array() {
         ws_debug!("[websocket] host_list size={}", server_list.len());
@@ -301,122 +178,136 @@ pub fn gen_damu_list(list: &serde_json::Value) -> Vec<DanmuServer> {
     res
 }
 
-fn find_server(vd: Vec<DanmuServer>) -> (String, String, String) {
-    let (host, wss_port) = (vd.get(0).unwrap().host.clone(), vd.get(0).unwrap().wss_port);
-    ws_debug!(
-        "[websocket] choose server host={} wss_port={}",
-        host,
-        wss_port
-    );
-    (
-        host.clone(),
-        format!("{}:{}", host.clone(), wss_port),
-        format!("wss://{}:{}/sub", host, wss_port),
-    )
+// 按 host_list 顺序尝试连接，返回第一个握手成功的连接
+async fn connect(host_list: &Value) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, String> {
+    let mut last_err = "No danmaku server available".to_string();
+    for server in gen_damu_list(host_list) {
+        let ws_url = format!("wss://{}:{}/sub", server.host, server.wss_port);
+        ws_debug!("[websocket] connecting ws {}", ws_url);
+        match connect_async(ws_url.as_str()).await {
+            Ok((socket, _resp)) => {
+                ws_debug!("[websocket] websocket handshake complete");
+                return Ok(socket);
+            }
+            Err(e) => {
+                ws_debug!("[websocket] connect {} failed: {}", ws_url, e);
+                last_err = format!("Failed to connect {}: {}", ws_url, e);
+            }
+        }
+    }
+    Err(last_err)
 }
 
-pub fn connect(v: Value) -> WebSocket<TlsStream<TcpStream>> {
-    let danmu_server = gen_damu_list(&v);
-    let (host, url, ws_url) = find_server(danmu_server);
-    ws_debug!("[websocket] connecting tcp {} and ws {}", url, ws_url);
-    let connector: native_tls::TlsConnector = native_tls::TlsConnector::new().unwrap();
-    let stream: TcpStream = TcpStream::connect(url).unwrap();
-    let stream: native_tls::TlsStream<TcpStream> =
-        connector.connect(host.as_str(), stream).unwrap();
-    let (socket, _resp) =
-        client(Url::parse(ws_url.as_str()).unwrap(), stream).expect("Can't connect");
-    ws_debug!("[websocket] websocket handshake complete");
-    socket
+fn json_u64(v: &Value) -> u64 {
+    match v {
+        Value::Number(n) => n.as_u64().unwrap_or(0),
+        Value::String(s) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
 }
 
-pub enum Operation {
-    AUTH,
-    HEARTBEAT,
+fn json_string(v: &Value) -> Option<String> {
+    v.as_str().filter(|s| !s.is_empty()).map(|s| s.to_string())
 }
 
-pub fn make_packet(body: &str, ops: Operation) -> Vec<u8> {
-    let json: Value = serde_json::from_str(body).unwrap();
-    let temp = json.to_string();
-    let body_content: &[u8] = temp.as_bytes();
-    let pack_len: [u8; 4] = ((16 + body.len()) as u32).to_be_bytes();
-    let raw_header_size: [u8; 2] = (16 as u16).to_be_bytes();
-    let ver: [u8; 2] = (1 as u16).to_be_bytes();
-    let operation: [u8; 4] = match ops {
-        Operation::AUTH => (7 as u32).to_be_bytes(),
-        Operation::HEARTBEAT => (2 as u32).to_be_bytes(),
-    };
-    let seq_id: [u8; 4] = (1 as u32).to_be_bytes();
-    let mut res = pack_len.to_vec();
-    res.append(&mut raw_header_size.to_vec());
-    res.append(&mut ver.to_vec());
-    res.append(&mut operation.to_vec());
-    res.append(&mut seq_id.to_vec());
-    res.append(&mut body_content.to_vec());
-    res
+// 从 data 中提取用户信息；不同 cmd 的昵称字段名不同（uname / username / user_info.uname）
+fn parse_user(data: &Value, name: &Value, face: &Value, medal: &Value) -> BiliUser {
+    BiliUser {
+        uid: json_u64(&data["uid"]),
+        name: name.as_str().unwrap_or("<unknown>").to_string(),
+        face: json_string(face),
+        medal_name: json_string(&medal["medal_name"]),
+        medal_level: medal["medal_level"].as_i64().unwrap_or(0) as i32,
+    }
 }
 
-pub fn get_msg_header(v_s: &[u8]) -> MsgHead {
-    let mut pack_len: [u8; 4] = [0; 4];
-    let mut raw_header_size: [u8; 2] = [0; 2];
-    let mut ver: [u8; 2] = [0; 2];
-    let mut operation: [u8; 4] = [0; 4];
-    let mut seq_id: [u8; 4] = [0; 4];
-    for (i, v) in v_s.iter().enumerate() {
-        if i < 4 {
-            pack_len[i] = *v;
-            continue;
-        }
-        if i < 6 {
-            raw_header_size[i - 4] = *v;
-            continue;
-        }
-        if i < 8 {
-            ver[i - 6] = *v;
-            continue;
-        }
-        if i < 12 {
-            operation[i - 8] = *v;
-            continue;
-        }
-        if i < 16 {
-            seq_id[i - 12] = *v;
-            continue;
-        }
-    }
-    MsgHead {
-        pack_len: u32::from_be_bytes(pack_len),
-        raw_header_size: u16::from_be_bytes(raw_header_size),
-        ver: u16::from_be_bytes(ver),
-        operation: u32::from_be_bytes(operation),
-        seq_id: u32::from_be_bytes(seq_id),
-    }
+// info[0][13]：表情弹幕为对象，普通弹幕为 "{}" 字符串
+fn parse_emoticon(v: &Value) -> Option<BiliEmoticon> {
+    let url = json_string(&v["url"])?;
+    Some(BiliEmoticon {
+        unique: v["emoticon_unique"].as_str().unwrap_or("").to_string(),
+        url,
+        width: json_u64(&v["width"]) as u32,
+        height: json_u64(&v["height"]) as u32,
+    })
 }
 
-pub fn decompress(body: &[u8]) -> std::io::Result<Vec<u8>> {
-    use brotlic::DecompressorReader;
-    use std::io::Read;
-    let mut decompressed_reader: DecompressorReader<&[u8]> = DecompressorReader::new(body);
-    let mut decoded_input = Vec::new();
-    let _ = decompressed_reader.read_to_end(&mut decoded_input)?;
-    Ok(decoded_input)
+// DANMU_MSG 的 info 数组：
+//   info[0] 弹幕属性 [_, mode, fontsize, color, ts, ..., emoticon(13), ...]
+//   info[1] 文本
+//   info[2] 用户 [uid, uname, ...]
+//   info[3] 粉丝牌 [level, name, anchor, roomid, ...]，未佩戴时为空数组
+//   info[4] 用户等级 [level, ...]
+fn parse_danmu(info: &Value) -> BiliMessage {
+    let attrs = &info[0];
+    let medal = &info[3];
+    BiliMessage::Danmu {
+        user: BiliUser {
+            uid: json_u64(&info[2][0]),
+            name: info[2][1].as_str().unwrap_or("<unknown>").to_string(),
+            face: None,
+            medal_name: json_string(&medal[1]),
+            medal_level: medal[0].as_i64().unwrap_or(0) as i32,
+        },
+        text: info[1].as_str().unwrap_or("").to_string(),
+        user_level: info[4][0].as_i64().unwrap_or(0),
+        color: json_u64(&attrs[3]) as u32,
+        mode: json_u64(&attrs[1]).max(1) as u8,
+        emoticon: parse_emoticon(&attrs[13]),
+    }
 }
 
 pub fn handle(json: Value) -> Option<BiliMessage> {
-    let category = json["cmd"].as_str().unwrap_or("");
+    // 部分弹幕的 cmd 带有后缀，例如 "DANMU_MSG:4:0:2:2:2:0"
+    let category = json["cmd"]
+        .as_str()
+        .unwrap_or("")
+        .split(':')
+        .next()
+        .unwrap_or("");
+    let data = &json["data"];
     match category {
-        "DANMU_MSG" => Some(BiliMessage::Danmu {
-            user: json["info"][2][1]
-                .as_str()
-                .unwrap_or("<unknown>")
-                .to_string(),
-            text: json["info"][1].as_str().unwrap_or("").to_string(),
-        }),
+        "DANMU_MSG" => Some(parse_danmu(&json["info"])),
         "SEND_GIFT" => Some(BiliMessage::Gift {
-            user: json["info"][2][1]
-                .as_str()
-                .unwrap_or("<unknown>")
-                .to_string(),
-            gift: json["info"][1].as_str().unwrap_or("").to_string(),
+            user: parse_user(data, &data["uname"], &data["face"], &data["medal_info"]),
+            gift_id: json_u64(&data["giftId"]),
+            gift_name: data["giftName"].as_str().unwrap_or("").to_string(),
+            num: json_u64(&data["num"]).max(1) as u32,
+            price: json_u64(&data["price"]),
+            coin_type: data["coin_type"].as_str().unwrap_or("gold").to_string(),
+        }),
+        "SUPER_CHAT_MESSAGE" => Some(BiliMessage::SuperChat {
+            user: parse_user(
+                data,
+                &data["user_info"]["uname"],
+                &data["user_info"]["face"],
+                &data["medal_info"],
+            ),
+            message: data["message"].as_str().unwrap_or("").to_string(),
+            price: json_u64(&data["price"]),
+            duration: json_u64(&data["time"]),
+            background_color: json_string(&data["background_bottom_color"]),
+        }),
+        "GUARD_BUY" => Some(BiliMessage::GuardBuy {
+            user: parse_user(data, &data["username"], &Value::Null, &Value::Null),
+            guard_level: json_u64(&data["guard_level"]) as u8,
+            gift_name: data["gift_name"].as_str().unwrap_or("").to_string(),
+            num: json_u64(&data["num"]).max(1) as u32,
+            price: json_u64(&data["price"]),
+        }),
+        "INTERACT_WORD" => Some(BiliMessage::Interact {
+            user: parse_user(data, &data["uname"], &Value::Null, &data["fans_medal"]),
+            msg_type: data["msg_type"].as_i64().unwrap_or(0),
+        }),
+        "WATCHED_CHANGE" => Some(BiliMessage::WatchedChange {
+            num: json_u64(&data["num"]),
+            text: data["text_large"].as_str().unwrap_or("").to_string(),
+        }),
+        "ONLINE_RANK_COUNT" => Some(BiliMessage::OnlineRankCount {
+            count: json_u64(&data["count"]),
+        }),
+        "LIKE_INFO_V3_UPDATE" => Some(BiliMessage::LikeInfoUpdate {
+            click_count: json_u64(&data["click_count"]),
         }),
         _ => Some(BiliMessage::Unsupported {
             cmd: category.to_string(),
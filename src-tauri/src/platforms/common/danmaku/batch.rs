@@ -0,0 +1,319 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use super::event::{DanmakuEvent, DanmakuEventKind, DANMAKU_EVENT};
+
+// 入站速率按该窗口统计
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// 批量推送参数，前端可按需覆盖
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BatchOptions {
+    /// 最长攒批时间
+    pub interval_ms: u64,
+    /// 攒够这么多条立即发送
+    pub max_batch: usize,
+    /// 每秒聊天弹幕超过该值时按比例抽样，0 表示不抽样
+    pub max_rate: u32,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            interval_ms: 100,
+            max_batch: 50,
+            max_rate: 60,
+        }
+    }
+}
+
+/// 通过 Channel 推送给前端的一批事件
+#[derive(Serialize, Clone, Debug)]
+pub struct DanmakuBatch {
+    pub events: Vec<DanmakuEvent>,
+    /// 自上一批以来因抽样丢弃的聊天弹幕数
+    pub sampled_out: u32,
+    /// 最近一秒的入站速率（条/秒）
+    pub rate: u32,
+}
+
+/// 监听任务持有的发送端；所有克隆都被丢弃后，后台任务发出最后一批并退出
+#[derive(Clone)]
+pub struct DanmakuChannel {
+    tx: mpsc::UnboundedSender<DanmakuEvent>,
+}
+
+impl DanmakuChannel {
+    /// Channel 失效后，未送达的事件改为逐条 emit 到 `danmaku-message`
+    pub fn spawn(
+        app_handle: AppHandle,
+        channel: Channel<DanmakuBatch>,
+        options: BatchOptions,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let deliver = move |batch: DanmakuBatch| channel.send(batch).map_err(|e| e.to_string());
+        let fallback = move |event: DanmakuEvent| {
+            if let Err(e) = app_handle.emit(DANMAKU_EVENT, event) {
+                eprintln!("[Danmaku Channel] Failed to emit fallback event: {}", e);
+            }
+        };
+        tokio::spawn(run_batcher(deliver, fallback, options, rx));
+        Self { tx }
+    }
+
+    /// 命令参数中带了 Channel 时启动批量推送
+    pub fn open(
+        app_handle: &AppHandle,
+        channel: Option<Channel<DanmakuBatch>>,
+        options: Option<BatchOptions>,
+    ) -> Option<Self> {
+        channel.map(|channel| Self::spawn(app_handle.clone(), channel, options.unwrap_or_default()))
+    }
+
+    /// 后台任务已退出（前端 Channel 失效）时原样交还事件，由调用方改用其它方式推送
+    pub fn send(&self, event: DanmakuEvent) -> Result<(), Box<DanmakuEvent>> {
+        self.tx.send(event).map_err(|e| Box::new(e.0))
+    }
+}
+
+// 按入站速率估计保留比例；只抽样聊天，礼物 / 醒目留言等事件总是保留
+struct Sampler {
+    max_rate: u32,
+    window_start: Instant,
+    window_count: u32,
+    rate: u32,
+}
+
+impl Sampler {
+    fn keep(&mut self, event: &DanmakuEvent) -> bool {
+        if !matches!(event.kind, DanmakuEventKind::Chat) {
+            return true;
+        }
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= RATE_WINDOW {
+            self.rate = self.window_count;
+            self.window_start = now;
+            self.window_count = 0;
+        }
+        self.window_count += 1;
+        // 当前窗口尚未结束时，用已过去的部分外推速率，突发流量能更快进入抽样
+        let elapsed = now.duration_since(self.window_start).as_secs_f64().max(0.1);
+        let current = (self.window_count as f64 / elapsed) as u32;
+        let rate = self.rate.max(current);
+        if self.max_rate == 0 || rate <= self.max_rate {
+            return true;
+        }
+        rand::thread_rng().gen_bool(self.max_rate as f64 / rate as f64)
+    }
+}
+
+async fn run_batcher(
+    mut deliver: impl FnMut(DanmakuBatch) -> Result<(), String>,
+    mut fallback: impl FnMut(DanmakuEvent),
+    options: BatchOptions,
+    mut rx: mpsc::UnboundedReceiver<DanmakuEvent>,
+) {
+    let max_batch = options.max_batch.max(1);
+    let mut ticker = tokio::time::interval(Duration::from_millis(options.interval_ms.max(10)));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // interval 的第一次 tick 立即完成，从现在起按完整间隔计时
+    ticker.reset();
+    let mut sampler = Sampler {
+        max_rate: options.max_rate,
+        window_start: Instant::now(),
+        window_count: 0,
+        rate: 0,
+    };
+    let mut events = Vec::with_capacity(max_batch);
+    let mut sampled_out = 0u32;
+
+    loop {
+        let flush = tokio::select! {
+            event = rx.recv() => match event {
+                Some(event) => {
+                    if sampler.keep(&event) {
+                        events.push(event);
+                    } else {
+                        sampled_out += 1;
+                    }
+                    events.len() >= max_batch
+                }
+                None => break,
+            },
+            _ = ticker.tick() => true,
+        };
+        if flush && (!events.is_empty() || sampled_out > 0) {
+            let batch = DanmakuBatch {
+                events: std::mem::replace(&mut events, Vec::with_capacity(max_batch)),
+                sampled_out: std::mem::take(&mut sampled_out),
+                rate: sampler.rate,
+            };
+            // Channel::send 会消耗这一批，失败时要把其中的事件交还给 fallback，只能先留一份
+            let pending = batch.events.clone();
+            if let Err(e) = deliver(batch) {
+                // 前端页面已关闭或刷新，改为逐条 emit：先补发这一批和已排队的事件，
+                // 关闭接收端后 DanmakuChannel::send 会失败，DanmakuSink 随之直接 emit
+                eprintln!("[Danmaku Channel] Failed to deliver batch: {}", e);
+                rx.close();
+                pending.into_iter().for_each(&mut fallback);
+                while let Some(event) = rx.recv().await {
+                    fallback(event);
+                }
+                return;
+            }
+            ticker.reset();
+        }
+    }
+
+    if !events.is_empty() {
+        let pending = events.clone();
+        let batch = DanmakuBatch {
+            events,
+            sampled_out,
+            rate: sampler.rate,
+        };
+        if deliver(batch).is_err() {
+            pending.into_iter().for_each(fallback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::platforms::common::danmaku::event::DanmakuUser;
+    use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+    fn chat(content: &str) -> DanmakuEvent {
+        DanmakuEvent::chat(
+            SupportedPlatformRust::Douyu,
+            "1",
+            DanmakuUser::named("user"),
+            content,
+        )
+    }
+
+    fn gift() -> DanmakuEvent {
+        DanmakuEvent::new(
+            SupportedPlatformRust::Douyu,
+            "1",
+            DanmakuEventKind::Gift {
+                gift_id: None,
+                gift_name: "礼物".to_string(),
+                gift_count: 1,
+                gift_price: None,
+                combo_count: None,
+            },
+            DanmakuUser::named("user"),
+            "",
+        )
+    }
+
+    fn sampler(max_rate: u32) -> Sampler {
+        Sampler {
+            max_rate,
+            window_start: Instant::now(),
+            window_count: 0,
+            rate: 0,
+        }
+    }
+
+    fn options(interval_ms: u64, max_batch: usize) -> BatchOptions {
+        BatchOptions {
+            interval_ms,
+            max_batch,
+            max_rate: 0,
+        }
+    }
+
+    type Delivered = Arc<Mutex<Vec<Vec<String>>>>;
+
+    fn recording_deliver(delivered: &Delivered) -> impl FnMut(DanmakuBatch) -> Result<(), String> {
+        let delivered = delivered.clone();
+        move |batch| {
+            let contents = batch.events.into_iter().map(|e| e.content).collect();
+            delivered.lock().unwrap().push(contents);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sampler_keeps_everything_without_limit() {
+        let mut sampler = sampler(0);
+        assert!((0..1000).all(|_| sampler.keep(&chat("hi"))));
+    }
+
+    #[test]
+    fn sampler_thins_chat_bursts_but_keeps_gifts() {
+        let mut sampler = sampler(10);
+        let kept = (0..1000).filter(|_| sampler.keep(&chat("hi"))).count();
+        assert!((1..200).contains(&kept), "kept {}", kept);
+        assert!((0..100).all(|_| sampler.keep(&gift())));
+    }
+
+    #[tokio::test]
+    async fn flushes_when_batch_is_full() {
+        let delivered = Delivered::default();
+        let (tx, rx) = mpsc::unbounded_channel();
+        for i in 0..7 {
+            tx.send(chat(&i.to_string())).unwrap();
+        }
+        drop(tx);
+        run_batcher(
+            recording_deliver(&delivered),
+            |_| panic!("unexpected fallback"),
+            options(60_000, 3),
+            rx,
+        )
+        .await;
+        let sizes: Vec<usize> = delivered.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn flushes_on_interval() {
+        let delivered = Delivered::default();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_batcher(
+            recording_deliver(&delivered),
+            |_| panic!("unexpected fallback"),
+            options(20, 100),
+            rx,
+        ));
+        tx.send(chat("a")).unwrap();
+        tx.send(chat("b")).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*delivered.lock().unwrap(), vec![vec!["a", "b"]]);
+        drop(tx);
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_falls_back_for_batch_and_queue() {
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        for i in 0..5 {
+            tx.send(chat(&i.to_string())).unwrap();
+        }
+        let sink = emitted.clone();
+        run_batcher(
+            |_| Err("closed".to_string()),
+            move |event: DanmakuEvent| sink.lock().unwrap().push(event.content),
+            options(60_000, 2),
+            rx,
+        )
+        .await;
+        assert_eq!(*emitted.lock().unwrap(), vec!["0", "1", "2", "3", "4"]);
+        // 接收端已关闭，后续事件由 DanmakuSink 直接 emit
+        assert!(tx.send(chat("5")).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+/// 所有平台弹幕事件统一使用的前端事件名
+pub const DANMAKU_EVENT: &str = "danmaku-message";
+/// 在线人数等房间统计单独推送，不进入聊天列表、录制和批量通道
+pub const DANMAKU_ROOM_STATS_EVENT: &str = "danmaku-room-stats";
+
+/// 发送者信息。字段名与旧的 `DanmakuFrontendPayload` 保持一致（user / user_level / fans_club_level）。
+/// 同一事件还会推送礼物、进场等非聊天消息，前端需按 `type` 字段区分展示（见 `danmakuEvents.ts`）。
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DanmakuUser {
+    #[serde(rename = "user")]
+    pub nickname: String,
+    pub user_id: Option<String>,
+    pub avatar: Option<String>,
+    pub user_level: i64,
+    // 粉丝牌 / 勋章
+    pub medal_name: Option<String>,
+    pub fans_club_level: i32,
+    /// 贵族等级（斗鱼 / 虎牙），0 表示无
+    pub noble_level: u8,
+}
+
+impl DanmakuUser {
+    pub fn named(nickname: impl Into<String>) -> Self {
+        Self {
+            nickname: nickname.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RankEntry {
+    pub nickname: String,
+    pub user_id: Option<String>,
+    pub avatar: Option<String>,
+    /// 亲密度 / 贡献值
+    pub score: Option<u64>,
+}
+
+/// 事件类型，序列化后以 `type` 字段区分，例如 `{ "type": "gift", "gift_name": ... }`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DanmakuEventKind {
+    Chat,
+    Gift {
+        gift_id: Option<String>,
+        gift_name: String,
+        gift_count: u32,
+        /// 单价，统一换算为人民币元；平台未提供时为 None
+        gift_price: Option<f64>,
+        /// 连击中的累计数量（用于展示 "x12"），非连击礼物为 None
+        combo_count: Option<u32>,
+    },
+    Enter,
+    Like {
+        count: u64,
+    },
+    Follow,
+    SuperChat {
+        /// 人民币元
+        price: f64,
+        /// 置顶时长（秒）
+        duration: u64,
+    },
+    GuardBuy {
+        guard_level: u8,
+        guard_name: String,
+        count: u32,
+        /// 单价，人民币元
+        price: f64,
+    },
+    /// 加入粉丝团 / 粉丝团升级
+    FansClub {
+        upgrade: bool,
+    },
+    /// 开通 / 续费贵族
+    Noble {
+        noble_level: u8,
+        noble_name: String,
+        renew: bool,
+    },
+    /// 开播 / 下播
+    LiveStatus {
+        live: bool,
+    },
+    /// 粉丝榜 / 贡献榜快照，按名次排列
+    Rank {
+        entries: Vec<RankEntry>,
+    },
+    /// 全站广播（例如其它房间的大额礼物），不计入本房间的礼物统计
+    Broadcast {
+        source_room_id: Option<String>,
+    },
+    /// 用户等级提升，新等级见 user_level
+    LevelUp,
+    /// 在线人数、累计观看、点赞总数等房间统计
+    RoomStats {
+        online: Option<u64>,
+        total_viewers: Option<u64>,
+        likes: Option<u64>,
+        display: Option<String>,
+    },
+    System,
+}
+
+/// 推送给前端的弹幕事件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DanmakuEvent {
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub user: DanmakuUser,
+    /// 聊天内容；非聊天事件为可直接展示的描述文本
+    pub content: String,
+    /// 弹幕颜色（#RRGGBB），未知时为 None
+    pub color: Option<String>,
+    /// 表情弹幕的图片地址，content 为表情名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoticon: Option<String>,
+    #[serde(flatten)]
+    pub kind: DanmakuEventKind,
+}
+
+impl DanmakuEvent {
+    pub fn new(
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        kind: DanmakuEventKind,
+        user: DanmakuUser,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            platform,
+            room_id: room_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            user,
+            content: content.into(),
+            color: None,
+            emoticon: None,
+            kind,
+        }
+    }
+
+    pub fn chat(
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        user: DanmakuUser,
+        content: impl Into<String>,
+    ) -> Self {
+        Self::new(platform, room_id, DanmakuEventKind::Chat, user, content)
+    }
+
+    pub fn system(
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        content: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            platform,
+            room_id,
+            DanmakuEventKind::System,
+            DanmakuUser::named("系统"),
+            content,
+        )
+    }
+
+    pub fn with_color(mut self, color: Option<String>) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_emoticon(mut self, emoticon: Option<String>) -> Self {
+        self.emoticon = emoticon;
+        self
+    }
+}
+
+/// 把平台给出的 RGB 整数转换为 `#RRGGBB`
+pub fn rgb_to_hex(rgb: u32) -> String {
+    format!("#{:06X}", rgb & 0xFF_FF_FF)
+}
@@ -0,0 +1,297 @@
+// 把录制的弹幕会话导出为 B 站 XML（<d p="...">）或滚动 ASS 字幕，
+// 配合代理录下的视频即可在 mpv / DanmakuFactory 等工具中回放弹幕。
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use super::event::DanmakuEventKind;
+use super::recorder::{DanmakuRecorder, RecordedDanmaku};
+
+const DEFAULT_COLOR: u32 = 0xFF_FF_FF;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Xml,
+    Ass,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub font_name: String,
+    pub font_size: u32,
+    /// 滚动轨道数，从画面顶部开始排列
+    pub lanes: u32,
+    /// 一条弹幕从右侧进入到完全离开画面的秒数
+    pub duration_secs: f64,
+    /// 叠加到每条弹幕时间上的偏移（毫秒），用于对齐录像开头；可为负
+    pub offset_ms: i64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            font_name: "Microsoft YaHei".to_string(),
+            font_size: 36,
+            lanes: 12,
+            duration_secs: 8.0,
+            offset_ms: 0,
+            width: 1920,
+            height: 1080,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportSummary {
+    /// 写入文件的弹幕数
+    pub exported: usize,
+    /// ASS 轨道已满而丢弃的弹幕数
+    pub dropped: usize,
+}
+
+// 导出用的单条弹幕
+struct ExportItem<'a> {
+    /// 相对视频开头的毫秒数
+    time_ms: i64,
+    received_at: i64,
+    text: &'a str,
+    color: u32,
+    user: &'a str,
+}
+
+fn parse_color(color: Option<&str>) -> u32 {
+    color
+        .and_then(|c| c.strip_prefix('#'))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(|rgb| rgb & 0xFF_FF_FF)
+        .unwrap_or(DEFAULT_COLOR)
+}
+
+// 只导出聊天和醒目留言，礼物 / 进场等事件不适合做成弹幕
+fn collect_items<'a>(
+    records: &'a [RecordedDanmaku],
+    options: &ExportOptions,
+) -> Vec<ExportItem<'a>> {
+    let mut items: Vec<ExportItem> = records
+        .iter()
+        .filter(|r| {
+            matches!(
+                r.event.kind,
+                DanmakuEventKind::Chat | DanmakuEventKind::SuperChat { .. }
+            )
+        })
+        .filter(|r| !r.event.content.trim().is_empty())
+        .map(|r| ExportItem {
+            time_ms: r.offset_ms + options.offset_ms,
+            received_at: r.received_at,
+            text: r.event.content.trim(),
+            color: parse_color(r.event.color.as_deref()),
+            user: r
+                .event
+                .user
+                .user_id
+                .as_deref()
+                .unwrap_or(&r.event.user.nickname),
+        })
+        .filter(|item| item.time_ms >= 0)
+        .collect();
+    items.sort_by_key(|item| item.time_ms);
+    items
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // XML 1.0 不允许的控制字符
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// B 站弹幕池里的 uid 字段是 crc32 后的十六进制，这里用简单的 FNV 哈希代替，只用于区分发送者
+fn user_hash(user: &str) -> String {
+    let hash = user.bytes().fold(0x811c_9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+    format!("{:08x}", hash)
+}
+
+/// p 属性：出现时间(秒),模式(1 滚动),字号,颜色(十进制),发送时间戳(秒),弹幕池,用户哈希,行号
+pub fn to_bilibili_xml(
+    records: &[RecordedDanmaku],
+    options: &ExportOptions,
+) -> (String, ExportSummary) {
+    let items = collect_items(records, options);
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<i>\n  <chatserver>chat.bilibili.com</chatserver>\n  <chatid>0</chatid>\n  <mission>0</mission>\n  <maxlimit>8000</maxlimit>\n  <state>0</state>\n  <real_name>0</real_name>\n  <source>k-v</source>\n",
+    );
+    for (index, item) in items.iter().enumerate() {
+        let _ = writeln!(
+            xml,
+            "  <d p=\"{:.3},1,25,{},{},0,{},{}\">{}</d>",
+            item.time_ms as f64 / 1000.0,
+            item.color,
+            item.received_at / 1000,
+            user_hash(item.user),
+            index + 1,
+            escape_xml(item.text)
+        );
+    }
+    xml.push_str("</i>\n");
+    (
+        xml,
+        ExportSummary {
+            exported: items.len(),
+            dropped: 0,
+        },
+    )
+}
+
+fn ass_time(ms: i64) -> String {
+    let cs = ms.max(0) / 10;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        cs / 360_000,
+        cs / 6000 % 60,
+        cs / 100 % 60,
+        cs % 100
+    )
+}
+
+// ASS 颜色为 &HBBGGRR
+fn ass_color(rgb: u32) -> String {
+    format!(
+        "&H{:02X}{:02X}{:02X}",
+        rgb & 0xFF,
+        (rgb >> 8) & 0xFF,
+        (rgb >> 16) & 0xFF
+    )
+}
+
+// 去掉会被当作覆盖标签或换行的字符
+fn escape_ass(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '{' => '｛',
+            '}' => '｝',
+            '\\' => '＼',
+            '\n' | '\r' => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+// 估算文字宽度：ASCII 按半个字号，其余按一个字号
+fn text_width(text: &str, font_size: u32) -> f64 {
+    text.chars()
+        .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+        .sum::<f64>()
+        * font_size as f64
+}
+
+pub fn to_ass(records: &[RecordedDanmaku], options: &ExportOptions) -> (String, ExportSummary) {
+    let items = collect_items(records, options);
+    let width = options.width.max(1) as f64;
+    let font_size = options.font_size.max(1);
+    let line_height = font_size as f64 * 1.2;
+    // 轨道数不超过画面能容纳的行数
+    let max_lanes = ((options.height as f64 / line_height).floor() as usize).max(1);
+    let lanes = (options.lanes.max(1) as usize).min(max_lanes);
+    let duration_ms = (options.duration_secs.max(1.0) * 1000.0) as i64;
+
+    let mut ass = String::new();
+    let _ = write!(
+        ass,
+        "[Script Info]\nScriptType: v4.00+\nCollisions: Normal\nPlayResX: {w}\nPlayResY: {h}\nWrapStyle: 2\nScaledBorderAndShadow: yes\n\n\
+[V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+Style: Danmaku,{font},{size},&H00FFFFFF,&H00FFFFFF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,1.5,0,7,0,0,0,1\n\n\
+[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        w = options.width,
+        h = options.height,
+        font = options.font_name,
+        size = font_size,
+    );
+
+    // 每条轨道记录上一条弹幕：(出现时间, 速度 px/ms, 宽度)
+    let mut lane_tail: Vec<Option<(i64, f64, f64)>> = vec![None; lanes];
+    let mut exported = 0;
+    let mut dropped = 0;
+    for item in &items {
+        let text_w = text_width(item.text, font_size);
+        let speed = (width + text_w) / duration_ms as f64;
+        // 轨道可用的条件：上一条已完全进入画面，且新弹幕到达左边缘前追不上它
+        let lane = lane_tail.iter().position(|tail| match tail {
+            None => true,
+            Some((start, prev_speed, prev_w)) => {
+                let elapsed = (item.time_ms - start) as f64;
+                let entered = elapsed * prev_speed >= *prev_w;
+                let prev_exit = (width + prev_w) / prev_speed - elapsed;
+                let catch_up = width / speed;
+                entered && catch_up >= prev_exit
+            }
+        });
+        let Some(lane) = lane else {
+            dropped += 1;
+            continue;
+        };
+        lane_tail[lane] = Some((item.time_ms, speed, text_w));
+
+        let y = lane as f64 * line_height;
+        let color = if item.color == DEFAULT_COLOR {
+            String::new()
+        } else {
+            format!("\\c{}", ass_color(item.color))
+        };
+        let _ = writeln!(
+            ass,
+            "Dialogue: 0,{},{},Danmaku,,0,0,0,,{{\\move({:.0},{:.0},{:.0},{:.0}){}}}{}",
+            ass_time(item.time_ms),
+            ass_time(item.time_ms + duration_ms),
+            width,
+            y,
+            -text_w,
+            y,
+            color,
+            escape_ass(item.text)
+        );
+        exported += 1;
+    }
+    (ass, ExportSummary { exported, dropped })
+}
+
+/// 导出录制会话到 `path`，返回导出数量
+#[tauri::command]
+pub async fn export_danmaku_session(
+    session_id: String,
+    format: ExportFormat,
+    path: String,
+    options: Option<ExportOptions>,
+    recorder: tauri::State<'_, DanmakuRecorder>,
+) -> Result<ExportSummary, String> {
+    let records = recorder.open(&session_id).await?;
+    let options = options.unwrap_or_default();
+    let (content, summary) = match format {
+        ExportFormat::Xml => to_bilibili_xml(&records, &options),
+        ExportFormat::Ass => to_ass(&records, &options),
+    };
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| format!("写入导出文件失败 {}: {}", path, e))?;
+    println!(
+        "[Danmaku Export] Session {} -> {} ({:?}): {} exported, {} dropped",
+        session_id, path, format, summary.exported, summary.dropped
+    );
+    Ok(summary)
+}
@@ -0,0 +1,529 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+
+use super::event::{DanmakuEvent, DanmakuEventKind};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+const RULES_FILE: &str = "danmaku_filters.json";
+// 去重 / 刷屏记录的上限，超过后清理过期条目
+const MAX_TRACKED: usize = 2000;
+
+/// 过滤规则。平台规则对该平台所有房间生效，房间规则在其基础上叠加。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FilterRules {
+    /// 包含任一关键词（不区分大小写）的弹幕被屏蔽
+    pub keywords: Vec<String>,
+    pub regexes: Vec<String>,
+    /// 按用户 id 或昵称屏蔽
+    pub blocked_users: Vec<String>,
+    pub min_user_level: i64,
+    pub min_fans_club_level: i32,
+    /// 该时间窗口内与之前内容相同的弹幕只保留第一条，0 为关闭。
+    /// 旧版本保存为 `collapse_window_secs`，读取时兼容
+    #[serde(alias = "collapse_window_secs")]
+    pub dedupe_window_secs: u64,
+    /// 单个用户在 `flood_window_secs` 内最多 `flood_max_messages` 条，任一为 0 则关闭
+    pub flood_max_messages: u32,
+    pub flood_window_secs: u64,
+}
+
+impl FilterRules {
+    fn merge(&self, room: &FilterRules) -> FilterRules {
+        let union = |a: &[String], b: &[String]| -> Vec<String> {
+            let mut out = a.to_vec();
+            out.extend(b.iter().filter(|v| !a.contains(v)).cloned());
+            out
+        };
+        let pick = |a: u64, b: u64| if b > 0 { b } else { a };
+        FilterRules {
+            keywords: union(&self.keywords, &room.keywords),
+            regexes: union(&self.regexes, &room.regexes),
+            blocked_users: union(&self.blocked_users, &room.blocked_users),
+            min_user_level: self.min_user_level.max(room.min_user_level),
+            min_fans_club_level: self.min_fans_club_level.max(room.min_fans_club_level),
+            dedupe_window_secs: pick(self.dedupe_window_secs, room.dedupe_window_secs),
+            flood_max_messages: pick(
+                self.flood_max_messages as u64,
+                room.flood_max_messages as u64,
+            ) as u32,
+            flood_window_secs: pick(self.flood_window_secs, room.flood_window_secs),
+        }
+    }
+}
+
+// 预处理后的规则；默认值不屏蔽任何事件
+#[derive(Default)]
+struct CompiledRules {
+    keywords: Vec<String>,
+    regexes: Option<RegexSet>,
+    blocked_users: HashSet<String>,
+    min_user_level: i64,
+    min_fans_club_level: i32,
+    dedupe_window_ms: i64,
+    flood_max_messages: usize,
+    flood_window_ms: i64,
+}
+
+impl CompiledRules {
+    fn compile(rules: &FilterRules) -> Result<Self, String> {
+        let patterns: Vec<&String> = rules.regexes.iter().filter(|r| !r.is_empty()).collect();
+        let regexes = if patterns.is_empty() {
+            None
+        } else {
+            Some(RegexSet::new(patterns).map_err(|e| format!("正则表达式无效: {}", e))?)
+        };
+        Ok(Self {
+            keywords: rules
+                .keywords
+                .iter()
+                .filter(|k| !k.is_empty())
+                .map(|k| k.to_lowercase())
+                .collect(),
+            regexes,
+            blocked_users: rules.blocked_users.iter().cloned().collect(),
+            min_user_level: rules.min_user_level,
+            min_fans_club_level: rules.min_fans_club_level,
+            dedupe_window_ms: rules.dedupe_window_secs as i64 * 1000,
+            flood_max_messages: rules.flood_max_messages as usize,
+            flood_window_ms: rules.flood_window_secs as i64 * 1000,
+        })
+    }
+}
+
+// 每个房间的运行时状态
+#[derive(Default)]
+struct RoomState {
+    /// 内容 -> 最近一次放行时间
+    recent: HashMap<String, i64>,
+    /// 用户 -> 窗口内的发送时间
+    per_user: HashMap<String, VecDeque<i64>>,
+}
+
+type RoomKey = (SupportedPlatformRust, String);
+
+#[derive(Default)]
+struct FilterState {
+    /// 键为 "platform" 或 "platform:room_id"
+    rules: HashMap<String, FilterRules>,
+    compiled: HashMap<RoomKey, Arc<CompiledRules>>,
+    rooms: HashMap<RoomKey, RoomState>,
+}
+
+fn rules_key(platform: SupportedPlatformRust, room_id: Option<&str>) -> String {
+    match room_id {
+        Some(room) => format!("{}:{}", platform.as_str(), room),
+        None => platform.as_str().to_string(),
+    }
+}
+
+/// 后端弹幕过滤：所有监听经 `DanmakuSink` 推送前都会经过这里，被屏蔽的事件不再跨 IPC 发给前端。
+pub struct DanmakuFilter {
+    path: PathBuf,
+    state: Mutex<FilterState>,
+    // 串行化写盘：从生成快照到写完文件都持有，保证文件内容与最后一次更新一致，
+    // 同时不让磁盘 IO 阻塞 allow() 所用的 state 锁。写盘期间要跨 await 持有，所以用 tokio 的锁
+    save_lock: tokio::sync::Mutex<()>,
+}
+
+impl DanmakuFilter {
+    /// 从应用数据目录加载已保存的规则，文件不存在或损坏时从空规则开始
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(RULES_FILE);
+        let rules = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                eprintln!("[Danmaku Filter] Ignoring invalid rules file: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path,
+            state: Mutex::new(FilterState {
+                rules,
+                ..Default::default()
+            }),
+            save_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn rules(&self, platform: SupportedPlatformRust, room_id: Option<&str>) -> FilterRules {
+        let state = self.state.lock().unwrap();
+        state
+            .rules
+            .get(&rules_key(platform, room_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// 更新规则并写回磁盘；规则为空时删除该条目
+    pub async fn set_rules(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: Option<&str>,
+        rules: FilterRules,
+    ) -> Result<(), String> {
+        // 先编译一次，避免保存无效的正则
+        CompiledRules::compile(&rules)?;
+        let _save_guard = self.save_lock.lock().await;
+        let snapshot = {
+            let mut state = self.state.lock().unwrap();
+            let key = rules_key(platform, room_id);
+            if rules == FilterRules::default() {
+                state.rules.remove(&key);
+            } else {
+                state.rules.insert(key, rules);
+            }
+            state.compiled.retain(|(p, _), _| *p != platform);
+            serde_json::to_string_pretty(&state.rules).map_err(|e| e.to_string())?
+        };
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        tokio::fs::write(&self.path, snapshot)
+            .await
+            .map_err(|e| format!("保存过滤规则失败: {}", e))
+    }
+
+    /// 房间监听结束时清理去重 / 刷屏状态
+    pub fn reset_room(&self, platform: SupportedPlatformRust, room_id: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .rooms
+            .remove(&(platform, room_id.to_string()));
+    }
+
+    /// 返回 false 表示该事件应被丢弃
+    pub fn allow(&self, event: &DanmakuEvent) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let key = (event.platform, event.room_id.clone());
+        let compiled = match state.compiled.get(&key) {
+            Some(compiled) => compiled.clone(),
+            None => {
+                let platform_rules = state
+                    .rules
+                    .get(&rules_key(event.platform, None))
+                    .cloned()
+                    .unwrap_or_default();
+                let merged = match state
+                    .rules
+                    .get(&rules_key(event.platform, Some(&event.room_id)))
+                {
+                    Some(room_rules) => platform_rules.merge(room_rules),
+                    None => platform_rules,
+                };
+                // 磁盘上的旧规则可能无法编译：缓存一份放行全部的规则，
+                // 避免每条弹幕都重新编译并刷日志，直到规则被更新
+                let compiled = Arc::new(CompiledRules::compile(&merged).unwrap_or_else(|e| {
+                    eprintln!(
+                        "[Danmaku Filter] Rules for {} {} disabled: {}",
+                        event.platform.as_str(),
+                        event.room_id,
+                        e
+                    );
+                    CompiledRules::default()
+                }));
+                state.compiled.insert(key.clone(), compiled.clone());
+                compiled
+            }
+        };
+
+        let user = &event.user;
+        let blocked = user
+            .user_id
+            .as_ref()
+            .is_some_and(|id| compiled.blocked_users.contains(id))
+            || compiled.blocked_users.contains(&user.nickname);
+        if blocked {
+            return false;
+        }
+        // 其余规则只针对聊天弹幕，礼物 / 进场等事件不受影响
+        if !matches!(event.kind, DanmakuEventKind::Chat) {
+            return true;
+        }
+        if user.user_level < compiled.min_user_level
+            || user.fans_club_level < compiled.min_fans_club_level
+        {
+            return false;
+        }
+        let content = event.content.to_lowercase();
+        if compiled.keywords.iter().any(|k| content.contains(k)) {
+            return false;
+        }
+        if compiled
+            .regexes
+            .as_ref()
+            .is_some_and(|set| set.is_match(&event.content))
+        {
+            return false;
+        }
+
+        let now = event.timestamp;
+        let room = state.rooms.entry(key).or_default();
+        if compiled.flood_max_messages > 0 && compiled.flood_window_ms > 0 {
+            let sender = user
+                .user_id
+                .clone()
+                .unwrap_or_else(|| user.nickname.clone());
+            if room.per_user.len() > MAX_TRACKED {
+                let window = compiled.flood_window_ms;
+                room.per_user
+                    .retain(|_, times| times.back().is_some_and(|t| now - t < window));
+            }
+            let times = room.per_user.entry(sender).or_default();
+            while times
+                .front()
+                .is_some_and(|t| now - t >= compiled.flood_window_ms)
+            {
+                times.pop_front();
+            }
+            if times.len() >= compiled.flood_max_messages {
+                return false;
+            }
+            times.push_back(now);
+        }
+        if compiled.dedupe_window_ms > 0 {
+            let window = compiled.dedupe_window_ms;
+            if room.recent.len() > MAX_TRACKED {
+                room.recent.retain(|_, t| now - *t < window);
+            }
+            let text = event.content.trim().to_string();
+            if let Some(last) = room.recent.get(&text) {
+                if now - last < window {
+                    return false;
+                }
+            }
+            room.recent.insert(text, now);
+        }
+        true
+    }
+}
+
+#[tauri::command]
+pub async fn get_danmaku_filter_rules(
+    platform: SupportedPlatformRust,
+    room_id: Option<String>,
+    filter: tauri::State<'_, DanmakuFilter>,
+) -> Result<FilterRules, String> {
+    Ok(filter.rules(platform, room_id.as_deref()))
+}
+
+/// 设置平台（room_id 为空）或单个房间的过滤规则
+#[tauri::command]
+pub async fn set_danmaku_filter_rules(
+    platform: SupportedPlatformRust,
+    room_id: Option<String>,
+    rules: FilterRules,
+    filter: tauri::State<'_, DanmakuFilter>,
+) -> Result<(), String> {
+    filter
+        .set_rules(platform, room_id.as_deref(), rules)
+        .await?;
+    println!(
+        "[Danmaku Filter] Updated rules for {} {}",
+        platform.as_str(),
+        room_id.as_deref().unwrap_or("(all rooms)")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::common::danmaku::event::DanmakuUser;
+
+    const PLATFORM: SupportedPlatformRust = SupportedPlatformRust::Douyu;
+
+    fn filter_with(rules: &[(Option<&str>, FilterRules)]) -> DanmakuFilter {
+        DanmakuFilter {
+            path: PathBuf::new(),
+            state: Mutex::new(FilterState {
+                rules: rules
+                    .iter()
+                    .map(|(room, rules)| (rules_key(PLATFORM, *room), rules.clone()))
+                    .collect(),
+                ..Default::default()
+            }),
+            save_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn chat_at(user: DanmakuUser, content: &str, timestamp: i64) -> DanmakuEvent {
+        let mut event = DanmakuEvent::chat(PLATFORM, "100", user, content);
+        event.timestamp = timestamp;
+        event
+    }
+
+    fn chat(nickname: &str, content: &str) -> DanmakuEvent {
+        chat_at(DanmakuUser::named(nickname), content, 0)
+    }
+
+    fn enter(nickname: &str) -> DanmakuEvent {
+        DanmakuEvent::new(
+            PLATFORM,
+            "100",
+            DanmakuEventKind::Enter,
+            DanmakuUser::named(nickname),
+            "",
+        )
+    }
+
+    #[test]
+    fn blocks_keywords_case_insensitively() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                keywords: vec!["Spam".to_string(), String::new()],
+                ..Default::default()
+            },
+        )]);
+        assert!(!filter.allow(&chat("a", "buy SPAM now")));
+        assert!(filter.allow(&chat("a", "hello")));
+    }
+
+    #[test]
+    fn blocks_regex_matches() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                regexes: vec![r"^\d{5,}$".to_string()],
+                ..Default::default()
+            },
+        )]);
+        assert!(!filter.allow(&chat("a", "1234567")));
+        assert!(filter.allow(&chat("a", "666")));
+    }
+
+    #[test]
+    fn blocks_users_by_id_or_name_for_all_events() {
+        let filter = filter_with(&[(
+            Some("100"),
+            FilterRules {
+                blocked_users: vec!["42".to_string(), "troll".to_string()],
+                ..Default::default()
+            },
+        )]);
+        let by_id = DanmakuUser {
+            user_id: Some("42".to_string()),
+            ..DanmakuUser::named("renamed")
+        };
+        assert!(!filter.allow(&chat_at(by_id, "hi", 0)));
+        assert!(!filter.allow(&chat("troll", "hi")));
+        assert!(!filter.allow(&enter("troll")));
+        assert!(filter.allow(&chat("friend", "hi")));
+    }
+
+    #[test]
+    fn level_thresholds_only_apply_to_chat() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                min_user_level: 10,
+                min_fans_club_level: 3,
+                ..Default::default()
+            },
+        )]);
+        let user = |user_level, fans_club_level| DanmakuUser {
+            user_level,
+            fans_club_level,
+            ..DanmakuUser::named("a")
+        };
+        assert!(!filter.allow(&chat_at(user(9, 5), "hi", 0)));
+        assert!(!filter.allow(&chat_at(user(20, 2), "hi", 0)));
+        assert!(filter.allow(&chat_at(user(10, 3), "hi", 0)));
+        assert!(filter.allow(&enter("a")));
+    }
+
+    #[test]
+    fn limits_messages_per_user_within_window() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                flood_max_messages: 2,
+                flood_window_secs: 10,
+                ..Default::default()
+            },
+        )]);
+        let user = || DanmakuUser::named("a");
+        assert!(filter.allow(&chat_at(user(), "1", 0)));
+        assert!(filter.allow(&chat_at(user(), "2", 1_000)));
+        assert!(!filter.allow(&chat_at(user(), "3", 2_000)));
+        // 其他用户不受影响
+        assert!(filter.allow(&chat_at(DanmakuUser::named("b"), "1", 2_000)));
+        // 最早的一条移出窗口后又可以发送
+        assert!(filter.allow(&chat_at(user(), "4", 10_000)));
+    }
+
+    #[test]
+    fn dedupes_identical_messages_within_window() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                dedupe_window_secs: 5,
+                ..Default::default()
+            },
+        )]);
+        let user = || DanmakuUser::named("a");
+        assert!(filter.allow(&chat_at(user(), "666", 0)));
+        assert!(!filter.allow(&chat_at(user(), " 666 ", 1_000)));
+        assert!(filter.allow(&chat_at(user(), "777", 1_000)));
+        assert!(filter.allow(&chat_at(user(), "666", 6_000)));
+    }
+
+    #[test]
+    fn reads_legacy_collapse_field() {
+        let rules: FilterRules = serde_json::from_str(r#"{"collapse_window_secs": 3}"#).unwrap();
+        assert_eq!(rules.dedupe_window_secs, 3);
+    }
+
+    #[test]
+    fn room_rules_extend_platform_rules() {
+        let platform = FilterRules {
+            keywords: vec!["a".to_string(), "b".to_string()],
+            min_user_level: 5,
+            min_fans_club_level: 2,
+            dedupe_window_secs: 10,
+            flood_max_messages: 3,
+            flood_window_secs: 60,
+            ..Default::default()
+        };
+        let room = FilterRules {
+            keywords: vec!["b".to_string(), "c".to_string()],
+            min_user_level: 3,
+            min_fans_club_level: 4,
+            flood_max_messages: 1,
+            ..Default::default()
+        };
+        let merged = platform.merge(&room);
+        assert_eq!(merged.keywords, vec!["a", "b", "c"]);
+        // 等级取较严格的一方
+        assert_eq!(merged.min_user_level, 5);
+        assert_eq!(merged.min_fans_club_level, 4);
+        // 房间设置了的窗口 / 条数覆盖平台设置，未设置（0）的沿用平台
+        assert_eq!(merged.flood_max_messages, 1);
+        assert_eq!(merged.flood_window_secs, 60);
+        assert_eq!(merged.dedupe_window_secs, 10);
+    }
+
+    #[test]
+    fn invalid_saved_rules_allow_everything_and_are_cached() {
+        let filter = filter_with(&[(
+            None,
+            FilterRules {
+                regexes: vec!["(".to_string()],
+                ..Default::default()
+            },
+        )]);
+        assert!(filter.allow(&chat("a", "(")));
+        assert!(filter.allow(&chat("a", "(")));
+        let state = filter.state.lock().unwrap();
+        assert!(state.compiled.contains_key(&(PLATFORM, "100".to_string())));
+    }
+}
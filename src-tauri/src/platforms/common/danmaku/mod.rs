@@ -0,0 +1,20 @@
+pub mod batch;
+pub mod event;
+pub mod export;
+pub mod filter;
+pub mod reconnect;
+pub mod recorder;
+pub mod registry;
+pub mod sink;
+pub mod stats;
+pub mod status;
+
+pub use batch::{BatchOptions, DanmakuBatch, DanmakuChannel};
+pub use event::{DanmakuEvent, DanmakuEventKind, DanmakuUser, RankEntry};
+pub use filter::DanmakuFilter;
+pub use reconnect::Backoff;
+pub use recorder::DanmakuRecorder;
+pub use registry::DanmakuListenerRegistry;
+pub use sink::DanmakuSink;
+pub use stats::DanmakuStats;
+pub use status::DanmakuStatus;
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::sync::oneshot;
+
+/// 连接持续超过该时长才视为"稳定"，之后断开会从最短退避重新开始
+pub const STABLE_SESSION: Duration = Duration::from_secs(60);
+
+/// 弹幕重连的指数退避（带随机抖动）。
+/// 第 n 次重试等待 `base * 2^(n-1)`（不超过 `max`），再随机取其 50%~100%，
+/// 避免多个房间在网络恢复时同时重连。
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    max_attempts: u32,
+    attempt: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60), 10)
+    }
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration, max_attempts: u32) -> Self {
+        Self {
+            base,
+            max,
+            max_attempts,
+            attempt: 0,
+        }
+    }
+
+    /// 已连续失败的次数
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// 连接恢复后调用
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// 记录一次失败并返回下次重连前的等待时间；超过最大次数时返回 None
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.attempt >= self.max_attempts {
+            return None;
+        }
+        let exp = self.base.saturating_mul(1u32 << self.attempt.min(16));
+        self.attempt += 1;
+        let capped = exp.min(self.max);
+        let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+        Some(capped.mul_f64(jitter))
+    }
+}
+
+/// 等待 `delay`，期间收到停止信号则立即返回 true
+pub async fn wait_or_shutdown(delay: Duration, shutdown: &mut oneshot::Receiver<()>) -> bool {
+    tokio::select! {
+        _ = shutdown => true,
+        _ = tokio::time::sleep(delay) => false,
+    }
+}
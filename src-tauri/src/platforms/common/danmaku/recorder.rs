@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+
+use super::event::DanmakuEvent;
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+/// 录制文件所在的子目录（位于应用数据目录下）
+const RECORDING_DIR: &str = "danmaku";
+const RECORDING_EXT: &str = "jsonl";
+// 长时间没有新弹幕的会话自动结束，避免监听异常退出后文件一直占用
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 录制文件中的一行
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedDanmaku {
+    /// 写入端收到事件的毫秒时间戳
+    pub received_at: i64,
+    /// 相对会话开始的毫秒偏移
+    pub offset_ms: i64,
+    #[serde(flatten)]
+    pub event: DanmakuEvent,
+}
+
+/// 录制会话概要，返回给前端
+#[derive(Serialize, Clone, Debug)]
+pub struct DanmakuSessionInfo {
+    /// 文件名（不含扩展名），作为 open / purge 的参数
+    pub id: String,
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    pub started_at: i64,
+    /// 最后写入时间（毫秒）
+    pub updated_at: i64,
+    pub size_bytes: u64,
+    /// 是否仍在录制
+    pub recording: bool,
+}
+
+enum RecorderCommand {
+    Record {
+        event: DanmakuEvent,
+        received_at: i64,
+    },
+    Close {
+        platform: SupportedPlatformRust,
+        room_id: String,
+    },
+}
+
+type SessionKey = (SupportedPlatformRust, String);
+
+struct OpenSession {
+    id: String,
+    started_at: i64,
+    last_write: i64,
+    writer: BufWriter<File>,
+}
+
+/// 弹幕录制：所有监听的事件经 `DanmakuSink` 送到同一个后台写入任务，
+/// 每个 (平台, 房间, 开始时间) 一个 JSONL 文件。监听停止或长时间无弹幕时结束会话。
+pub struct DanmakuRecorder {
+    dir: PathBuf,
+    tx: mpsc::UnboundedSender<RecorderCommand>,
+    open: Arc<Mutex<HashSet<String>>>,
+}
+
+impl DanmakuRecorder {
+    /// 在 `app_data_dir` 下创建录制目录并启动写入任务
+    pub fn spawn(app_data_dir: &Path) -> Self {
+        let dir = app_data_dir.join(RECORDING_DIR);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let open = Arc::new(Mutex::new(HashSet::new()));
+        tauri::async_runtime::spawn(run_writer(dir.clone(), rx, open.clone()));
+        Self { dir, tx, open }
+    }
+
+    pub fn record(&self, event: &DanmakuEvent) {
+        let _ = self.tx.send(RecorderCommand::Record {
+            event: event.clone(),
+            received_at: chrono::Utc::now().timestamp_millis(),
+        });
+    }
+
+    /// 结束该房间当前的录制会话，下次收到弹幕时开启新文件
+    pub fn close(&self, platform: SupportedPlatformRust, room_id: &str) {
+        let _ = self.tx.send(RecorderCommand::Close {
+            platform,
+            room_id: room_id.to_string(),
+        });
+    }
+
+    fn is_open(&self, id: &str) -> bool {
+        self.open.lock().unwrap().contains(id)
+    }
+
+    fn session_path(&self, id: &str) -> Result<PathBuf, String> {
+        // 只接受 list 返回的文件名，防止通过 id 访问目录外的文件
+        if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+            return Err(format!("无效的录制会话: {}", id));
+        }
+        Ok(self.dir.join(format!("{}.{}", id, RECORDING_EXT)))
+    }
+
+    pub async fn list(&self) -> Result<Vec<DanmakuSessionInfo>, String> {
+        let mut entries = match fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("读取录制目录失败: {}", e)),
+        };
+        let mut sessions = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(RECORDING_EXT) {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let Some((platform, started_at, room_id)) = parse_session_id(id) else {
+                continue;
+            };
+            let Ok(meta) = entry.metadata().await else {
+                continue;
+            };
+            let updated_at = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(started_at);
+            sessions.push(DanmakuSessionInfo {
+                id: id.to_string(),
+                platform,
+                room_id,
+                started_at,
+                updated_at,
+                size_bytes: meta.len(),
+                recording: self.is_open(id),
+            });
+        }
+        sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        Ok(sessions)
+    }
+
+    /// 读取一个会话的全部记录；末尾未写完的行会被跳过
+    pub async fn open(&self, id: &str) -> Result<Vec<RecordedDanmaku>, String> {
+        let path = self.session_path(id)?;
+        let text = fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("读取录制文件失败 {}: {}", path.display(), e))?;
+        Ok(text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// 删除开始时间早于 `before` 的会话（正在录制的除外），返回删除数量
+    pub async fn purge(&self, before: i64) -> Result<usize, String> {
+        let mut removed = 0;
+        for session in self.list().await? {
+            if session.recording || session.started_at >= before {
+                continue;
+            }
+            let path = self.session_path(&session.id)?;
+            match fs::remove_file(&path).await {
+                Ok(()) => removed += 1,
+                Err(e) => eprintln!(
+                    "[Danmaku Recorder] Failed to remove {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        Ok(removed)
+    }
+}
+
+// 文件名：{platform}_{started_at}_{room_id}，房间号中的特殊字符替换为 '_'
+fn session_id(platform: SupportedPlatformRust, room_id: &str, started_at: i64) -> String {
+    let room: String = room_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_{}_{}", platform.as_str(), started_at, room)
+}
+
+fn parse_session_id(id: &str) -> Option<(SupportedPlatformRust, i64, String)> {
+    let mut parts = id.splitn(3, '_');
+    let platform =
+        serde_json::from_value(serde_json::Value::String(parts.next()?.to_string())).ok()?;
+    let started_at = parts.next()?.parse().ok()?;
+    Some((platform, started_at, parts.next()?.to_string()))
+}
+
+async fn open_session(dir: &Path, key: &SessionKey, now: i64) -> std::io::Result<OpenSession> {
+    fs::create_dir_all(dir).await?;
+    let id = session_id(key.0, &key.1, now);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{}.{}", id, RECORDING_EXT)))
+        .await?;
+    println!(
+        "[Danmaku Recorder] Recording {} room {} to {}",
+        key.0.as_str(),
+        key.1,
+        id
+    );
+    Ok(OpenSession {
+        id,
+        started_at: now,
+        last_write: now,
+        writer: BufWriter::new(file),
+    })
+}
+
+async fn close_session(mut session: OpenSession, open: &Mutex<HashSet<String>>) {
+    if let Err(e) = session.writer.flush().await {
+        eprintln!("[Danmaku Recorder] Failed to flush {}: {}", session.id, e);
+    }
+    open.lock().unwrap().remove(&session.id);
+    println!("[Danmaku Recorder] Session {} closed", session.id);
+}
+
+async fn run_writer(
+    dir: PathBuf,
+    mut rx: mpsc::UnboundedReceiver<RecorderCommand>,
+    open: Arc<Mutex<HashSet<String>>>,
+) {
+    let mut sessions: HashMap<SessionKey, OpenSession> = HashMap::new();
+    let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            cmd = rx.recv() => {
+                let Some(cmd) = cmd else { break };
+                let mut dirty = HashSet::new();
+                // 一次取完积压的命令，再统一 flush，保证 open 时能读到最新内容
+                let mut next = Some(cmd);
+                while let Some(cmd) = next.take().or_else(|| rx.try_recv().ok()) {
+                    match cmd {
+                        RecorderCommand::Record { event, received_at } => {
+                            let key = (event.platform, event.room_id.clone());
+                            if !sessions.contains_key(&key) {
+                                match open_session(&dir, &key, received_at).await {
+                                    Ok(session) => {
+                                        open.lock().unwrap().insert(session.id.clone());
+                                        sessions.insert(key.clone(), session);
+                                    }
+                                    Err(e) => {
+                                        eprintln!("[Danmaku Recorder] Failed to open session: {}", e);
+                                        continue;
+                                    }
+                                }
+                            }
+                            let session = sessions.get_mut(&key).unwrap();
+                            let record = RecordedDanmaku {
+                                received_at,
+                                offset_ms: received_at - session.started_at,
+                                event,
+                            };
+                            let Ok(mut line) = serde_json::to_vec(&record) else {
+                                continue;
+                            };
+                            line.push(b'\n');
+                            if let Err(e) = session.writer.write_all(&line).await {
+                                eprintln!("[Danmaku Recorder] Failed to write {}: {}", session.id, e);
+                            }
+                            session.last_write = received_at;
+                            dirty.insert(key);
+                        }
+                        RecorderCommand::Close { platform, room_id } => {
+                            let key = (platform, room_id);
+                            dirty.remove(&key);
+                            if let Some(session) = sessions.remove(&key) {
+                                close_session(session, &open).await;
+                            }
+                        }
+                    }
+                }
+                for key in dirty {
+                    if let Some(session) = sessions.get_mut(&key) {
+                        if let Err(e) = session.writer.flush().await {
+                            eprintln!("[Danmaku Recorder] Failed to flush {}: {}", session.id, e);
+                        }
+                    }
+                }
+            }
+            _ = idle_check.tick() => {
+                let deadline = chrono::Utc::now().timestamp_millis() - IDLE_TIMEOUT.as_millis() as i64;
+                let idle: Vec<SessionKey> = sessions
+                    .iter()
+                    .filter(|(_, s)| s.last_write < deadline)
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                for key in idle {
+                    if let Some(session) = sessions.remove(&key) {
+                        close_session(session, &open).await;
+                    }
+                }
+            }
+        }
+    }
+
+    for (_, session) in sessions.drain() {
+        close_session(session, &open).await;
+    }
+}
+
+#[tauri::command]
+pub async fn list_danmaku_sessions(
+    platform: Option<SupportedPlatformRust>,
+    room_id: Option<String>,
+    recorder: tauri::State<'_, DanmakuRecorder>,
+) -> Result<Vec<DanmakuSessionInfo>, String> {
+    let sessions = recorder.list().await?;
+    Ok(sessions
+        .into_iter()
+        .filter(|s| platform.map_or(true, |p| s.platform == p))
+        .filter(|s| room_id.as_ref().map_or(true, |r| &s.room_id == r))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn open_danmaku_session(
+    session_id: String,
+    recorder: tauri::State<'_, DanmakuRecorder>,
+) -> Result<Vec<RecordedDanmaku>, String> {
+    recorder.open(&session_id).await
+}
+
+/// 删除 `older_than_days` 天前开始的录制，返回删除的会话数
+#[tauri::command]
+pub async fn purge_danmaku_sessions(
+    older_than_days: u32,
+    recorder: tauri::State<'_, DanmakuRecorder>,
+) -> Result<usize, String> {
+    let before =
+        chrono::Utc::now().timestamp_millis() - i64::from(older_than_days) * 24 * 60 * 60 * 1000;
+    let removed = recorder.purge(before).await?;
+    println!("[Danmaku Recorder] Purged {} session(s)", removed);
+    Ok(removed)
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+type ListenerKey = (SupportedPlatformRust, String);
+
+struct ListenerEntry {
+    id: u64,
+    stop_tx: oneshot::Sender<()>,
+    started_at: i64,
+}
+
+// 返回给前端的活跃监听信息
+#[derive(Serialize, Debug, Clone)]
+pub struct ActiveDanmakuListener {
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    pub started_at: i64, // 毫秒时间戳
+}
+
+/// 按 (平台, 房间号) 管理所有弹幕监听任务的停止信号，允许多个房间同时监听。
+/// 每次注册都会分配一个递增 id，监听任务退出时凭 id 注销，避免误删同房间的新任务。
+#[derive(Default)]
+pub struct DanmakuListenerRegistry {
+    listeners: Mutex<HashMap<ListenerKey, ListenerEntry>>,
+    // 每个房间最近一次注册的 id；监听被停止后仍保留，用于识别已被替换的旧任务
+    latest: Mutex<HashMap<ListenerKey, u64>>,
+    next_id: AtomicU64,
+}
+
+impl DanmakuListenerRegistry {
+    /// 注册新的监听任务；若该房间已有监听，会先向旧任务发送停止信号。
+    pub fn register(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: &str,
+    ) -> (u64, oneshot::Receiver<()>) {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let entry = ListenerEntry {
+            id,
+            stop_tx,
+            started_at: chrono::Utc::now().timestamp_millis(),
+        };
+        self.latest
+            .lock()
+            .unwrap()
+            .insert((platform, room_id.to_string()), id);
+        let previous = self
+            .listeners
+            .lock()
+            .unwrap()
+            .insert((platform, room_id.to_string()), entry);
+        if let Some(previous) = previous {
+            println!(
+                "[Danmaku Registry] Replacing existing {} listener for room {}",
+                platform.as_str(),
+                room_id
+            );
+            let _ = previous.stop_tx.send(());
+        }
+        (id, stop_rx)
+    }
+
+    /// 停止指定房间的监听，返回是否存在该监听。
+    pub fn stop(&self, platform: SupportedPlatformRust, room_id: &str) -> bool {
+        let entry = self
+            .listeners
+            .lock()
+            .unwrap()
+            .remove(&(platform, room_id.to_string()));
+        match entry {
+            Some(entry) => {
+                let _ = entry.stop_tx.send(());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 停止某个平台（或 `None` 时所有平台）的全部监听，返回停止的数量。
+    pub fn stop_all(&self, platform: Option<SupportedPlatformRust>) -> usize {
+        let drained: Vec<ListenerEntry> = {
+            let mut listeners = self.listeners.lock().unwrap();
+            let keys: Vec<ListenerKey> = listeners
+                .keys()
+                .filter(|(p, _)| platform.map_or(true, |target| *p == target))
+                .cloned()
+                .collect();
+            keys.iter().filter_map(|k| listeners.remove(k)).collect()
+        };
+        let count = drained.len();
+        for entry in drained {
+            let _ = entry.stop_tx.send(());
+        }
+        count
+    }
+
+    /// 监听任务自行退出（连接失败等）时调用，仅在 id 匹配时移除。
+    pub fn finish(&self, platform: SupportedPlatformRust, room_id: &str, id: u64) {
+        let mut listeners = self.listeners.lock().unwrap();
+        let key = (platform, room_id.to_string());
+        if listeners.get(&key).map(|e| e.id) == Some(id) {
+            listeners.remove(&key);
+        }
+    }
+
+    /// 该房间在 `id` 之后又注册过新的监听，旧任务不应再清理房间级状态
+    pub fn is_superseded(&self, platform: SupportedPlatformRust, room_id: &str, id: u64) -> bool {
+        self.latest
+            .lock()
+            .unwrap()
+            .get(&(platform, room_id.to_string()))
+            .is_some_and(|latest| *latest != id)
+    }
+
+    pub fn list(&self) -> Vec<ActiveDanmakuListener> {
+        let mut active: Vec<ActiveDanmakuListener> = self
+            .listeners
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((platform, room_id), entry)| ActiveDanmakuListener {
+                platform: *platform,
+                room_id: room_id.clone(),
+                started_at: entry.started_at,
+            })
+            .collect();
+        active.sort_by_key(|l| l.started_at);
+        active
+    }
+}
+
+#[tauri::command]
+pub async fn list_active_danmaku_listeners(
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
+) -> Result<Vec<ActiveDanmakuListener>, String> {
+    Ok(registry.list())
+}
+
+#[tauri::command]
+pub async fn stop_room_danmaku_listener(
+    platform: SupportedPlatformRust,
+    room_id: String,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
+) -> Result<bool, String> {
+    Ok(registry.stop(platform, &room_id))
+}
+
+#[tauri::command]
+pub async fn stop_all_danmaku_listeners(
+    platform: Option<SupportedPlatformRust>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
+) -> Result<usize, String> {
+    let count = registry.stop_all(platform);
+    println!("[Danmaku Registry] Stopped {} listener(s)", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOUYU: SupportedPlatformRust = SupportedPlatformRust::Douyu;
+
+    #[test]
+    fn replaced_listener_is_superseded() {
+        let registry = DanmakuListenerRegistry::default();
+        let (old, _old_rx) = registry.register(DOUYU, "1");
+        assert!(!registry.is_superseded(DOUYU, "1", old));
+
+        let (new, _new_rx) = registry.register(DOUYU, "1");
+        assert!(registry.is_superseded(DOUYU, "1", old));
+        assert!(!registry.is_superseded(DOUYU, "1", new));
+
+        // 旧任务退出时不能注销新监听
+        registry.finish(DOUYU, "1", old);
+        assert_eq!(registry.list().len(), 1);
+
+        // 停止后最新的监听仍不算被替换，可以正常清理
+        assert!(registry.stop(DOUYU, "1"));
+        assert!(!registry.is_superseded(DOUYU, "1", new));
+        assert!(!registry.is_superseded(DOUYU, "2", new));
+    }
+}
@@ -0,0 +1,166 @@
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::batch::DanmakuChannel;
+use super::event::{
+    DanmakuEvent, DanmakuEventKind, DanmakuUser, DANMAKU_EVENT, DANMAKU_ROOM_STATS_EVENT,
+};
+use super::filter::DanmakuFilter;
+use super::recorder::DanmakuRecorder;
+use super::registry::DanmakuListenerRegistry;
+use super::stats::DanmakuStats;
+use super::status::{DanmakuStatus, DanmakuStatusEvent, DANMAKU_STATUS_EVENT};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+/// 单个房间弹幕监听的出口，各平台监听器只通过它向前端推送弹幕和连接状态。
+#[derive(Clone)]
+pub struct DanmakuSink {
+    app_handle: AppHandle,
+    platform: SupportedPlatformRust,
+    room_id: String,
+    // 前端传入 Channel 时批量推送，否则逐条 emit
+    channel: Option<DanmakuChannel>,
+    // DanmakuListenerRegistry 分配的 id，用于识别被同房间新监听替换的旧任务
+    listener_id: Option<u64>,
+}
+
+impl DanmakuSink {
+    pub fn new(app_handle: AppHandle, platform: SupportedPlatformRust, room_id: &str) -> Self {
+        Self {
+            app_handle,
+            platform,
+            room_id: room_id.to_string(),
+            channel: None,
+            listener_id: None,
+        }
+    }
+
+    pub fn with_channel(mut self, channel: Option<DanmakuChannel>) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    pub fn with_listener_id(mut self, listener_id: u64) -> Self {
+        self.listener_id = Some(listener_id);
+        self
+    }
+
+    fn is_superseded(&self) -> bool {
+        let Some(id) = self.listener_id else {
+            return false;
+        };
+        self.app_handle
+            .try_state::<DanmakuListenerRegistry>()
+            .is_some_and(|registry| registry.is_superseded(self.platform, &self.room_id, id))
+    }
+
+    pub fn room_id(&self) -> &str {
+        &self.room_id
+    }
+
+    pub fn event(
+        &self,
+        kind: DanmakuEventKind,
+        user: DanmakuUser,
+        content: impl Into<String>,
+    ) -> DanmakuEvent {
+        DanmakuEvent::new(self.platform, &self.room_id, kind, user, content)
+    }
+
+    pub fn emit(&self, mut event: DanmakuEvent) {
+        if matches!(event.kind, DanmakuEventKind::RoomStats { .. }) {
+            self.emit_room_stats(event);
+            return;
+        }
+        // 录制和统计使用完整数据，过滤只影响推送给前端的事件
+        if let Some(recorder) = self.app_handle.try_state::<DanmakuRecorder>() {
+            recorder.record(&event);
+        }
+        if let Some(stats) = self.app_handle.try_state::<DanmakuStats>() {
+            stats.record(&event);
+        }
+        if let Some(filter) = self.app_handle.try_state::<DanmakuFilter>() {
+            if !filter.allow(&event) {
+                return;
+            }
+        }
+        if let Some(channel) = &self.channel {
+            match channel.send(event) {
+                Ok(()) => return,
+                // 批量通道已关闭，退回逐条 emit，避免弹幕被静默丢弃
+                Err(returned) => event = *returned,
+            }
+        }
+        if let Err(e) = self.app_handle.emit(DANMAKU_EVENT, event) {
+            eprintln!(
+                "[Danmaku {}] Failed to emit event for room {}: {}",
+                self.platform.as_str(),
+                self.room_id,
+                e
+            );
+        }
+    }
+
+    // 周期性的房间计数，与弹幕分开推送，避免刷屏和撑大录制文件
+    fn emit_room_stats(&self, event: DanmakuEvent) {
+        if let Err(e) = self.app_handle.emit(DANMAKU_ROOM_STATS_EVENT, event) {
+            eprintln!(
+                "[Danmaku {}] Failed to emit room stats for room {}: {}",
+                self.platform.as_str(),
+                self.room_id,
+                e
+            );
+        }
+    }
+
+    pub fn emit_chat(&self, user: DanmakuUser, content: impl Into<String>) {
+        self.emit(self.event(DanmakuEventKind::Chat, user, content));
+    }
+
+    /// 推送连接状态（danmaku-status），不进入弹幕列表
+    pub fn status(&self, status: DanmakuStatus) {
+        println!(
+            "[Danmaku {}] Room {} status: {:?}",
+            self.platform.as_str(),
+            self.room_id,
+            status
+        );
+        if matches!(
+            status,
+            DanmakuStatus::Stopped | DanmakuStatus::Failed { .. }
+        ) {
+            // 录制、过滤和统计按房间保存；旧任务被替换后这些状态已属于新监听，
+            // 它的结束状态也不再推送，避免前端把新监听显示为已停止
+            if self.is_superseded() {
+                println!(
+                    "[Danmaku {}] Room {} listener was replaced, skipping cleanup",
+                    self.platform.as_str(),
+                    self.room_id
+                );
+                return;
+            }
+            if let Some(recorder) = self.app_handle.try_state::<DanmakuRecorder>() {
+                recorder.close(self.platform, &self.room_id);
+            }
+            if let Some(filter) = self.app_handle.try_state::<DanmakuFilter>() {
+                filter.reset_room(self.platform, &self.room_id);
+            }
+            if let Some(stats) = self.app_handle.try_state::<DanmakuStats>() {
+                stats.finish(self.platform, &self.room_id);
+            }
+        }
+        let event = DanmakuStatusEvent {
+            platform: self.platform,
+            room_id: self.room_id.clone(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            status,
+        };
+        if let Err(e) = self.app_handle.emit(DANMAKU_STATUS_EVENT, event) {
+            eprintln!(
+                "[Danmaku {}] Failed to emit status for room {}: {}",
+                self.platform.as_str(),
+                self.room_id,
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::event::{DanmakuEvent, DanmakuEventKind};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+pub const DANMAKU_STATS_EVENT: &str = "danmaku-stats";
+const EMIT_INTERVAL: Duration = Duration::from_secs(5);
+// 每分钟弹幕数保留最近一小时
+const HISTORY_MINUTES: usize = 60;
+const DEFAULT_TOP: usize = 10;
+// 短语只统计较短的弹幕，长句几乎不会重复
+const MAX_PHRASE_CHARS: usize = 30;
+// 计数表超过该大小时只保留计数最高的一半
+const MAX_TRACKED: usize = 5000;
+
+fn emote_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // 斗鱼 / 虎牙 / 抖音的文字表情都是 [名称] 形式
+    RE.get_or_init(|| Regex::new(r"\[[^\[\]\s]{1,12}\]").unwrap())
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MinuteCount {
+    /// 该分钟开始的毫秒时间戳
+    pub minute: i64,
+    pub count: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ChatterCount {
+    pub nickname: String,
+    pub user_id: Option<String>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TextCount {
+    pub text: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GiftTotal {
+    pub gift_name: String,
+    pub count: u64,
+    /// 人民币元
+    pub value: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DanmakuStatsSnapshot {
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    pub started_at: i64,
+    pub total_messages: u64,
+    /// 最近 60 秒的弹幕数
+    pub messages_per_minute: u64,
+    /// 按分钟统计的历史，最早的在前
+    pub history: Vec<MinuteCount>,
+    /// 发言人数。发言者表超限清理后再次发言的用户会被重复计入，为近似值
+    pub unique_chatters: usize,
+    pub top_chatters: Vec<ChatterCount>,
+    pub top_phrases: Vec<TextCount>,
+    pub top_emotes: Vec<TextCount>,
+    /// 礼物、醒目留言、上舰的总价值（元）
+    pub gift_total_value: f64,
+    pub gifts: Vec<GiftTotal>,
+}
+
+struct Chatter {
+    nickname: String,
+    user_id: Option<String>,
+    count: u64,
+}
+
+struct RoomStats {
+    started_at: i64,
+    active: bool,
+    total_messages: u64,
+    /// (秒, 数量)，只保留最近 60 秒
+    seconds: VecDeque<(i64, u64)>,
+    /// (分钟, 数量)，连续的分钟，没有弹幕的分钟计 0
+    minutes: VecDeque<(i64, u64)>,
+    chatters: HashMap<String, Chatter>,
+    unique_chatters: usize,
+    phrases: HashMap<String, u64>,
+    emotes: HashMap<String, u64>,
+    gifts: HashMap<String, (u64, f64)>,
+    gift_total_value: f64,
+}
+
+// 超过上限时丢弃计数最低的条目，只留下 MAX_TRACKED / 2 条，表的大小始终有界
+fn prune<V>(map: &mut HashMap<String, V>, count: impl Fn(&V) -> u64) {
+    if map.len() <= MAX_TRACKED {
+        return;
+    }
+    let mut entries: Vec<(String, V)> = map.drain().collect();
+    entries.sort_by_key(|(_, v)| std::cmp::Reverse(count(v)));
+    entries.truncate(MAX_TRACKED / 2);
+    map.extend(entries);
+}
+
+fn bump(counts: &mut HashMap<String, u64>, key: &str) {
+    prune(counts, |count| *count);
+    *counts.entry(key.to_string()).or_default() += 1;
+}
+
+fn top_counts(counts: &HashMap<String, u64>, limit: usize) -> Vec<TextCount> {
+    let mut items: Vec<TextCount> = counts
+        .iter()
+        .filter(|(_, count)| **count > 1)
+        .map(|(text, count)| TextCount {
+            text: text.clone(),
+            count: *count,
+        })
+        .collect();
+    items.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.text.cmp(&b.text)));
+    items.truncate(limit);
+    items
+}
+
+impl RoomStats {
+    fn new(now: i64) -> Self {
+        Self {
+            started_at: now,
+            active: true,
+            total_messages: 0,
+            seconds: VecDeque::new(),
+            minutes: VecDeque::new(),
+            chatters: HashMap::new(),
+            unique_chatters: 0,
+            phrases: HashMap::new(),
+            emotes: HashMap::new(),
+            gifts: HashMap::new(),
+            gift_total_value: 0.0,
+        }
+    }
+
+    fn add_value(&mut self, name: &str, count: u64, value: f64) {
+        let entry = self.gifts.entry(name.to_string()).or_default();
+        entry.0 += count;
+        entry.1 += value;
+        self.gift_total_value += value;
+    }
+
+    fn record(&mut self, event: &DanmakuEvent) {
+        self.active = true;
+        match &event.kind {
+            DanmakuEventKind::Chat => self.record_chat(event),
+            DanmakuEventKind::Gift {
+                gift_name,
+                gift_count,
+                gift_price,
+                ..
+            } => {
+                let value = gift_price.unwrap_or(0.0) * *gift_count as f64;
+                self.add_value(gift_name, *gift_count as u64, value);
+            }
+            DanmakuEventKind::SuperChat { price, .. } => {
+                self.add_value("醒目留言", 1, *price);
+                self.record_chat(event);
+            }
+            DanmakuEventKind::GuardBuy {
+                guard_name,
+                count,
+                price,
+                ..
+            } => self.add_value(guard_name, *count as u64, price * *count as f64),
+            _ => {}
+        }
+    }
+
+    fn record_chat(&mut self, event: &DanmakuEvent) {
+        let now = event.timestamp;
+        self.total_messages += 1;
+
+        let second = now / 1000;
+        match self.seconds.back_mut() {
+            Some((s, count)) if *s == second => *count += 1,
+            _ => self.seconds.push_back((second, 1)),
+        }
+        while self.seconds.front().is_some_and(|(s, _)| second - s >= 60) {
+            self.seconds.pop_front();
+        }
+        let minute = now / 60_000 * 60_000;
+        match self.minutes.back().map(|(m, _)| *m) {
+            // 乱序到达的弹幕计入对应的分钟，已移出历史的不再计
+            Some(last) if minute <= last => {
+                if let Some((_, count)) = self.minutes.iter_mut().rev().find(|(m, _)| *m == minute)
+                {
+                    *count += 1;
+                }
+            }
+            last => {
+                // 中间没有弹幕的分钟补 0，最多补到历史长度
+                if let Some(last) = last {
+                    let oldest = minute - (HISTORY_MINUTES as i64 - 1) * 60_000;
+                    let mut gap = (last + 60_000).max(oldest);
+                    while gap < minute {
+                        self.minutes.push_back((gap, 0));
+                        gap += 60_000;
+                    }
+                }
+                self.minutes.push_back((minute, 1));
+            }
+        }
+        while self.minutes.len() > HISTORY_MINUTES {
+            self.minutes.pop_front();
+        }
+
+        let user = &event.user;
+        let key = user
+            .user_id
+            .clone()
+            .unwrap_or_else(|| user.nickname.clone());
+        if !self.chatters.contains_key(&key) {
+            prune(&mut self.chatters, |c| c.count);
+            self.unique_chatters += 1;
+        }
+        let chatter = self.chatters.entry(key).or_insert_with(|| Chatter {
+            nickname: user.nickname.clone(),
+            user_id: user.user_id.clone(),
+            count: 0,
+        });
+        chatter.count += 1;
+
+        let text = event.content.trim();
+        if event.emoticon.is_some() {
+            bump(&mut self.emotes, text);
+            return;
+        }
+        for emote in emote_regex().find_iter(text) {
+            bump(&mut self.emotes, emote.as_str());
+        }
+        if !text.is_empty() && text.chars().count() <= MAX_PHRASE_CHARS {
+            bump(&mut self.phrases, text);
+        }
+    }
+
+    fn snapshot(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        limit: usize,
+        now: i64,
+    ) -> DanmakuStatsSnapshot {
+        let now_second = now / 1000;
+        let mut top_chatters: Vec<ChatterCount> = self
+            .chatters
+            .values()
+            .map(|c| ChatterCount {
+                nickname: c.nickname.clone(),
+                user_id: c.user_id.clone(),
+                count: c.count,
+            })
+            .collect();
+        top_chatters.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.nickname.cmp(&b.nickname))
+        });
+        top_chatters.truncate(limit);
+
+        let mut gifts: Vec<GiftTotal> = self
+            .gifts
+            .iter()
+            .map(|(name, (count, value))| GiftTotal {
+                gift_name: name.clone(),
+                count: *count,
+                value: *value,
+            })
+            .collect();
+        gifts.sort_by(|a, b| {
+            b.value
+                .partial_cmp(&a.value)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.count.cmp(&a.count))
+        });
+
+        DanmakuStatsSnapshot {
+            platform,
+            room_id: room_id.to_string(),
+            started_at: self.started_at,
+            total_messages: self.total_messages,
+            messages_per_minute: self
+                .seconds
+                .iter()
+                .filter(|(s, _)| now_second - s < 60)
+                .map(|(_, count)| count)
+                .sum(),
+            history: self
+                .minutes
+                .iter()
+                .map(|(minute, count)| MinuteCount {
+                    minute: *minute,
+                    count: *count,
+                })
+                .collect(),
+            unique_chatters: self.unique_chatters,
+            top_chatters,
+            top_phrases: top_counts(&self.phrases, limit),
+            top_emotes: top_counts(&self.emotes, limit),
+            gift_total_value: self.gift_total_value,
+            gifts,
+        }
+    }
+}
+
+type RoomKey = (SupportedPlatformRust, String);
+
+/// 按房间汇总弹幕统计；数据来自 `DanmakuSink`，不受前端过滤规则影响
+#[derive(Default)]
+pub struct DanmakuStats {
+    rooms: Mutex<HashMap<RoomKey, RoomStats>>,
+}
+
+impl DanmakuStats {
+    pub fn record(&self, event: &DanmakuEvent) {
+        let mut rooms = self.rooms.lock().unwrap();
+        rooms
+            .entry((event.platform, event.room_id.clone()))
+            .or_insert_with(|| RoomStats::new(event.timestamp))
+            .record(event);
+    }
+
+    /// 监听结束后保留统计数据，但不再定时推送
+    pub fn finish(&self, platform: SupportedPlatformRust, room_id: &str) {
+        if let Some(room) = self
+            .rooms
+            .lock()
+            .unwrap()
+            .get_mut(&(platform, room_id.to_string()))
+        {
+            room.active = false;
+        }
+    }
+
+    pub fn snapshot(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        limit: usize,
+    ) -> Option<DanmakuStatsSnapshot> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .get(&(platform, room_id.to_string()))
+            .map(|room| {
+                room.snapshot(
+                    platform,
+                    room_id,
+                    limit,
+                    chrono::Utc::now().timestamp_millis(),
+                )
+            })
+    }
+
+    fn active_snapshots(&self) -> Vec<DanmakuStatsSnapshot> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, room)| room.active)
+            .map(|((platform, room_id), room)| room.snapshot(*platform, room_id, DEFAULT_TOP, now))
+            .collect()
+    }
+
+    /// 定时向前端推送正在监听的房间的统计
+    pub fn spawn_emitter(app_handle: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = tokio::time::interval(EMIT_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let snapshots = app_handle.state::<DanmakuStats>().active_snapshots();
+                for snapshot in snapshots {
+                    if let Err(e) = app_handle.emit(DANMAKU_STATS_EVENT, &snapshot) {
+                        eprintln!(
+                            "[Danmaku Stats] Failed to emit stats for room {}: {}",
+                            snapshot.room_id, e
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn get_danmaku_stats(
+    platform: SupportedPlatformRust,
+    room_id: String,
+    limit: Option<usize>,
+    stats: tauri::State<'_, DanmakuStats>,
+) -> Result<Option<DanmakuStatsSnapshot>, String> {
+    Ok(stats.snapshot(platform, &room_id, limit.unwrap_or(DEFAULT_TOP)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platforms::common::danmaku::event::DanmakuUser;
+
+    // 对齐到整分钟的起始时间
+    const T0: i64 = 28_000_000 * 60_000;
+
+    fn chat_at(nickname: &str, content: &str, timestamp: i64) -> DanmakuEvent {
+        let mut event = DanmakuEvent::chat(
+            SupportedPlatformRust::Douyu,
+            "100",
+            DanmakuUser::named(nickname),
+            content,
+        );
+        event.timestamp = timestamp;
+        event
+    }
+
+    fn snapshot(room: &RoomStats, now: i64) -> DanmakuStatsSnapshot {
+        room.snapshot(SupportedPlatformRust::Douyu, "100", 3, now)
+    }
+
+    #[test]
+    fn rate_counts_the_last_sixty_seconds() {
+        let mut room = RoomStats::new(T0);
+        for offset in [0, 30_000, 70_000, 89_000, 89_500] {
+            room.record(&chat_at("a", "hi", T0 + offset));
+        }
+        let stats = snapshot(&room, T0 + 90_000);
+        assert_eq!(stats.total_messages, 5);
+        assert_eq!(stats.messages_per_minute, 3);
+        assert_eq!(snapshot(&room, T0 + 200_000).messages_per_minute, 0);
+    }
+
+    #[test]
+    fn history_fills_skipped_minutes_with_zero() {
+        let mut room = RoomStats::new(T0);
+        room.record(&chat_at("a", "hi", T0));
+        room.record(&chat_at("a", "hi", T0 + 10_000));
+        room.record(&chat_at("a", "hi", T0 + 3 * 60_000 + 5_000));
+        // 乱序到达的旧弹幕计入原来的分钟
+        room.record(&chat_at("a", "hi", T0 + 20_000));
+        let history: Vec<(i64, u64)> = snapshot(&room, T0)
+            .history
+            .iter()
+            .map(|m| ((m.minute - T0) / 60_000, m.count))
+            .collect();
+        assert_eq!(history, vec![(0, 3), (1, 0), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn history_keeps_only_the_last_hour() {
+        let mut room = RoomStats::new(T0);
+        room.record(&chat_at("a", "hi", T0));
+        room.record(&chat_at("a", "hi", T0 + 100 * 60_000));
+        let history = snapshot(&room, T0).history;
+        assert_eq!(history.len(), HISTORY_MINUTES);
+        assert_eq!(history[0].minute, T0 + 41 * 60_000);
+        assert!(history[..HISTORY_MINUTES - 1].iter().all(|m| m.count == 0));
+        assert_eq!(history[HISTORY_MINUTES - 1].count, 1);
+    }
+
+    #[test]
+    fn top_lists_are_sorted_and_limited() {
+        let mut room = RoomStats::new(T0);
+        let messages = [
+            ("a", "666"),
+            ("a", "666"),
+            ("a", "[doge]"),
+            ("b", "666"),
+            ("b", "hello"),
+            ("b", "hello [doge]"),
+            ("c", "once"),
+            ("d", "hello"),
+            ("e", "x"),
+        ];
+        for (nickname, content) in messages {
+            room.record(&chat_at(nickname, content, T0));
+        }
+        let stats = snapshot(&room, T0);
+        let chatters: Vec<(&str, u64)> = stats
+            .top_chatters
+            .iter()
+            .map(|c| (c.nickname.as_str(), c.count))
+            .collect();
+        assert_eq!(chatters, vec![("a", 3), ("b", 3), ("c", 1)]);
+        assert_eq!(stats.unique_chatters, 5);
+        // 只出现一次的短语不计入排行
+        let phrases: Vec<(&str, u64)> = stats
+            .top_phrases
+            .iter()
+            .map(|p| (p.text.as_str(), p.count))
+            .collect();
+        assert_eq!(phrases, vec![("666", 3), ("hello", 2)]);
+        assert_eq!(stats.top_emotes[0].text, "[doge]");
+        assert_eq!(stats.top_emotes[0].count, 2);
+    }
+
+    #[test]
+    fn tracked_tables_are_pruned_to_half() {
+        let mut room = RoomStats::new(T0);
+        for _ in 0..3 {
+            room.record(&chat_at("regular", "666", T0));
+        }
+        for i in 0..=MAX_TRACKED {
+            room.record(&chat_at(&format!("user{}", i), &format!("msg{}", i), T0));
+        }
+        assert!(room.chatters.len() <= MAX_TRACKED / 2 + 1);
+        assert!(room.phrases.len() <= MAX_TRACKED / 2 + 1);
+        assert_eq!(room.chatters["regular"].count, 3);
+        assert_eq!(room.phrases["666"], 3);
+        assert_eq!(room.unique_chatters, MAX_TRACKED + 2);
+    }
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+/// 连接状态事件名，与弹幕消息分开，避免错误提示混入聊天列表和导出
+pub const DANMAKU_STATUS_EVENT: &str = "danmaku-status";
+
+/// 序列化后以 `state` 字段区分，例如 `{ "state": "reconnecting", "attempt": 2 }`
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DanmakuStatus {
+    Connecting,
+    Connected,
+    /// 第 `attempt` 次重连前的等待中
+    Reconnecting {
+        attempt: u32,
+    },
+    /// 主动停止
+    Stopped,
+    /// 放弃重连，监听已结束
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DanmakuStatusEvent {
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub status: DanmakuStatus,
+}
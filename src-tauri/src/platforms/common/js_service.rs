@@ -0,0 +1,247 @@
+// 执行页面抓取脚本的共享 JS 服务：少量常驻 V8 isolate 跑在专用线程上，
+// 每次调用有超时（terminate_execution）和堆上限，超限的 isolate 会被丢弃重建。
+// 每次调用都在新建的 context 中执行，调用之间不共享全局对象。
+// 运行时不加载任何 deno 扩展，脚本没有文件、网络等能力。
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use deno_core::v8::{self, IsolateHandle};
+use deno_core::{JsRuntime, RuntimeOptions};
+use tokio::sync::oneshot;
+
+const POOL_SIZE: usize = 2;
+const CALL_TIMEOUT: Duration = Duration::from_secs(3);
+const HEAP_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+// 同一个 isolate 执行过多次后重建，回收堆上的残留
+const MAX_CALLS_PER_ISOLATE: u32 = 200;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JsError {
+    #[error("脚本执行超时 ({0:?})")]
+    Timeout(Duration),
+    #[error("脚本超出内存限制")]
+    HeapLimit,
+    #[error("脚本执行失败: {0}")]
+    Script(String),
+    #[error("JS 服务不可用")]
+    Unavailable,
+}
+
+struct Job {
+    scripts: Vec<String>,
+    timeout: Duration,
+    reply: oneshot::Sender<Result<String, JsError>>,
+}
+
+#[derive(Default)]
+struct WatchState {
+    deadline: Option<Instant>,
+    handle: Option<IsolateHandle>,
+    fired: bool,
+}
+
+// 每个工作线程一个看门狗：到期时终止当前 isolate 的执行。
+// 撤销与触发都在同一把锁下进行，撤销返回后不会再误杀下一次调用。
+#[derive(Clone, Default)]
+struct Watchdog {
+    state: Arc<(Mutex<WatchState>, Condvar)>,
+}
+
+impl Watchdog {
+    fn spawn(name: String) -> std::io::Result<Self> {
+        let watchdog = Self::default();
+        let state = watchdog.state.clone();
+        thread::Builder::new().name(name).spawn(move || {
+            let (lock, cvar) = &*state;
+            let mut guard = lock.lock().unwrap();
+            loop {
+                let deadline = guard.deadline;
+                guard = match deadline {
+                    None => cvar.wait(guard).unwrap(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            if let Some(handle) = &guard.handle {
+                                handle.terminate_execution();
+                            }
+                            guard.fired = true;
+                            guard.deadline = None;
+                            guard
+                        } else {
+                            cvar.wait_timeout(guard, deadline - now).unwrap().0
+                        }
+                    }
+                };
+            }
+        })?;
+        Ok(watchdog)
+    }
+
+    fn arm(&self, handle: IsolateHandle, timeout: Duration) {
+        let (lock, cvar) = &*self.state;
+        let mut guard = lock.lock().unwrap();
+        guard.handle = Some(handle);
+        guard.deadline = Some(Instant::now() + timeout);
+        guard.fired = false;
+        cvar.notify_one();
+    }
+
+    /// 返回本次调用是否已被看门狗终止
+    fn disarm(&self) -> bool {
+        let mut guard = self.state.0.lock().unwrap();
+        guard.deadline = None;
+        guard.handle = None;
+        guard.fired
+    }
+}
+
+struct Isolate {
+    runtime: JsRuntime,
+    heap_exceeded: Arc<AtomicBool>,
+    calls: u32,
+}
+
+impl Isolate {
+    fn new() -> Self {
+        let mut runtime = JsRuntime::new(RuntimeOptions {
+            create_params: Some(v8::CreateParams::default().heap_limits(0, HEAP_LIMIT_BYTES)),
+            ..Default::default()
+        });
+        // 接近堆上限时终止执行，并临时放宽上限让 V8 能完成终止，而不是直接崩溃
+        let heap_exceeded = Arc::new(AtomicBool::new(false));
+        let handle = runtime.v8_isolate().thread_safe_handle();
+        let flag = heap_exceeded.clone();
+        runtime.add_near_heap_limit_callback(move |current, _initial| {
+            flag.store(true, Ordering::SeqCst);
+            handle.terminate_execution();
+            current * 2
+        });
+        Self {
+            runtime,
+            heap_exceeded,
+            calls: 0,
+        }
+    }
+
+    // 在新建的 context 中依次执行脚本，返回最后一个脚本的值（转为字符串）。
+    // 上一次调用留下的全局变量、被改写的内置对象不会影响这一次
+    fn run(&mut self, scripts: &[String]) -> Result<String, String> {
+        self.calls += 1;
+        let isolate: &mut v8::Isolate = self.runtime.v8_isolate();
+        let scope = &mut v8::HandleScope::new(isolate);
+        let context = v8::Context::new(scope);
+        let scope = &mut v8::ContextScope::new(scope, context);
+        let scope = &mut v8::TryCatch::new(scope);
+        let mut last = None;
+        for script in scripts {
+            let source = v8::String::new(scope, script).ok_or("script too large")?;
+            let value = v8::Script::compile(scope, source, None).and_then(|s| s.run(scope));
+            let Some(value) = value else {
+                // 被看门狗终止时没有异常对象
+                return Err(match scope.exception() {
+                    Some(exception) => exception.to_rust_string_lossy(scope),
+                    None => "script execution terminated".to_string(),
+                });
+            };
+            last = Some(value);
+        }
+        let value = last.ok_or("no script to execute")?;
+        Ok(value.to_rust_string_lossy(scope))
+    }
+
+    fn exhausted(&self) -> bool {
+        self.calls >= MAX_CALLS_PER_ISOLATE
+    }
+}
+
+fn worker_loop(index: usize, jobs: Arc<Mutex<mpsc::Receiver<Job>>>, watchdog: Watchdog) {
+    let mut isolate = Isolate::new();
+    loop {
+        let job = {
+            let jobs = jobs.lock().unwrap();
+            jobs.recv()
+        };
+        let Ok(job) = job else {
+            return;
+        };
+        // 调用方已放弃等待时不必执行
+        if job.reply.is_closed() {
+            continue;
+        }
+
+        watchdog.arm(
+            isolate.runtime.v8_isolate().thread_safe_handle(),
+            job.timeout,
+        );
+        let output = isolate.run(&job.scripts);
+        let timed_out = watchdog.disarm();
+        let heap_exceeded = isolate.heap_exceeded.load(Ordering::SeqCst);
+
+        let result = match output {
+            _ if heap_exceeded => Err(JsError::HeapLimit),
+            _ if timed_out => Err(JsError::Timeout(job.timeout)),
+            Ok(value) => Ok(value),
+            Err(e) => Err(JsError::Script(e)),
+        };
+        // 被终止过的 isolate 状态不可信，直接换新的
+        if timed_out || heap_exceeded || isolate.exhausted() {
+            if timed_out || heap_exceeded {
+                eprintln!(
+                    "[JS Service] Worker {} recycling isolate: {:?}",
+                    index, result
+                );
+            }
+            isolate = Isolate::new();
+        }
+        let _ = job.reply.send(result);
+    }
+}
+
+/// 进程内共享的脚本执行服务，首次使用时启动工作线程
+pub struct JsService {
+    jobs: Option<mpsc::Sender<Job>>,
+}
+
+impl JsService {
+    pub fn global() -> &'static JsService {
+        static SERVICE: OnceLock<JsService> = OnceLock::new();
+        SERVICE.get_or_init(JsService::spawn)
+    }
+
+    fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let mut started = 0;
+        for index in 0..POOL_SIZE {
+            let rx = rx.clone();
+            let spawned = Watchdog::spawn(format!("js-watchdog-{}", index)).and_then(|watchdog| {
+                thread::Builder::new()
+                    .name(format!("js-worker-{}", index))
+                    .spawn(move || worker_loop(index, rx, watchdog))
+            });
+            match spawned {
+                Ok(_) => started += 1,
+                Err(e) => eprintln!("[JS Service] Failed to start worker {}: {}", index, e),
+            }
+        }
+        Self {
+            jobs: (started > 0).then_some(tx),
+        }
+    }
+
+    /// 在一个新的 context 中依次执行 `scripts`，返回最后一个脚本的值。
+    /// 同一次调用内的脚本共享全局作用域，不同调用之间互不可见。
+    pub async fn eval(&self, scripts: Vec<String>) -> Result<String, JsError> {
+        let jobs = self.jobs.as_ref().ok_or(JsError::Unavailable)?;
+        let (reply, rx) = oneshot::channel();
+        jobs.send(Job {
+            scripts,
+            timeout: CALL_TIMEOUT,
+            reply,
+        })
+        .map_err(|_| JsError::Unavailable)?;
+        rx.await.map_err(|_| JsError::Unavailable)?
+    }
+}
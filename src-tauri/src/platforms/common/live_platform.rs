@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::platforms::bilibili::platform::BilibiliPlatform;
+use crate::platforms::common::types::LiveStreamInfo;
+use crate::platforms::common::types_rust::{
+    CommonLiveListRust, CommonPlatformCategoryRust, CommonRoomInfoRust, CommonStreamerRust,
+    SupportedPlatformRust,
+};
+use crate::platforms::douyin::platform::DouyinPlatform;
+use crate::platforms::douyu::platform::DouyuPlatform;
+use crate::platforms::huya::platform::HuyaPlatform;
+
+/// 各直播平台的统一能力：房间信息、取流、搜索、分类与直播列表。
+/// 各平台模块内的 `platform.rs` 负责把已有命令的返回值转换为这里的通用类型。
+#[allow(async_fn_in_trait)]
+pub trait LivePlatform {
+    fn platform(&self) -> SupportedPlatformRust;
+
+    async fn room_info(&self, room_id: &str) -> Result<CommonRoomInfoRust, String>;
+
+    async fn stream_url(
+        &self,
+        room_id: &str,
+        quality: &str,
+        line: Option<&str>,
+    ) -> Result<LiveStreamInfo, String>;
+
+    async fn search(&self, keyword: &str, page: u32) -> Result<Vec<CommonStreamerRust>, String>;
+
+    async fn categories(&self) -> Result<Vec<CommonPlatformCategoryRust>, String>;
+
+    /// `parent_id` 仅对需要两级分区的平台有意义（B 站 parent_area_id、抖音 partition_type）
+    async fn live_list(
+        &self,
+        category_id: &str,
+        parent_id: Option<&str>,
+        page: u32,
+    ) -> Result<CommonLiveListRust, String>;
+}
+
+// 前端传入的请求：{ action: "room_info", room_id: "..." }
+#[derive(Deserialize, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LivePlatformRequest {
+    RoomInfo {
+        room_id: String,
+    },
+    StreamUrl {
+        room_id: String,
+        quality: Option<String>,
+        line: Option<String>,
+    },
+    Search {
+        keyword: String,
+        page: Option<u32>,
+    },
+    Categories,
+    LiveList {
+        category_id: String,
+        parent_id: Option<String>,
+        page: Option<u32>,
+    },
+}
+
+// 返回给前端：{ action: "room_info", data: { ... } }
+#[derive(Serialize, Debug)]
+#[serde(tag = "action", content = "data", rename_all = "snake_case")]
+pub enum LivePlatformResponse {
+    RoomInfo(CommonRoomInfoRust),
+    StreamUrl(LiveStreamInfo),
+    Search(Vec<CommonStreamerRust>),
+    Categories(Vec<CommonPlatformCategoryRust>),
+    LiveList(CommonLiveListRust),
+}
+
+const DEFAULT_QUALITY: &str = "原画";
+
+async fn dispatch<P: LivePlatform>(
+    platform: &P,
+    request: LivePlatformRequest,
+) -> Result<LivePlatformResponse, String> {
+    match request {
+        LivePlatformRequest::RoomInfo { room_id } => platform
+            .room_info(&room_id)
+            .await
+            .map(LivePlatformResponse::RoomInfo),
+        LivePlatformRequest::StreamUrl {
+            room_id,
+            quality,
+            line,
+        } => platform
+            .stream_url(
+                &room_id,
+                quality.as_deref().unwrap_or(DEFAULT_QUALITY),
+                line.as_deref(),
+            )
+            .await
+            .map(LivePlatformResponse::StreamUrl),
+        LivePlatformRequest::Search { keyword, page } => platform
+            .search(&keyword, page.unwrap_or(1).max(1))
+            .await
+            .map(LivePlatformResponse::Search),
+        LivePlatformRequest::Categories => platform
+            .categories()
+            .await
+            .map(LivePlatformResponse::Categories),
+        LivePlatformRequest::LiveList {
+            category_id,
+            parent_id,
+            page,
+        } => platform
+            .live_list(&category_id, parent_id.as_deref(), page.unwrap_or(1).max(1))
+            .await
+            .map(LivePlatformResponse::LiveList),
+    }
+}
+
+/// 统一的平台调度命令，前端只需传入平台与请求类型，无需再按平台分支调用不同命令。
+#[tauri::command]
+pub async fn live_platform_request(
+    platform: SupportedPlatformRust,
+    request: LivePlatformRequest,
+    cookie: Option<String>,
+    app_handle: AppHandle,
+) -> Result<LivePlatformResponse, String> {
+    match platform {
+        SupportedPlatformRust::Douyu => dispatch(&DouyuPlatform::new(app_handle), request).await,
+        SupportedPlatformRust::Huya => dispatch(&HuyaPlatform::new(app_handle), request).await,
+        SupportedPlatformRust::Douyin => dispatch(&DouyinPlatform::new(app_handle), request).await,
+        SupportedPlatformRust::Bilibili => {
+            dispatch(&BilibiliPlatform::new(app_handle, cookie), request).await
+        }
+    }
+}
@@ -56,40 +56,6 @@ pub struct LiveStreamInfo {
     pub normalized_room_id: Option<String>,
     // 新增：直播间的 web_rid（用于关注列表以 web_id 为主键）
     pub web_rid: Option<String>,
-}
-
-#[derive(Default, Clone)]
-#[allow(dead_code)]
-pub struct StreamUrlStore {
-    pub url: std::sync::Arc<std::sync::Mutex<String>>,
-}
-
-// Moved from main.rs
-// State for the Douyin Danmaku listener
-#[derive(Default)]
-#[allow(dead_code)]
-pub struct DouyinDanmakuState(pub std::sync::Mutex<Option<tokio::sync::mpsc::Sender<()>>>);
-
-// State for the Bilibili Danmaku listener
-#[derive(Default)]
-#[allow(dead_code)]
-pub struct BilibiliDanmakuState(pub std::sync::Mutex<Option<tokio::sync::mpsc::Sender<()>>>);
-
-// State for the Douyu Danmaku listener
-#[derive(Default)]
-#[allow(dead_code)]
-pub struct DouyuDanmakuState(pub std::sync::Mutex<Option<tokio::sync::mpsc::Sender<()>>>);
-
-// State for the Huya Danmaku listener
-#[derive(Default)]
-#[allow(dead_code)]
-pub struct HuyaDanmakuState(pub std::sync::Mutex<Option<tokio::sync::mpsc::Sender<()>>>);
-
-#[derive(Serialize, Clone, Debug, specta::Type)]
-pub struct DanmakuFrontendPayload {
-    pub room_id: String,
-    pub user: String,
-    pub content: String,
-    pub user_level: i64,
-    pub fans_club_level: i32,
+    // 本地代理会话 id，停止播放时前端用它调用 unregister_stream
+    pub proxy_session_id: Option<String>,
 }
@@ -1,13 +1,27 @@
 use serde::{Deserialize, Serialize};
 
 // Enum mirroring TypeScript SupportedPlatform
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SupportedPlatformRust {
     #[serde(rename = "douyu")]
     Douyu,
     #[serde(rename = "bilibili")]
     Bilibili,
-    // Add other platforms as they are supported
+    #[serde(rename = "huya")]
+    Huya,
+    #[serde(rename = "douyin")]
+    Douyin,
+}
+
+impl SupportedPlatformRust {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SupportedPlatformRust::Douyu => "douyu",
+            SupportedPlatformRust::Bilibili => "bilibili",
+            SupportedPlatformRust::Huya => "huya",
+            SupportedPlatformRust::Douyin => "douyin",
+        }
+    }
 }
 
 // Struct mirroring TypeScript CommonPlatformCategory
@@ -32,3 +46,35 @@ pub struct CommonCategoryGroupRust {
     pub platform: SupportedPlatformRust,
     pub categories: Vec<CommonPlatformCategoryRust>,
 }
+
+// Struct mirroring TypeScript CommonStreamer (streamerTypes.ts)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommonStreamerRust {
+    pub room_id: String,
+    pub title: String,
+    pub nickname: String,
+    pub avatar: String,
+    pub room_cover: String,
+    pub viewer_count_str: String,
+    pub platform: SupportedPlatformRust,
+    pub is_live: Option<bool>,
+}
+
+// 分页后的直播间列表
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommonLiveListRust {
+    pub rooms: Vec<CommonStreamerRust>,
+    pub has_more: bool,
+    pub page: u32,
+}
+
+// 房间基础信息（标题/主播/头像/开播状态）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommonRoomInfoRust {
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    pub title: Option<String>,
+    pub anchor_name: Option<String>,
+    pub avatar: Option<String>,
+    pub is_live: bool,
+}
@@ -3,7 +3,7 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-fn rc4_encrypt(plaintext: &str, key: &str) -> String {
+pub(crate) fn rc4_encrypt(plaintext: &str, key: &str) -> String {
     // Mirror Python behavior: iterate over scalars and truncate each to a byte.
     let pbytes: Vec<u8> = plaintext.chars().map(|c| c as u32 as u8).collect();
     let kbytes: Vec<u8> = key.chars().map(|c| c as u32 as u8).collect();
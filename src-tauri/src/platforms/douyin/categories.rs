@@ -0,0 +1,377 @@
+// 抖音直播分区。抖音没有可用的分区列表接口，数据与前端 douyinCategoriesData.ts 保持一致。
+// id 为分区链接 /category/ 之后的部分，二级分区的最后两段依次是 partition_type 与 partition。
+
+pub struct DouyinCategoryGroup {
+    pub name: &'static str,
+    pub id: &'static str,
+    /// (名称, id)
+    pub subcategories: &'static [(&'static str, &'static str)],
+}
+
+pub const DOUYIN_CATEGORIES: &[DouyinCategoryGroup] = &[
+    DouyinCategoryGroup {
+        name: "射击游戏",
+        id: "1_1",
+        subcategories: &[
+            ("和平精英", "1_1_1_1010032"),
+            ("CSGO", "1_1_1_1010003"),
+            ("守望先锋", "1_1_1_1010339"),
+            ("穿越火线", "1_1_1_1010037"),
+            ("暗区突围：无限", "1_1_1_1011124"),
+            ("三角洲行动", "1_1_1_1011032"),
+            ("无畏契约", "1_1_1_1010017"),
+            ("绝地求生", "1_1_1_1010026"),
+            ("暗区突围", "1_1_1_1010018"),
+            ("穿越火线：枪战王者", "1_1_1_1010015"),
+            ("Apex英雄", "1_1_1_1010002"),
+            ("逆战", "1_1_1_1010132"),
+            ("使命召唤手游", "1_1_1_1010080"),
+            ("萤火突击", "1_1_1_1010214"),
+            ("荒野行动", "1_1_1_1010064"),
+            ("反恐精英OL", "1_1_1_1010336"),
+            ("使命召唤", "1_1_1_1010329"),
+            ("逃离塔科夫", "1_1_1_1010104"),
+            ("漫威争锋", "1_1_1_1011240"),
+            ("界外狂潮", "1_1_1_1011310"),
+            ("生死狙击2", "1_1_1_1010068"),
+            ("彩虹六号：围攻", "1_1_1_1010402"),
+            ("生死狙击", "1_1_1_1010409"),
+            ("高能英雄", "1_1_1_1010198"),
+            ("战术小队", "1_1_1_1010445"),
+            ("The Finals", "1_1_1_1010593"),
+            ("堡垒之夜", "1_1_1_1010383"),
+            ("战地5", "1_1_1_1010177"),
+            ("战地1", "1_1_1_1010367"),
+            ("远光84", "1_1_1_1010187"),
+            ("超凡先锋", "1_1_1_1010144"),
+            ("香肠派对", "1_1_1_1010050"),
+            ("卡拉彼丘", "1_1_1_1010168"),
+            ("迷你枪战精英", "1_1_1_1010460"),
+            ("不羁联盟", "1_1_1_1010592"),
+            ("全民枪神：边境王者", "1_1_1_1010645"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "竞技游戏",
+        id: "1_2",
+        subcategories: &[
+            ("英雄联盟手游", "1_2_1_1010023"),
+            ("永劫无间", "1_2_1_1010016"),
+            ("魔兽争霸3", "1_2_1_1010350"),
+            ("第五人格", "1_2_1_1010041"),
+            ("金铲铲之战", "1_2_1_1010055"),
+            ("云顶之弈", "1_2_1_1010005"),
+            ("英雄联盟", "1_2_1_1010014"),
+            ("王者荣耀", "1_2_1_1010045"),
+            ("QQ飞车端游", "1_2_1_1010146"),
+            ("巅峰极速", "1_2_1_1010007"),
+            ("DOTA1", "1_2_1_1010341"),
+            ("QQ飞车手游", "1_2_1_1010033"),
+            ("DOTA2", "1_2_1_1010093"),
+            ("炉石传说", "1_2_1_1010397"),
+            ("永劫无间手游", "1_2_1_1010278"),
+            ("坦克世界", "1_2_1_1010340"),
+            ("红色警戒2", "1_2_1_1010102"),
+            ("决胜巅峰", "1_2_1_1010292"),
+            ("三国杀", "1_2_1_1010061"),
+            ("跑跑卡丁车", "1_2_1_1010331"),
+            ("跑跑卡丁车官方竞速版", "1_2_1_1010131"),
+            ("战争雷霆", "1_2_1_1010170"),
+            ("极品飞车：集结", "1_2_1_1010686"),
+            ("星际争霸", "1_2_1_1010483"),
+            ("至暗时刻", "1_2_1_1010435"),
+            ("实况足球", "1_2_1_1010030"),
+            ("极限竞速：地平线5", "1_2_1_1010429"),
+            ("战舰世界", "1_2_1_1010418"),
+            ("恐惧饥荒", "1_2_1_1010430"),
+            ("全明星街球派对", "1_2_1_1010180"),
+            ("鹅鸭杀", "1_2_1_1010167"),
+            ("宝可梦大集结", "1_2_1_1010027"),
+            ("狼人杀", "1_2_1_1010313"),
+            ("决战！平安京", "1_2_1_1010057"),
+            ("哈利波特：魔法觉醒", "1_2_1_1010054"),
+            ("极限竞速：地平线4", "1_2_1_1010353"),
+            ("皇室战争", "1_2_1_1010230"),
+            ("极品飞车", "1_2_1_1010264"),
+            ("猫和老鼠", "1_2_1_1010327"),
+            ("逃跑吧！少年", "1_2_1_1010058"),
+            ("荒野乱斗", "1_2_1_1010138"),
+            ("星际争霸2", "1_2_1_1010509"),
+            ("最强NBA", "1_2_1_1010107"),
+            ("王牌竞速", "1_2_1_1010524"),
+            ("曙光英雄", "1_2_1_1010381"),
+            ("狂野飙车9：竞速传奇", "1_2_1_1010532"),
+            ("梦三国", "1_2_1_1010597"),
+            ("坦克世界：闪电战", "1_2_1_1010395"),
+            ("红色警戒3", "1_2_1_1010510"),
+            ("太空杀", "1_2_1_1010208"),
+            ("游戏王：决斗链接", "1_2_1_1010378"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "单机游戏",
+        id: "1_3",
+        subcategories: &[
+            ("植物大战僵尸", "1_3_1_1010324"),
+            ("黑神话：悟空", "1_3_1_1010358"),
+            ("俄罗斯钓鱼4", "1_3_1_1011048"),
+            ("星露谷物语", "1_3_1_1010791"),
+            ("方舟", "1_3_1_1010100"),
+            ("饥荒", "1_3_1_1010335"),
+            ("艾尔登法环", "1_3_1_1010087"),
+            ("人渣", "1_3_1_1010326"),
+            ("拳皇97", "1_3_1_1010334"),
+            ("荒野大镖客2", "1_3_1_1010363"),
+            ("泰拉瑞亚", "1_3_1_1010396"),
+            ("实况足球", "1_3_1_1010030"),
+            ("极限竞速：地平线5", "1_3_1_1010429"),
+            ("只狼：影逝二度", "1_3_1_1010149"),
+            ("猛兽派对", "1_3_1_1010038"),
+            ("幻兽帕鲁", "1_3_1_1010981"),
+            ("双影奇境", "1_3_1_1010436"),
+            ("绝地潜兵2", "1_3_1_1011000"),
+            ("骑马与砍杀2：霸主", "1_3_1_1010401"),
+            ("都市：天际线", "1_3_1_1010142"),
+            ("木筏求生", "1_3_1_1010408"),
+            ("塞尔达传说：旷野之息", "1_3_1_1010081"),
+            ("拳皇98", "1_3_1_1010407"),
+            ("The Finals", "1_3_1_1010593"),
+            ("战地5", "1_3_1_1010177"),
+            ("战神", "1_3_1_1010788"),
+            ("英雄无敌3", "1_3_1_1011119"),
+            ("宝可梦朱紫", "1_3_1_1010847"),
+            ("街头霸王2", "1_3_1_1010352"),
+            ("街头霸王6", "1_3_1_1010361"),
+            ("极限竞速：地平线4", "1_3_1_1010353"),
+            ("命运2", "1_3_1_1010422"),
+            ("战地1", "1_3_1_1010367"),
+            ("星际战甲", "1_3_1_1010250"),
+            ("森林之子", "1_3_1_1010783"),
+            ("链在一起", "1_3_1_1011170"),
+            ("缉私警察", "1_3_1_1010769"),
+            ("不祥之夜：回魂", "1_3_1_1011089"),
+            ("赛博朋克2077", "1_3_1_1010128"),
+            ("怪物猎人：崛起", "1_3_1_1010420"),
+            ("全面战争：三国", "1_3_1_1010779"),
+            ("潜水员戴夫", "1_3_1_1010626"),
+            ("塞尔达传说：王国之泪", "1_3_1_1010082"),
+            ("仁王", "1_3_1_1010774"),
+            ("三国志14", "1_3_1_1010514"),
+            ("死亡搁浅", "1_3_1_1010777"),
+            ("不羁联盟", "1_3_1_1010592"),
+            ("禁闭求生", "1_3_1_1010247"),
+            ("天国拯救2", "1_3_1_1011399"),
+            ("人类：一败涂地", "1_3_1_1010130"),
+            ("第一后裔", "1_3_1_1011175"),
+            ("NBA 2K22", "1_3_1_1010417"),
+            ("鬼谷八荒", "1_3_1_1010224"),
+            ("无主之地3", "1_3_1_1010512"),
+            ("暖雪", "1_3_1_1010472"),
+            ("冰与火之舞", "1_3_1_1011146"),
+            ("消逝的光芒2：人与仁之战", "1_3_1_1010433"),
+            ("鬼泣5", "1_3_1_1010246"),
+            ("猎人：荒野的召唤", "1_3_1_1010441"),
+            ("匹诺曹的谎言", "1_3_1_1010320"),
+            ("从军", "1_3_1_1011394"),
+            ("泰坦陨落", "1_3_1_1010784"),
+            ("超级马里奥制造", "1_3_1_1010442"),
+            ("博德之门3", "1_3_1_1010640"),
+            ("女神异闻录5", "1_3_1_1010171"),
+            ("刺客信条：奥德赛", "1_3_1_1010485"),
+            ("最终幻想 16", "1_3_1_1010316"),
+            ("i wanna", "1_3_1_1010776"),
+            ("看门狗2", "1_3_1_1010790"),
+            ("掘地求升", "1_3_1_1011136"),
+            ("Shooterspool", "1_3_1_1011108"),
+            ("极限国度", "1_3_1_1010434"),
+            ("流放之路", "1_3_1_1010411"),
+            ("致命公司", "1_3_1_1010846"),
+            ("三国志11", "1_3_1_1010515"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "棋牌游戏",
+        id: "1_4",
+        subcategories: &[
+            ("JJ象棋", "1_4_1_1010063"),
+            ("JJ斗地主", "1_4_1_1010004"),
+            ("途游斗地主", "1_4_1_1010012"),
+            ("JJ麻将", "1_4_1_1010094"),
+            ("指尖四川麻将", "1_4_1_1010040"),
+            ("天天象棋", "1_4_1_1010060"),
+            ("欢乐斗地主", "1_4_1_1010062"),
+            ("微乐斗地主", "1_4_1_1010714"),
+            ("开运麻将", "1_4_1_1010711"),
+            ("微乐四川麻将", "1_4_1_1010710"),
+            ("芒果斗地主", "1_4_1_1010028"),
+            ("多乐升级", "1_4_1_1010721"),
+            ("腾讯欢乐麻将", "1_4_1_1010059"),
+            ("多乐够级", "1_4_1_1010720"),
+            ("禅游斗地主", "1_4_1_1010098"),
+            ("富豪麻将", "1_4_1_1010101"),
+            ("途游象棋", "1_4_1_1010553"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "休闲益智",
+        id: "1_5",
+        subcategories: &[
+            ("蛋仔派对", "1_5_1_1010011"),
+            ("我的世界", "1_5_1_1010022"),
+            ("元梦之星", "1_5_1_1010263"),
+            ("球球大作战", "1_5_1_1010010"),
+            ("沙盒与副本：英勇之地", "1_5_1_1010699"),
+            ("开心消消乐", "1_5_1_1010520"),
+            ("迷你世界", "1_5_1_1010046"),
+            ("忍者必须死3", "1_5_1_1010129"),
+            ("贪吃蛇大作战", "1_5_1_1010056"),
+            ("天天台球", "1_5_1_1010806"),
+            ("罗布乐思", "1_5_1_1010523"),
+            ("地铁跑酷", "1_5_1_1010099"),
+            ("台球帝国", "1_5_1_1010921"),
+            ("天天酷跑", "1_5_1_1010410"),
+            ("创世战车", "1_5_1_1010639"),
+            ("腾讯桌球", "1_5_1_1010121"),
+            ("创造与魔法", "1_5_1_1010399"),
+            ("群雄逐鹿", "1_5_1_1010895"),
+            ("阿瑞斯病毒2", "1_5_1_1010272"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "角色扮演",
+        id: "1_6",
+        subcategories: &[
+            ("燕云十六声", "1_6_1_1010271"),
+            ("火影忍者手游", "1_6_1_1010042"),
+            ("魔兽世界", "1_6_1_1010150"),
+            ("原神", "1_6_1_1010039"),
+            ("地下城与勇士", "1_6_1_1010092"),
+            ("大话西游2", "1_6_1_1010205"),
+            ("梦幻西游手游", "1_6_1_1010051"),
+            ("逆水寒手游", "1_6_1_1010083"),
+            ("地下城与勇士：起源", "1_6_1_1010234"),
+            ("鸣潮", "1_6_1_1010159"),
+            ("梦幻西游", "1_6_1_1010053"),
+            ("光遇", "1_6_1_1010035"),
+            ("剑网3", "1_6_1_1010249"),
+            ("七日世界", "1_6_1_1010558"),
+            ("命运方舟", "1_6_1_1010233"),
+            ("诛仙世界", "1_6_1_1010151"),
+            ("明日之后", "1_6_1_1010006"),
+            ("火炬之光：无限", "1_6_1_1010241"),
+            ("绝区零", "1_6_1_1010155"),
+            ("无限暖暖", "1_6_1_1010253"),
+            ("问道", "1_6_1_1010116"),
+            ("逆水寒", "1_6_1_1010364"),
+            ("龙之谷世界", "1_6_1_1010181"),
+            ("洛克王国", "1_6_1_1010203"),
+            ("航海王：壮志雄心", "1_6_1_1011139"),
+            ("大话西游", "1_6_1_1010143"),
+            ("神武4", "1_6_1_1010125"),
+            ("只狼：影逝二度", "1_6_1_1010149"),
+            ("暗黑破坏神：不朽", "1_6_1_1010096"),
+            ("梦幻新诛仙", "1_6_1_1010315"),
+            ("星球：重启", "1_6_1_1010193"),
+            ("冒险岛：枫之传说", "1_6_1_1010311"),
+            ("晶核", "1_6_1_1010044"),
+            ("一梦江湖", "1_6_1_1010412"),
+            ("射雕", "1_6_1_1010245"),
+            ("石器时代：觉醒", "1_6_1_1010487"),
+            ("新完美世界", "1_6_1_1010257"),
+            ("妄想山海", "1_6_1_1010533"),
+            ("航海王热血航线", "1_6_1_1010231"),
+            ("命运2", "1_6_1_1010422"),
+            ("仙剑世界", "1_6_1_1010212"),
+            ("黎明觉醒：生机", "1_6_1_1010029"),
+            ("新大话西游3", "1_6_1_1010568"),
+            ("星际战甲", "1_6_1_1010250"),
+            ("崩坏3", "1_6_1_1010020"),
+            ("长安幻想", "1_6_1_1010097"),
+            ("塔瑞斯世界", "1_6_1_1010270"),
+            ("新天龙八部", "1_6_1_1010266"),
+            ("月圆之夜", "1_6_1_1010024"),
+            ("元气骑士", "1_6_1_1010343"),
+            ("归龙潮", "1_6_1_1010153"),
+            ("尘白禁区", "1_6_1_1010086"),
+            ("战双帕弥什", "1_6_1_1010089"),
+            ("失落城堡", "1_6_1_1010223"),
+            ("元气骑士前传", "1_6_1_1010182"),
+            ("激战2", "1_6_1_1010405"),
+            ("暖雪", "1_6_1_1010472"),
+            ("苍翼：混沌效应", "1_6_1_1010604"),
+            ("战斗法则", "1_6_1_1010646"),
+            ("仙境传说：爱如初见", "1_6_1_1010679"),
+            ("无主之地3", "1_6_1_1010512"),
+            ("行侠仗义五千年", "1_6_1_1010248"),
+            ("博德之门3", "1_6_1_1010640"),
+            ("天涯明月刀", "1_6_1_1010119"),
+            ("匹诺曹的谎言", "1_6_1_1010320"),
+            ("女神异闻录5", "1_6_1_1010171"),
+            ("一念逍遥", "1_6_1_1010112"),
+            ("天龙八部2：飞龙战天", "1_6_1_1010120"),
+            ("斗罗大陆：史莱克学院", "1_6_1_1010259"),
+            ("全境封锁2", "1_6_1_1010199"),
+            ("流放之路", "1_6_1_1010411"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "策略卡牌",
+        id: "1_7",
+        subcategories: &[
+            ("崩坏：星穹铁道", "1_7_1_1010043"),
+            ("植物大战僵尸", "1_7_1_1010324"),
+            ("三国志·战略版", "1_7_1_1010009"),
+            ("阴阳师", "1_7_1_1010025"),
+            ("明日方舟", "1_7_1_1010013"),
+            ("漫威终极逆转", "1_7_1_1011150"),
+            ("率土之滨", "1_7_1_1010021"),
+            ("万国觉醒", "1_7_1_1010105"),
+            ("恋与深空", "1_7_1_1010084"),
+            ("海岛奇兵", "1_7_1_1010385"),
+            ("部落冲突", "1_7_1_1010145"),
+            ("斗罗大陆：魂师对决", "1_7_1_1010365"),
+            ("植物大战僵尸2", "1_7_1_1010067"),
+            ("赛尔号", "1_7_1_1010521"),
+            ("奥奇传说", "1_7_1_1010419"),
+            ("如鸢", "1_7_1_1010192"),
+            ("蔚蓝档案", "1_7_1_1010289"),
+            ("无尽的拉格朗日", "1_7_1_1010008"),
+            ("重返未来1999", "1_7_1_1010196"),
+            ("战火勋章", "1_7_1_1010108"),
+            ("文明", "1_7_1_1010265"),
+            ("梦幻模拟战", "1_7_1_1010394"),
+            ("航海王：燃烧意志", "1_7_1_1010574"),
+            ("三国志·战棋版", "1_7_1_1010127"),
+            ("闪耀！优俊少女", "1_7_1_1010291"),
+            ("三国志11", "1_7_1_1010515"),
+            ("小冰冰传奇", "1_7_1_1010673"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "娱乐天地",
+        id: "3_10000",
+        subcategories: &[
+            ("时尚", "3_10000_2_2823"),
+            ("美食", "3_10000_2_2786"),
+            ("旅行", "3_10000_2_2751"),
+            ("舞蹈", "3_10000_2_2726"),
+            ("户外", "3_10000_2_2742"),
+            ("运动", "3_10000_2_2791"),
+            ("音乐", "3_10000_2_2707"),
+            ("语音互动", "3_10000_2_2842"),
+        ],
+    },
+    DouyinCategoryGroup {
+        name: "科技文化",
+        id: "3_10001",
+        subcategories: &[("人文艺术", "3_10001_2_2756"), ("教育", "3_10001_2_2800")],
+    },
+];
+
+/// 从二级分区 id 中取出 (partition, partition_type)
+pub fn split_partition(category_id: &str) -> Option<(&str, &str)> {
+    let mut parts = category_id.rsplit('_');
+    let partition = parts.next()?;
+    let partition_type = parts.next()?;
+    Some((partition, partition_type))
+}
@@ -2,21 +2,24 @@ use flate2::read::GzDecoder;
 use futures_util::{stream::SplitStream, StreamExt};
 use prost::Message as ProstMessage; // For decode/encode
 use std::io::Read;
-use tauri::Emitter;
 use tokio::sync::mpsc::Sender;
-use tokio_tungstenite::tungstenite::protocol::Message as WsMessage; // Import the Emitter trait for app_handle.emit()
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+
+use crate::platforms::common::danmaku::DanmakuSink;
 
 use crate::platforms::douyin::danmu::gen::{PushFrame, Response}; // Removed ::douyin
 use crate::platforms::douyin::danmu::message_parsers;
 use crate::platforms::douyin::danmu::websocket_connection::WsStream; // Corrected path // Corrected path
 
-// This function will handle the message receiving loop and parsing
+// This function will handle the message receiving loop and parsing.
+// 只有出错或连接被断开时才会返回（总是 Err）；用户停止由监听任务的 shutdown 信号处理
 pub async fn handle_received_messages(
     mut read_stream: SplitStream<WsStream>,
     ack_tx: Sender<WsMessage>,
-    app_handle: tauri::AppHandle, // Added AppHandle
-    room_id: String,              // Added room_id parameter
+    sink: DanmakuSink,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let room_id = sink.room_id().to_string();
+    let mut combo_tracker = message_parsers::GiftComboTracker::default();
     println!(
         "[Douyin Danmaku] Message handler started for room_id: {}",
         room_id
@@ -59,33 +62,71 @@ pub async fn handle_received_messages(
                                             }
                                         }
                                         for msg in response.messages_list {
-                                            // println!("  -> Method: {}, Payload Length: {}", msg.method, msg.payload.len());
-                                            let mut danmaku_to_send = None;
-                                            if msg.method == "WebcastChatMessage" {
-                                                match message_parsers::parse_chat_message(
-                                                    &msg.payload,
-                                                    &room_id,
-                                                ) {
-                                                    Ok(Some(chat_payload)) => {
-                                                        danmaku_to_send = Some(chat_payload);
-                                                    }
-                                                    Ok(None) => { /* Not a message to display or ignored */
-                                                    }
-                                                    Err(_e) => { /* Error already logged in parser */
-                                                    }
+                                            let parsed = match msg.method.as_str() {
+                                                "WebcastChatMessage" => {
+                                                    message_parsers::parse_chat_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
                                                 }
-                                            }
-                                            // Add other message types here if needed, similar to ChatMessage
-                                            // else if msg.method == "WebcastMemberMessage" { ... }
-
-                                            if let Some(payload) = danmaku_to_send {
-                                                // Use app_handle.emit for Tauri v2 style global event emitting
-                                                if let Err(e) = app_handle
-                                                    .emit("danmaku-message", payload.clone())
-                                                {
-                                                    // payload needs to be Clone for emit
-                                                    eprintln!("[Douyin Danmaku] Failed to emit danmaku event: {}", e);
+                                                "WebcastGiftMessage" => {
+                                                    message_parsers::parse_gift_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                        &mut combo_tracker,
+                                                    )
+                                                }
+                                                "WebcastMemberMessage" => {
+                                                    message_parsers::parse_member_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
+                                                }
+                                                "WebcastLikeMessage" => {
+                                                    message_parsers::parse_like_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
+                                                }
+                                                "WebcastSocialMessage" => {
+                                                    message_parsers::parse_social_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
                                                 }
+                                                "WebcastFansclubMessage" => {
+                                                    message_parsers::parse_fansclub_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
+                                                }
+                                                "WebcastRoomUserSeqMessage" => {
+                                                    message_parsers::parse_room_user_seq_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
+                                                }
+                                                "WebcastRoomStatsMessage" => {
+                                                    message_parsers::parse_room_stats_message(
+                                                        &msg.payload,
+                                                        &room_id,
+                                                    )
+                                                }
+                                                _ => Ok(None),
+                                            };
+                                            let danmaku_to_send = match parsed {
+                                                Ok(event) => event,
+                                                Err(e) => {
+                                                    eprintln!(
+                                                        "[Douyin Danmaku] Failed to parse {}: {}",
+                                                        msg.method, e
+                                                    );
+                                                    None
+                                                }
+                                            };
+
+                                            if let Some(event) = danmaku_to_send {
+                                                sink.emit(event);
                                             }
                                         }
                                     }
@@ -109,22 +150,14 @@ pub async fn handle_received_messages(
                         eprintln!("[Douyin Danmaku] Failed to send PONG from message_handler");
                     }
                 } else if let WsMessage::Close(close_frame) = ws_msg {
-                    println!(
-                        "[Douyin Danmaku] WebSocket closed by server: {:?}",
-                        close_frame
-                    );
-                    break;
+                    // 服务器主动断开也按连接失败处理，由监听任务重连
+                    return Err(format!("WebSocket closed by server: {:?}", close_frame).into());
                 }
             }
             Err(e) => {
-                eprintln!(
-                    "[Douyin Danmaku] WebSocket receive error in message_handler: {}",
-                    e
-                );
-                break;
+                return Err(format!("WebSocket receive error: {}", e).into());
             }
         }
     }
-    println!("[Douyin Danmaku] Message handler finished.");
-    Ok(())
+    Err("WebSocket stream ended unexpectedly".into())
 }
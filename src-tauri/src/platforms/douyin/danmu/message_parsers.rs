@@ -1,49 +1,82 @@
-use super::gen::{ChatMessage, LikeMessage, MemberMessage, RoomStatsMessage}; // Updated to directly use types from gen
-use crate::platforms::common::DanmakuFrontendPayload;
-use prost::Message as ProstMessage; // For .decode() // Use shared payload type
+use super::gen::{
+    ChatMessage, FansclubMessage, GiftMessage, LikeMessage, MemberMessage, RoomStatsMessage,
+    RoomUserSeqMessage, SocialMessage, User,
+}; // Updated to directly use types from gen
+use crate::platforms::common::danmaku::{DanmakuEvent, DanmakuEventKind, DanmakuUser};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+use prost::Message as ProstMessage; // For .decode()
+use std::collections::HashMap;
+
+type ParseResult = Result<Option<DanmakuEvent>, Box<dyn std::error::Error + Send + Sync>>;
+
+// 抖音 1 钻 = 0.1 元
+const DIAMOND_TO_CNY: f64 = 0.1;
+// 连击记录上限，防止长时间运行时未收到 repeat_end 的记录无限增长
+const MAX_TRACKED_COMBOS: usize = 2048;
+
+fn douyin_event(
+    room_id: &str,
+    kind: DanmakuEventKind,
+    user: DanmakuUser,
+    content: impl Into<String>,
+) -> DanmakuEvent {
+    DanmakuEvent::new(SupportedPlatformRust::Douyin, room_id, kind, user, content)
+}
+
+// 将 protobuf 中的 User 转换为统一的发送者信息
+fn to_danmaku_user(user: &User) -> DanmakuUser {
+    // 获取用户等级 (来自 demo)
+    let user_level = user.pay_grade.as_ref().map(|pg| pg.level).unwrap_or(0);
+    // 粉丝团 (fans_club.data)：团名作为勋章名，团等级作为勋章等级
+    let fans_club = user.fans_club.as_ref().and_then(|fc| fc.data.as_ref());
+    let user_id = if user.id_str.is_empty() {
+        user.id.to_string()
+    } else {
+        user.id_str.clone()
+    };
+    DanmakuUser {
+        nickname: user.nick_name.clone(),
+        user_id: Some(user_id),
+        avatar: user
+            .avatar_thumb
+            .as_ref()
+            .and_then(|img| img.url_list_list.first().cloned()),
+        user_level,
+        medal_name: fans_club
+            .map(|fcd| fcd.club_name.clone())
+            .filter(|name| !name.is_empty()),
+        fans_club_level: fans_club.map(|fcd| fcd.level).unwrap_or(0),
+        noble_level: 0,
+    }
+}
 
 // Parser for ChatMessage
-pub fn parse_chat_message(
-    payload: &[u8],
-    current_room_id: &str,
-) -> Result<Option<DanmakuFrontendPayload>, Box<dyn std::error::Error + Send + Sync>> {
+pub fn parse_chat_message(payload: &[u8], current_room_id: &str) -> ParseResult {
     match ChatMessage::decode(payload) {
         Ok(chat_msg) => {
-            if let Some(user) = chat_msg.user {
-                // 获取用户等级 (来自 demo)
-                let user_level = user.pay_grade.as_ref().map(|pg| pg.level).unwrap_or(0);
-                // 获取粉丝牌等级 (来自 demo)
-                // 注意：demo中的 fans_club.data.level 路径，确保你的 proto 定义一致
-                // 如果你的 User 结构体中 fans_club 直接是 FansClubData 类型，则不需要 .data
-                // 假设 FansClub 结构体包含一个 Option<FansClubData> 类型的字段 data
-                let fans_club_level = user
-                    .fans_club
-                    .as_ref()
-                    .and_then(|fc| fc.data.as_ref()) // 如果 FansClub 直接是 FansClubData，则为 .map(|fc| fc.level)
-                    .map(|fcd| fcd.level)
-                    .unwrap_or(0);
-
-                Ok(Some(DanmakuFrontendPayload {
-                    room_id: current_room_id.to_string(), // Populate room_id
-                    user: user.nick_name.clone(),
-                    content: chat_msg.content.clone(),
-                    user_level,
-                    fans_club_level,
-                    // r#type: "chat".to_string(),
-                }))
+            // 全屏弹幕颜色（如 "#FFFFFF"），为空时使用前端默认颜色
+            let color = Some(chat_msg.full_screen_text_color.clone()).filter(|c| !c.is_empty());
+            if let Some(user) = chat_msg.user.as_ref() {
+                Ok(Some(
+                    DanmakuEvent::chat(
+                        SupportedPlatformRust::Douyin,
+                        current_room_id,
+                        to_danmaku_user(user),
+                        chat_msg.content.clone(),
+                    )
+                    .with_color(color),
+                ))
             } else {
-                // 对于没有用户信息的聊天消息 (例如系统消息)，也可能需要发送，但等级为0
+                // 对于没有用户信息的聊天消息 (例如系统消息)，作为系统事件发送
                 println!(
                     "    【聊天msg】Content: {} (no user info)",
                     chat_msg.content
                 );
-                Ok(Some(DanmakuFrontendPayload {
-                    room_id: current_room_id.to_string(), // Populate room_id
-                    user: "系统".to_string(),             // Or some other placeholder
-                    content: chat_msg.content.clone(),
-                    user_level: 0,
-                    fans_club_level: 0,
-                }))
+                Ok(Some(DanmakuEvent::system(
+                    SupportedPlatformRust::Douyin,
+                    current_room_id,
+                    chat_msg.content.clone(),
+                )))
             }
         }
         Err(e) => {
@@ -53,80 +86,206 @@ pub fn parse_chat_message(
     }
 }
 
-// Demo 中此函数返回 Result<(), ...> 并且只打印，这里保持原有返回 Option<DanmakuFrontendPayload> 结构
-// 如果不需要将进场消息发送到前端，可以保持返回 Ok(None)
-#[allow(dead_code)] // ADDED to suppress warning
-pub fn parse_member_message(
-    payload: &[u8],
-    _current_room_id: &str,
-) -> Result<Option<DanmakuFrontendPayload>, Box<dyn std::error::Error + Send + Sync>> {
-    match MemberMessage::decode(payload) {
-        Ok(member_msg) => {
-            if let Some(user) = member_msg.user {
-                let user_level = user.pay_grade.as_ref().map(|pg| pg.level).unwrap_or(0);
-                let fans_club_level = user
-                    .fans_club
-                    .as_ref()
-                    .and_then(|fc| fc.data.as_ref())
-                    .map(|fcd| fcd.level)
-                    .unwrap_or(0);
+/// 抖音连击礼物会在连击过程中反复推送 GiftMessage，repeat_count 为累计次数。
+/// 这里按 (用户, group_id) 记录上一次的累计值，只把新增数量作为 gift_count 下发，
+/// 避免统计时重复计算；repeat_end == 1 表示本轮连击结束。
+#[derive(Default)]
+pub struct GiftComboTracker {
+    last_repeat: HashMap<(String, u64), u64>,
+}
 
-                println!(
-                    "    【进场msg】[用户等级: {}][粉丝牌等级: {}]{} 进入了直播间",
-                    user_level, fans_club_level, user.nick_name
-                );
-                Ok(None) // 当前不发送到前端
-            } else {
-                println!("    【进场msg】MemberMessage without user details.");
-                Ok(None)
+impl GiftComboTracker {
+    // 返回 (本次新增次数, 本轮累计次数)
+    fn advance(&mut self, key: (String, u64), repeat_count: u64, repeat_end: bool) -> (u64, u64) {
+        let previous = self.last_repeat.get(&key).copied().unwrap_or(0);
+        let delta = repeat_count.saturating_sub(previous);
+        if repeat_end {
+            self.last_repeat.remove(&key);
+        } else if delta > 0 {
+            if self.last_repeat.len() >= MAX_TRACKED_COMBOS {
+                self.last_repeat.clear();
             }
+            self.last_repeat.insert(key, repeat_count);
         }
-        Err(e) => {
-            eprintln!("    【X】Failed to parse MemberMessage in parser: {}", e);
-            Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-        }
+        (delta, repeat_count)
     }
 }
 
-// Parser for LikeMessage (点赞消息)
-// Demo 中此函数返回 Result<(), ...> 并且只打印
-#[allow(dead_code)] // ADDED to suppress warning
-pub fn parse_like_message(
+// Parser for GiftMessage（礼物，含连击）
+pub fn parse_gift_message(
     payload: &[u8],
-    _current_room_id: &str,
-) -> Result<Option<DanmakuFrontendPayload>, Box<dyn std::error::Error + Send + Sync>> {
-    match LikeMessage::decode(payload) {
-        Ok(like_msg) => {
-            if let Some(user) = like_msg.user {
-                println!(
-                    "    【点赞msg】{} 点了{}个赞",
-                    user.nick_name, like_msg.count
-                );
-            } else {
-                println!("    【点赞msg】点赞 {} 个 (无用户信息)", like_msg.count);
-            }
-            Ok(None) // 点赞消息通常不直接作为弹幕显示在列表
-        }
-        Err(e) => {
-            eprintln!("    【X】Failed to parse LikeMessage in parser: {}", e);
-            Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+    current_room_id: &str,
+    tracker: &mut GiftComboTracker,
+) -> ParseResult {
+    let gift_msg = GiftMessage::decode(payload)?;
+    let user = gift_msg
+        .user
+        .as_ref()
+        .map(to_danmaku_user)
+        .unwrap_or_default();
+    let gift = gift_msg.gift.as_ref();
+    let gift_id = gift.map(|g| g.id).unwrap_or(gift_msg.gift_id);
+    let gift_name = gift
+        .map(|g| g.name.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("礼物{}", gift_id));
+    let gift_price = gift
+        .map(|g| g.diamond_count as f64 * DIAMOND_TO_CNY)
+        .filter(|price| *price > 0.0);
+    let group_count = gift_msg.group_count.max(1);
+    let is_combo = gift.map(|g| g.combo).unwrap_or(false) && gift_msg.group_id != 0;
+
+    let (count, combo_count) = if is_combo {
+        let key = (user.user_id.clone().unwrap_or_default(), gift_msg.group_id);
+        let (delta, total) = tracker.advance(key, gift_msg.repeat_count, gift_msg.repeat_end == 1);
+        if delta == 0 {
+            // 连击结束标记或重复推送，没有新增礼物
+            return Ok(None);
         }
+        (delta * group_count, Some((total * group_count) as u32))
+    } else {
+        (gift_msg.repeat_count.max(1) * group_count, None)
+    };
+
+    let content = match combo_count {
+        Some(total) => format!("送出 {} x{}", gift_name, total),
+        None => format!("送出 {} x{}", gift_name, count),
+    };
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::Gift {
+            gift_id: Some(gift_id.to_string()),
+            gift_name,
+            gift_count: count as u32,
+            gift_price,
+            combo_count,
+        },
+        user,
+        content,
+    )))
+}
+
+// Parser for MemberMessage（进场）
+pub fn parse_member_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let member_msg = MemberMessage::decode(payload)?;
+    let Some(user) = member_msg.user.as_ref() else {
+        return Ok(None);
+    };
+    let user = to_danmaku_user(user);
+    let content = format!("{} 进入了直播间", user.nickname);
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::Enter,
+        user,
+        content,
+    )))
+}
+
+// Parser for LikeMessage (点赞消息)
+pub fn parse_like_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let like_msg = LikeMessage::decode(payload)?;
+    let user = like_msg
+        .user
+        .as_ref()
+        .map(to_danmaku_user)
+        .unwrap_or_default();
+    let content = format!("{} 点了 {} 个赞", user.nickname, like_msg.count);
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::Like {
+            count: like_msg.count,
+        },
+        user,
+        content,
+    )))
+}
+
+// Parser for SocialMessage（action == 1 为关注，其余为分享等，忽略）
+pub fn parse_social_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let social_msg = SocialMessage::decode(payload)?;
+    if social_msg.action != 1 {
+        return Ok(None);
     }
+    let Some(user) = social_msg.user.as_ref() else {
+        return Ok(None);
+    };
+    let user = to_danmaku_user(user);
+    let content = format!("{} 关注了主播", user.nickname);
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::Follow,
+        user,
+        content,
+    )))
 }
 
-#[allow(dead_code)] // ADDED to suppress warning
-pub fn parse_room_stats_message(
-    payload: &[u8],
-    _current_room_id: &str,
-) -> Result<Option<DanmakuFrontendPayload>, Box<dyn std::error::Error + Send + Sync>> {
-    match RoomStatsMessage::decode(payload) {
-        Ok(stats_msg) => {
-            println!("    【直播间统计msg】{}", stats_msg.display_long);
-            Ok(None) // 统计信息通常不作为普通弹幕显示
-        }
-        Err(e) => {
-            eprintln!("    【X】Failed to parse RoomStatsMessage in parser: {}", e);
-            Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+// Parser for FansclubMessage（type 1 升级，2 加入）
+pub fn parse_fansclub_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let fansclub_msg = FansclubMessage::decode(payload)?;
+    let user = fansclub_msg
+        .user
+        .as_ref()
+        .map(to_danmaku_user)
+        .unwrap_or_default();
+    let upgrade = fansclub_msg.r#type == 1;
+    let content = if fansclub_msg.content.is_empty() {
+        if upgrade {
+            format!("{} 的粉丝团等级升级了", user.nickname)
+        } else {
+            format!("{} 加入了粉丝团", user.nickname)
         }
+    } else {
+        fansclub_msg.content.clone()
+    };
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::FansClub { upgrade },
+        user,
+        content,
+    )))
+}
+
+// Parser for RoomUserSeqMessage（在线人数 / 累计观看）
+pub fn parse_room_user_seq_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let seq_msg = RoomUserSeqMessage::decode(payload)?;
+    let online = u64::try_from(seq_msg.total).ok();
+    let total_viewers = u64::try_from(seq_msg.total_user).ok();
+    let display = Some(seq_msg.total_user_str.clone()).filter(|s| !s.is_empty());
+    let content = format!(
+        "当前在线 {}，累计观看 {}",
+        seq_msg.total,
+        display
+            .clone()
+            .unwrap_or_else(|| seq_msg.total_user.to_string())
+    );
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::RoomStats {
+            online,
+            total_viewers,
+            likes: None,
+            display,
+        },
+        DanmakuUser::default(),
+        content,
+    )))
+}
+
+// Parser for RoomStatsMessage（直播间统计文案）
+pub fn parse_room_stats_message(payload: &[u8], current_room_id: &str) -> ParseResult {
+    let stats_msg = RoomStatsMessage::decode(payload)?;
+    if stats_msg.is_hidden || stats_msg.display_long.is_empty() {
+        return Ok(None);
     }
+    Ok(Some(douyin_event(
+        current_room_id,
+        DanmakuEventKind::RoomStats {
+            online: None,
+            total_viewers: u64::try_from(stats_msg.total).ok().filter(|t| *t > 0),
+            likes: None,
+            display: Some(stats_msg.display_long.clone()),
+        },
+        DanmakuUser::default(),
+        stats_msg.display_long,
+    )))
 }
@@ -1,13 +1,73 @@
-use deno_core::{FastString, JsRuntime, RuntimeOptions};
 use md5::{Digest, Md5};
 use rand::Rng;
 use std::collections::HashMap;
 use url::Url;
 
-// Load sign.js content at compile time
-const SIGN_JS_CONTENT: &str = include_str!("./sign.js");
+use crate::platforms::douyin::a_bogus::rc4_encrypt;
 
-pub async fn generate_signature(
+// X-Bogus 使用的 base64 字符表（第 27 位是 '+'，不是 a_bogus 里的 '-'）
+const X_BOGUS_ALPHABET: &[u8; 64] =
+    b"Dkdpgh4ZKsQB80/Mfvw36XI1R25+WUAlEi7NLboqYTOPuzmFjJnryx9HVGcaStCe";
+
+fn md5_tail(data: &[u8]) -> [u8; 2] {
+    let digest = Md5::digest(data);
+    [digest[14], digest[15]]
+}
+
+/// sign.js 中 `get_sign(md5)` 的原生实现，即
+/// `byted_acrawler.frontierSign({"X-MS-STUB": md5})["X-Bogus"]`
+pub fn get_sign(md5_stub: &str) -> Result<String, String> {
+    let mut rng = rand::thread_rng();
+    // 对应 JS 中三次 Math.random()：floor(r * 100) 的奇偶、floor(r * 255)、floor(r * 255)
+    frontier_sign(
+        md5_stub,
+        rng.gen_bool(0.5),
+        rng.gen_range(0..255),
+        rng.gen_range(0..255),
+    )
+}
+
+// 确定性部分，随机量由调用方给出；与 sign.js 输出对照的样例见下方测试
+fn frontier_sign(md5_stub: &str, flag: bool, salt: u8, key: u8) -> Result<String, String> {
+    let stub = hex::decode(md5_stub).map_err(|e| format!("invalid X-MS-STUB: {}", e))?;
+    let url_md5 = md5_tail(&stub);
+    // 无请求体时 body 的摘要固定为 md5(md5(""))
+    let body_md5 = md5_tail(&Md5::digest(b""));
+
+    // 第一个字节是页面内的调用计数，旧实现每次新建运行时，所以恒为 1
+    let mut payload = vec![
+        1,
+        0,
+        1,
+        14,
+        body_md5[0],
+        body_md5[1],
+        url_md5[0],
+        url_md5[1],
+        salt,
+    ];
+    let checksum = payload.iter().fold(0u8, |acc, b| acc ^ b);
+    payload.push(checksum);
+
+    let plaintext: String = payload.iter().map(|&b| b as char).collect();
+    let mut data = vec![0x40 | ((flag as u8) << 4), key];
+    data.extend(
+        rc4_encrypt(&plaintext, &(key as char).to_string())
+            .chars()
+            .map(|c| c as u32 as u8),
+    );
+
+    let mut signature = String::with_capacity(data.len() / 3 * 4);
+    for chunk in data.chunks(3) {
+        let n = ((chunk[0] as u32) << 16) | ((chunk[1] as u32) << 8) | chunk[2] as u32;
+        for shift in [18, 12, 6, 0] {
+            signature.push(X_BOGUS_ALPHABET[((n >> shift) & 63) as usize] as char);
+        }
+    }
+    Ok(signature)
+}
+
+pub fn generate_signature(
     wss_url: &str,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let parsed_url = Url::parse(wss_url)?;
@@ -40,63 +100,14 @@ pub async fn generate_signature(
         tpl_params_vec.push(format!("{}={}", key_str, value));
     }
     let to_sign_str = tpl_params_vec.join(",");
-    println!("[Rust] String to MD5 for signature: {}", to_sign_str);
 
     // Use md_5 crate for MD5 computation
     let mut hasher = Md5::new();
     hasher.update(to_sign_str.as_bytes());
     let digest_bytes = hasher.finalize();
     let md5_param = format!("{:x}", digest_bytes);
-    println!("[Rust] MD5 param for signature: {}", md5_param);
-
-    let mut runtime = JsRuntime::new(RuntimeOptions::default());
-
-    let bootstrap_script = r#"
-        globalThis.window = globalThis;
-        globalThis.self = globalThis;
-        globalThis.document = {}; 
-        globalThis.navigator = {
-            userAgent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"
-        };
-    "#;
-    runtime
-        .execute_script("[bootstrap]", FastString::from_static(bootstrap_script))
-        .map_err(|e| {
-            eprintln!("Error during deno_core bootstrap script: {}", e);
-            e
-        })?;
-
-    // Use the embedded sign.js content
-    runtime
-        .execute_script("./sign.js", FastString::from_static(SIGN_JS_CONTENT))
-        .map_err(|e| {
-            eprintln!("Error during deno_core eval of sign.js: {}", e);
-            e
-        })?;
-
-    let call_script = format!("get_sign('{}')", md5_param);
-    // For dynamic strings, convert to String first, then into FastString
-    let fast_call_script = FastString::from(call_script);
-    let result = runtime
-        .execute_script("[call_get_sign]", fast_call_script)
-        .map_err(|e| {
-            eprintln!("Error during deno_core call to get_sign: {}", e);
-            e
-        })?;
-
-    let scope = &mut runtime.handle_scope();
-    let local_value = deno_core::v8::Local::new(scope, result);
-
-    if local_value.is_string() {
-        let signature = local_value.to_rust_string_lossy(scope);
-        println!("[Rust] Final signature: {}", signature);
-        Ok(signature)
-    } else {
-        Err(
-            Box::from("get_sign did not return a string value from deno_core")
-                as Box<dyn std::error::Error + Send + Sync>,
-        )
-    }
+
+    Ok(get_sign(&md5_param)?)
 }
 
 pub fn generate_ms_token(length: usize) -> String {
@@ -117,8 +128,50 @@ pub fn generate_douyin_ms_token() -> String {
     generate_ms_token(107)
 }
 
-// Placeholder for the more complex signature generation if needed later.
-// pub async fn generate_signature(wss_url: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-//     // ... (implementation from demo if required)
-//     unimplemented!();
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 固定 Math.random() 后从原 sign.js 采集的输出
+    #[test]
+    fn frontier_sign_matches_sign_js() {
+        let cases = [
+            (
+                "d41d8cd98f00b204e9800998ecf8427e",
+                false,
+                0x7f,
+                0x7f,
+                "fZCpNb7LVQAWG7w4",
+            ),
+            (
+                "e50eb7598b580311bab8f39bb8f4815b",
+                true,
+                143,
+                109,
+                "64x1wDHfiCtI6Ijf",
+            ),
+            (
+                "6dbf9ac2da09ee1d3debf5a51873ec6d",
+                false,
+                5,
+                190,
+                "fBcd7RoWr6hYIrsH",
+            ),
+        ];
+        for (stub, flag, salt, key, expected) in cases {
+            assert_eq!(frontier_sign(stub, flag, salt, key).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn get_sign_rejects_invalid_stub() {
+        assert!(get_sign("not-hex").is_err());
+    }
+
+    #[test]
+    fn get_sign_has_x_bogus_shape() {
+        let sign = get_sign("d41d8cd98f00b204e9800998ecf8427e").unwrap();
+        assert_eq!(sign.len(), 16);
+        assert!(sign.bytes().all(|b| X_BOGUS_ALPHABET.contains(&b)));
+    }
+}
@@ -46,7 +46,7 @@ pub async fn connect_and_manage_websocket(
         user_unique_id,
         room_id
     );
-    let signature = signature::generate_signature(&wss_url_str_for_signature).await?;
+    let signature = signature::generate_signature(&wss_url_str_for_signature)?;
     let internal_ext_encoded = urlencoding::encode(&internal_ext_original);
     let final_wss_url_str = format!(
         "{}&signature={}",
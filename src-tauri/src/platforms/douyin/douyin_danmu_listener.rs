@@ -1,12 +1,19 @@
+use crate::platforms::common::danmaku::{
+    BatchOptions, DanmakuBatch, DanmakuChannel, DanmakuSink, DanmakuStatus,
+};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+use crate::platforms::common::DanmakuListenerRegistry;
 use crate::platforms::douyin::web_api::normalize_douyin_live_id;
-use tauri::Emitter;
-use tokio::sync::mpsc as tokio_mpsc;
+use tauri::ipc::Channel;
+use tauri::Manager;
 
 #[tauri::command]
 pub async fn start_douyin_danmu_listener(
     payload: crate::platforms::common::GetStreamUrlPayload,
+    channel: Option<Channel<DanmakuBatch>>,
+    batch: Option<BatchOptions>,
     app_handle: tauri::AppHandle,
-    state: tauri::State<'_, crate::platforms::common::DouyinDanmakuState>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
     let room_id_or_url = payload.args.room_id_str;
     println!(
@@ -14,35 +21,32 @@ pub async fn start_douyin_danmu_listener(
         room_id_or_url
     );
 
-    let previous_tx = {
-        let mut lock = state.inner().0.lock().unwrap();
-        lock.take()
-    };
-
-    if let Some(tx) = previous_tx {
-        println!("[Douyin Danmaku] Sending shutdown to previous Douyin listener task.");
-        if tx.send(()).await.is_err() {
-            eprintln!("[Douyin Danmaku] Failed to send shutdown. Task might have already completed or panicked.");
-        }
-    }
-
+    // 兼容旧前端：stop_listening 表示停止所有抖音房间的监听
     if room_id_or_url == "stop_listening" {
+        let stopped = registry.stop_all(Some(SupportedPlatformRust::Douyin));
         println!(
-            "[Douyin Danmaku] Received stop_listening signal. Listener will not be restarted."
+            "[Douyin Danmaku] Received stop_listening signal. Stopped {} listener(s).",
+            stopped
         );
         return Ok(());
     }
 
     let normalized_room_id = normalize_douyin_live_id(&room_id_or_url);
 
-    let (tx_shutdown, mut rx_shutdown) = tokio_mpsc::channel::<()>(1);
-    {
-        let mut lock = state.inner().0.lock().unwrap();
-        *lock = Some(tx_shutdown);
-    }
+    // 同一房间已有监听时，register 会先停止旧任务
+    let (listener_id, mut rx_shutdown) =
+        registry.register(SupportedPlatformRust::Douyin, &normalized_room_id);
 
-    let app_handle_clone = app_handle.clone();
     let room_id_str_clone = normalized_room_id.clone();
+    // 弹幕和状态事件都使用前端注册时的房间号（web_rid），而不是内部 room_id，
+    // 录制、过滤、统计和前端才能对应到同一个房间
+    let sink = DanmakuSink::new(
+        app_handle.clone(),
+        SupportedPlatformRust::Douyin,
+        &normalized_room_id,
+    )
+    .with_channel(DanmakuChannel::open(&app_handle, channel, batch))
+    .with_listener_id(listener_id);
 
     tokio::spawn(async move {
         println!(
@@ -53,7 +57,10 @@ pub async fn start_douyin_danmu_listener(
         let task_result = {
             let mut attempt: u32 = 1;
             loop {
+                // 连上过的会话断开后重新计数，长时间运行中的偶发断线不会耗尽重试次数
+                let mut connected = false;
                 let attempt_result = async {
+                    sink.status(DanmakuStatus::Connecting);
                     let mut fetcher = crate::platforms::douyin::danmu::web_fetcher::DouyinLiveWebFetcher::new(&room_id_str_clone)?;
                     fetcher
                         .fetch_room_details()
@@ -80,19 +87,22 @@ pub async fn start_douyin_danmu_listener(
                         "[Douyin Danmaku] WebSocket connected for room: {}",
                         actual_room_id
                     );
+                    sink.status(DanmakuStatus::Connected);
+                    connected = true;
 
+                    // 消息处理只会以 Err 结束（断线、服务器关闭），交给下面的重试逻辑；
+                    // 只有收到 shutdown 信号才算正常停止
                     tokio::select! {
                         res = crate::platforms::douyin::danmu::message_handler::handle_received_messages(
                             read_stream,
                             ack_tx,
-                            app_handle_clone.clone(),
-                            actual_room_id.clone()
+                            sink.clone(),
                         ) => {
                             if let Err(e) = res {
                                 return Err(e);
                             }
                         }
-                        _ = rx_shutdown.recv() => {
+                        _ = &mut rx_shutdown => {
                             println!(
                                 "[Douyin Danmaku] Received shutdown signal for room {}.",
                                 actual_room_id
@@ -107,6 +117,9 @@ pub async fn start_douyin_danmu_listener(
                 match attempt_result {
                     Ok(_) => break Ok(()),
                     Err(e) => {
+                        if connected {
+                            attempt = 1;
+                        }
                         if attempt >= 2 {
                             eprintln!("[Douyin Danmaku] Listener connect/fetch failed after {} attempts: {}", attempt, e);
                             break Err(e);
@@ -115,6 +128,7 @@ pub async fn start_douyin_danmu_listener(
                                 "[Douyin Danmaku WARN] Attempt {} failed: {}. Retrying...",
                                 attempt, e
                             );
+                            sink.status(DanmakuStatus::Reconnecting { attempt });
                             attempt += 1;
                             continue;
                         }
@@ -123,25 +137,22 @@ pub async fn start_douyin_danmu_listener(
             }
         };
 
+        app_handle.state::<DanmakuListenerRegistry>().finish(
+            SupportedPlatformRust::Douyin,
+            &room_id_str_clone,
+            listener_id,
+        );
+
         if let Err(e) = task_result {
             eprintln!(
                 "[Douyin Danmaku] Listener task for room {} critically failed: {}",
                 room_id_str_clone, e
             );
-            let error_payload = crate::platforms::common::DanmakuFrontendPayload {
-                room_id: room_id_str_clone.clone(),
-                user: "系统消息".to_string(),
-                content: format!("弹幕连接发生错误: {}", e),
-                user_level: 0,
-                fans_club_level: 0,
-            };
-            if let Err(emit_err) = app_handle.emit("danmaku-message", error_payload) {
-                eprintln!(
-                    "[Douyin Danmaku] Failed to emit error event to frontend: {}",
-                    emit_err
-                );
-            }
+            sink.status(DanmakuStatus::Failed {
+                reason: e.to_string(),
+            });
         } else {
+            sink.status(DanmakuStatus::Stopped);
             println!(
                 "[Douyin Danmaku] Listener task for room {} completed.",
                 room_id_str_clone
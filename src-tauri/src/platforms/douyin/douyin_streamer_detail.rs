@@ -1,14 +1,14 @@
 use crate::platforms::common::http_client::HttpClient;
 use crate::platforms::common::types::StreamVariant;
+use crate::platforms::common::types_rust::SupportedPlatformRust;
 use crate::platforms::common::GetStreamUrlPayload;
 use crate::platforms::common::LiveStreamInfo as CommonLiveStreamInfo;
 use crate::platforms::douyin::web_api::{
-    choose_flv_stream, fetch_room_data, normalize_douyin_live_id, DouyinRoomData,
+    choose_flv_stream, choose_hls_stream, fetch_room_data, normalize_douyin_live_id, DouyinRoomData,
 };
-use crate::proxy::ProxyServerHandle;
-use crate::StreamUrlStore;
+use crate::proxy::StreamProxy;
 use serde_json::Value;
-use tauri::{command, AppHandle, State};
+use tauri::{command, AppHandle, Manager};
 
 const QUALITY_OD: &str = "OD";
 const QUALITY_BD: &str = "BD";
@@ -16,25 +16,14 @@ const QUALITY_UHD: &str = "UHD";
 #[command]
 pub async fn get_douyin_live_stream_url(
     app_handle: AppHandle,
-    stream_url_store: State<'_, StreamUrlStore>,
-    proxy_server_handle: State<'_, ProxyServerHandle>,
     payload: GetStreamUrlPayload,
 ) -> Result<CommonLiveStreamInfo, String> {
-    get_douyin_live_stream_url_with_quality(
-        app_handle,
-        stream_url_store,
-        proxy_server_handle,
-        payload,
-        QUALITY_OD.to_string(),
-    )
-    .await
+    get_douyin_live_stream_url_with_quality(app_handle, payload, QUALITY_OD.to_string()).await
 }
 
 #[command]
 pub async fn get_douyin_live_stream_url_with_quality(
-    _app_handle: AppHandle,
-    _stream_url_store: State<'_, StreamUrlStore>,
-    _proxy_server_handle: State<'_, ProxyServerHandle>,
+    app_handle: AppHandle,
     payload: GetStreamUrlPayload,
     quality: String,
 ) -> Result<CommonLiveStreamInfo, String> {
@@ -51,6 +40,7 @@ pub async fn get_douyin_live_stream_url_with_quality(
             available_streams: None,
             normalized_room_id: None,
             web_rid: None,
+            proxy_session_id: None,
         });
     }
 
@@ -93,34 +83,51 @@ pub async fn get_douyin_live_stream_url_with_quality(
             available_streams: available_streams.clone(),
             normalized_room_id: None,
             web_rid: Some(web_rid),
+            proxy_session_id: None,
         });
     }
 
     let target_quality = normalize_quality_tag(&quality);
-    let selected = choose_flv_stream(&room, target_quality)
-        .or_else(|| first_flv_stream(&room))
-        .ok_or_else(|| {
-            "[Douyin Stream Detail] No FLV streams available in stream_url.flv_pull_url".to_string()
-        })?;
-    let (selected_key, real_url) = selected;
-    println!(
-        "[Douyin Stream Detail] Selected FLV stream key='{}' url='{}'",
-        selected_key, real_url
-    );
-
-    let sanitized_url = enforce_https(&real_url);
+    let stream_proxy = app_handle.state::<StreamProxy>();
+    // FLV 直连播放；房间没有 FLV 时改用 HLS，经本地代理带上抖音的 UA 和 Referer
+    let (stream_url, upstream_url, proxy_session_id) = if let Some((selected_key, real_url)) =
+        choose_flv_stream(&room, target_quality).or_else(|| first_flv_stream(&room))
+    {
+        println!(
+            "[Douyin Stream Detail] Selected FLV stream key='{}' url='{}'",
+            selected_key, real_url
+        );
+        stream_proxy.unregister_room(SupportedPlatformRust::Douyin, &web_rid);
+        let sanitized_url = enforce_https(&real_url);
+        (sanitized_url.clone(), sanitized_url, None)
+    } else {
+        let (selected_key, real_url) =
+            choose_hls_stream(&room, target_quality).ok_or_else(|| {
+                "[Douyin Stream Detail] No FLV or HLS streams available in stream_url".to_string()
+            })?;
+        println!(
+            "[Douyin Stream Detail] Selected HLS stream key='{}' url='{}'",
+            selected_key, real_url
+        );
+        let sanitized_url = enforce_https(&real_url);
+        let session = stream_proxy
+            .replace_room(SupportedPlatformRust::Douyin, &web_rid, &sanitized_url)
+            .await?;
+        (session.url, sanitized_url, Some(session.session_id))
+    };
 
     Ok(CommonLiveStreamInfo {
         title,
         anchor_name,
         avatar,
-        stream_url: Some(sanitized_url.clone()),
+        stream_url: Some(stream_url),
         status: Some(status),
         error_message: None,
-        upstream_url: Some(sanitized_url),
+        upstream_url: Some(upstream_url),
         available_streams,
         normalized_room_id: None,
         web_rid: Some(web_rid),
+        proxy_session_id,
     })
 }
 
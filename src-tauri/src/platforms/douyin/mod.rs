@@ -6,6 +6,7 @@ pub mod douyin_streamer_list;
 pub mod models;
 pub mod web_api;
 pub mod a_bogus;
+pub mod categories;
 
 pub use self::danmu::web_fetcher::fetch_douyin_room_info;
 pub use self::douyin_danmu_listener::start_douyin_danmu_listener;
@@ -14,3 +15,4 @@ pub use self::douyin_streamer_detail::{
 };
 pub use self::douyin_streamer_info::fetch_douyin_streamer_info;
 pub use self::douyin_streamer_list::fetch_douyin_partition_rooms;
+pub mod platform;
@@ -0,0 +1,170 @@
+use tauri::{AppHandle, Manager};
+
+use crate::platforms::common::live_platform::LivePlatform;
+use crate::platforms::common::types::GetStreamUrlArgs;
+use crate::platforms::common::types_rust::{
+    CommonLiveListRust, CommonPlatformCategoryRust, CommonRoomInfoRust, CommonStreamerRust,
+    SupportedPlatformRust,
+};
+use crate::platforms::common::{FollowHttpClient, GetStreamUrlPayload, LiveStreamInfo};
+use crate::platforms::douyin::categories::{split_partition, DOUYIN_CATEGORIES};
+use crate::platforms::douyin::danmu::signature::generate_ms_token;
+use crate::platforms::douyin::{
+    fetch_douyin_partition_rooms, fetch_douyin_streamer_info,
+    get_douyin_live_stream_url_with_quality,
+};
+
+// 与 fetch_douyin_partition_rooms 内部的 count 保持一致
+const LIVE_LIST_PAGE_SIZE: u32 = 15;
+
+fn payload_for(room_id: &str) -> GetStreamUrlPayload {
+    GetStreamUrlPayload {
+        args: GetStreamUrlArgs {
+            room_id_str: room_id.to_string(),
+        },
+    }
+}
+
+pub struct DouyinPlatform {
+    app_handle: AppHandle,
+}
+
+impl DouyinPlatform {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl LivePlatform for DouyinPlatform {
+    fn platform(&self) -> SupportedPlatformRust {
+        SupportedPlatformRust::Douyin
+    }
+
+    async fn room_info(&self, room_id: &str) -> Result<CommonRoomInfoRust, String> {
+        let follow_http = self.app_handle.state::<FollowHttpClient>();
+        let info = fetch_douyin_streamer_info(payload_for(room_id), follow_http).await?;
+        if let Some(err) = info
+            .error_message
+            .as_ref()
+            .filter(|_| info.status.is_none())
+        {
+            return Err(err.clone());
+        }
+        Ok(CommonRoomInfoRust {
+            platform: self.platform(),
+            room_id: info.web_rid.unwrap_or_else(|| room_id.to_string()),
+            title: info.title,
+            anchor_name: info.anchor_name,
+            avatar: info.avatar,
+            // 抖音 status == 2 为直播中
+            is_live: info.status == Some(2),
+        })
+    }
+
+    async fn stream_url(
+        &self,
+        room_id: &str,
+        quality: &str,
+        _line: Option<&str>,
+    ) -> Result<LiveStreamInfo, String> {
+        get_douyin_live_stream_url_with_quality(
+            self.app_handle.clone(),
+            payload_for(room_id),
+            quality.to_string(),
+        )
+        .await
+    }
+
+    // 抖音没有可用的主播搜索接口，与前端一致：将关键词视为房间号/直播间链接直接查询。
+    // 因此昵称等关键词搜不到结果，且最多只有一条结果，第 2 页起固定为空。
+    async fn search(&self, keyword: &str, page: u32) -> Result<Vec<CommonStreamerRust>, String> {
+        let keyword = keyword.trim();
+        if keyword.is_empty() || page > 1 {
+            return Ok(Vec::new());
+        }
+        let info = self.room_info(keyword).await?;
+        if info.anchor_name.is_none() && info.title.is_none() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![CommonStreamerRust {
+            room_id: info.room_id,
+            title: info.title.unwrap_or_default(),
+            nickname: info.anchor_name.unwrap_or_default(),
+            avatar: info.avatar.unwrap_or_default(),
+            room_cover: String::new(),
+            viewer_count_str: String::new(),
+            platform: self.platform(),
+            is_live: Some(info.is_live),
+        }])
+    }
+
+    async fn categories(&self) -> Result<Vec<CommonPlatformCategoryRust>, String> {
+        let mut categories = Vec::new();
+        for group in DOUYIN_CATEGORIES {
+            categories.push(CommonPlatformCategoryRust {
+                id: group.id.to_string(),
+                name: group.name.to_string(),
+                platform: self.platform(),
+                icon_url: None,
+                parent_id: None,
+            });
+            categories.extend(group.subcategories.iter().map(|(name, id)| {
+                CommonPlatformCategoryRust {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    platform: self.platform(),
+                    icon_url: None,
+                    parent_id: Some(group.id.to_string()),
+                }
+            }));
+        }
+        Ok(categories)
+    }
+
+    async fn live_list(
+        &self,
+        category_id: &str,
+        parent_id: Option<&str>,
+        page: u32,
+    ) -> Result<CommonLiveListRust, String> {
+        // categories() 返回的二级分区 id 自带 partition_type；只传 partition 时由 parent_id 提供
+        let (partition, partition_type) = match split_partition(category_id) {
+            Some(pair) => pair,
+            None => (
+                category_id,
+                parent_id.ok_or_else(|| {
+                    "Douyin live list requires parent_id (partition_type)".to_string()
+                })?,
+            ),
+        };
+        let offset = ((page - 1) * LIVE_LIST_PAGE_SIZE) as i32;
+        let response = fetch_douyin_partition_rooms(
+            self.app_handle.state::<reqwest::Client>(),
+            partition.to_string(),
+            partition_type.to_string(),
+            offset,
+            generate_ms_token(107),
+        )
+        .await?;
+
+        let rooms = response
+            .rooms
+            .into_iter()
+            .map(|r| CommonStreamerRust {
+                room_id: r.web_rid,
+                title: r.title,
+                nickname: r.owner_nickname,
+                avatar: r.avatar_url,
+                room_cover: r.cover_url,
+                viewer_count_str: r.user_count_str,
+                platform: SupportedPlatformRust::Douyin,
+                is_live: Some(true),
+            })
+            .collect();
+        Ok(CommonLiveListRust {
+            rooms,
+            has_more: response.has_more,
+            page,
+        })
+    }
+}
@@ -0,0 +1,208 @@
+// 斗鱼弹幕服务器地址、登录参数与已建立连接的发送通道
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use md5::{Digest, Md5};
+use tokio::sync::mpsc;
+
+use super::stt;
+
+const DANMU_HOST: &str = "danmuproxy.douyu.com";
+/// 8506 是网页端默认端口，其余按顺序作为备用
+pub const DANMU_PORTS: [u16; 6] = [8506, 8501, 8502, 8503, 8504, 8505];
+// 连续失败后暂时跳过该端口：30s、60s … 最长 10 分钟
+const BASE_COOLDOWN: Duration = Duration::from_secs(30);
+const MAX_COOLDOWN: Duration = Duration::from_secs(600);
+// 网页端 loginreq 中 vk 的盐
+const VK_SALT: &str = "r5*^5;}2#${XF[h+;'./.Q'1;,-]f'p[";
+
+pub fn endpoint_url(port: u16) -> String {
+    format!("wss://{}:{}/", DANMU_HOST, port)
+}
+
+#[derive(Default, Clone, Copy)]
+struct PortHealth {
+    failures: u32,
+    last_failure: Option<Instant>,
+}
+
+impl PortHealth {
+    fn cooling_down(&self, now: Instant) -> bool {
+        let Some(last) = self.last_failure else {
+            return false;
+        };
+        let cooldown = BASE_COOLDOWN
+            .saturating_mul(1u32 << self.failures.saturating_sub(1).min(5))
+            .min(MAX_COOLDOWN);
+        now.duration_since(last) < cooldown
+    }
+}
+
+/// 登录用户的斗鱼 cookie 中与弹幕登录相关的字段
+#[derive(Clone, Debug)]
+pub struct DouyuAuth {
+    pub uid: String,
+    pub stk: String,
+    pub ltkid: String,
+    pub username: String,
+    pub biz: String,
+    pub device_id: String,
+}
+
+impl DouyuAuth {
+    /// 从 cookie 字符串解析；缺少 acf_uid / acf_stk / acf_ltkid 时返回 None（匿名登录）
+    pub fn from_cookie(cookie: &str) -> Option<Self> {
+        let get = |name: &str| {
+            cookie
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| {
+                    urlencoding::decode(v)
+                        .map(|v| v.into_owned())
+                        .unwrap_or_default()
+                })
+                .unwrap_or_default()
+        };
+        let auth = Self {
+            uid: get("acf_uid"),
+            stk: get("acf_stk"),
+            ltkid: get("acf_ltkid"),
+            username: get("acf_username"),
+            biz: get("acf_biz"),
+            device_id: get("dy_did"),
+        };
+        if auth.uid.is_empty() || auth.stk.is_empty() || auth.ltkid.is_empty() {
+            return None;
+        }
+        Some(auth)
+    }
+}
+
+/// loginreq 包；带 cookie 时以登录用户身份登录，才能发言并收到个人相关消息
+pub fn login_packet(room_id: &str, auth: Option<&DouyuAuth>) -> Vec<u8> {
+    let Some(auth) = auth else {
+        return stt::encode_message(&[("type", "loginreq"), ("roomid", room_id)]);
+    };
+    let rt = chrono::Utc::now().timestamp().to_string();
+    let vk = hex::encode(Md5::digest(
+        format!("{}{}{}", rt, VK_SALT, auth.device_id).as_bytes(),
+    ));
+    stt::encode_message(&[
+        ("type", "loginreq"),
+        ("roomid", room_id),
+        ("dfl", ""),
+        ("username", &auth.username),
+        ("uid", &auth.uid),
+        ("ltkid", &auth.ltkid),
+        ("biz", &auth.biz),
+        ("stk", &auth.stk),
+        ("devid", &auth.device_id),
+        ("ct", "0"),
+        ("pt", "2"),
+        ("cvr", "0"),
+        ("tvr", "7"),
+        ("apd", ""),
+        ("rt", &rt),
+        ("vk", &vk),
+        ("ver", "20190610"),
+        ("aver", "218101901"),
+    ])
+}
+
+/// 发言包，`col` 为斗鱼弹幕颜色编号（0 为默认白色）
+pub fn chat_packet(text: &str, col: u8) -> Vec<u8> {
+    let col = col.to_string();
+    stt::encode_message(&[
+        ("type", "chatmessage"),
+        ("receiver", "0"),
+        ("content", text),
+        ("scope", ""),
+        ("col", &col),
+        ("pid", ""),
+        ("p2p", "0"),
+        ("nc", "0"),
+        ("rev", "0"),
+        ("hg", "0"),
+        ("ifs", "0"),
+        ("sid", ""),
+        ("lid", "0"),
+    ])
+}
+
+/// 所有斗鱼房间共享的连接状态：端口健康度，以及已登录连接的发送通道
+#[derive(Default)]
+pub struct DouyuConnections {
+    health: Mutex<HashMap<u16, PortHealth>>,
+    senders: Mutex<HashMap<String, mpsc::UnboundedSender<Vec<u8>>>>,
+}
+
+impl DouyuConnections {
+    /// 按配置顺序返回端口，冷却中的端口排在最后（最早失败的优先重试）
+    pub fn ordered_ports(&self) -> Vec<u16> {
+        let health = self.health.lock().unwrap();
+        let now = Instant::now();
+        let (mut ready, mut cooling): (Vec<u16>, Vec<u16>) = DANMU_PORTS
+            .iter()
+            .copied()
+            .partition(|port| !health.get(port).is_some_and(|h| h.cooling_down(now)));
+        cooling.sort_by_key(|port| health.get(port).and_then(|h| h.last_failure));
+        ready.append(&mut cooling);
+        ready
+    }
+
+    pub fn report_success(&self, port: u16) {
+        self.health
+            .lock()
+            .unwrap()
+            .insert(port, PortHealth::default());
+    }
+
+    pub fn report_failure(&self, port: u16) {
+        let mut health = self.health.lock().unwrap();
+        let entry = health.entry(port).or_default();
+        entry.failures += 1;
+        entry.last_failure = Some(Instant::now());
+    }
+
+    /// 登录连接建立后登记发送通道
+    pub fn attach(&self, room_id: &str, tx: mpsc::UnboundedSender<Vec<u8>>) {
+        self.senders.lock().unwrap().insert(room_id.to_string(), tx);
+    }
+
+    /// 仅当登记的仍是 `tx` 时移除，避免误删同房间新连接的通道
+    pub fn detach(&self, room_id: &str, tx: &mpsc::UnboundedSender<Vec<u8>>) {
+        let mut senders = self.senders.lock().unwrap();
+        if senders
+            .get(room_id)
+            .is_some_and(|current| current.same_channel(tx))
+        {
+            senders.remove(room_id);
+        }
+    }
+
+    fn send(&self, room_id: &str, packet: Vec<u8>) -> Result<(), String> {
+        let senders = self.senders.lock().unwrap();
+        let tx = senders
+            .get(room_id)
+            .ok_or_else(|| format!("房间 {} 没有已登录的弹幕连接", room_id))?;
+        tx.send(packet)
+            .map_err(|_| format!("房间 {} 的弹幕连接已断开", room_id))
+    }
+}
+
+/// 通过已登录的弹幕连接发言；需先以 cookie 启动该房间的弹幕监听
+#[tauri::command]
+pub async fn send_douyu_danmaku(
+    room_id: String,
+    text: String,
+    color: Option<u8>,
+    connections: tauri::State<'_, DouyuConnections>,
+) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("弹幕内容为空".to_string());
+    }
+    connections.send(&room_id, chat_packet(text, color.unwrap_or(0)))
+}
@@ -1,193 +1,452 @@
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
-use tauri::{Emitter, Window};
-use tokio::sync::mpsc;
-use tokio::sync::oneshot;
-use tokio::time::Duration;
+use tauri::{Manager, Window};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Duration, Instant};
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::{connect_async_tls_with_config, tungstenite::Message};
 use url::Url;
 
+use crate::platforms::common::danmaku::reconnect::{wait_or_shutdown, STABLE_SESSION};
+use crate::platforms::common::danmaku::{
+    Backoff, DanmakuChannel, DanmakuEvent, DanmakuEventKind, DanmakuSink, DanmakuStatus,
+    DanmakuUser, RankEntry,
+};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+use crate::platforms::douyu::connection::{self, endpoint_url, DouyuAuth, DouyuConnections};
+use crate::platforms::douyu::gift::{fetch_gift_catalog, DouyuGift};
+use crate::platforms::douyu::stt::{self, SttDecoder, SttValue};
+
+// 斗鱼弹幕颜色编号 col -> 颜色
+pub(crate) fn douyu_color(col: &str) -> Option<String> {
+    let color = match col {
+        "1" => "#FF0000",
+        "2" => "#1E87F0",
+        "3" => "#7AC84B",
+        "4" => "#FF7F00",
+        "5" => "#9B39F4",
+        "6" => "#FF69B4",
+        _ => return None,
+    };
+    Some(color.to_string())
+}
+
+fn douyu_avatar(ic: &str) -> String {
+    format!("https://apic.douyucdn.cn/upload/{}_middle.jpg", ic)
+}
+
+// 贵族等级 nl -> 名称
+fn noble_name(level: u8) -> String {
+    match level {
+        1 => "骑士",
+        2 => "子爵",
+        3 => "伯爵",
+        4 => "公爵",
+        5 => "国王",
+        6 => "皇帝",
+        7 => "游侠",
+        _ => "贵族",
+    }
+    .to_string()
+}
+
+fn parse_num<T: std::str::FromStr>(msg: &SttValue, key: &str) -> Option<T> {
+    msg.text(key).and_then(|v| v.parse().ok())
+}
+
+fn douyu_user(msg: &SttValue) -> DanmakuUser {
+    let get = |key: &str| msg.text(key).filter(|v| !v.is_empty()).map(str::to_string);
+    DanmakuUser {
+        nickname: get("nn").unwrap_or_else(|| "unknown".to_string()),
+        user_id: get("uid"),
+        avatar: get("ic").map(|ic| douyu_avatar(&ic)),
+        user_level: get("level").and_then(|v| v.parse().ok()).unwrap_or(0),
+        medal_name: get("bnn"),
+        fans_club_level: get("bl").and_then(|v| v.parse().ok()).unwrap_or(0),
+        noble_level: get("nl").and_then(|v| v.parse().ok()).unwrap_or(0),
+    }
+}
+
+type SessionError = Box<dyn std::error::Error + Send + Sync>;
+type SessionResult = Result<(), SessionError>;
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 连接结束（包括被停止信号取消）时注销发送通道；同房间的新连接已登记时不动它
+struct AttachedSender<'a> {
+    connections: &'a DouyuConnections,
+    room_id: &'a str,
+    tx: mpsc::UnboundedSender<Vec<u8>>,
+}
+
+impl Drop for AttachedSender<'_> {
+    fn drop(&mut self) {
+        self.connections.detach(self.room_id, &self.tx);
+    }
+}
+
 pub struct DanmakuClient {
     room_id: String,
     window: Window,
+    sink: DanmakuSink,
     stop_signal_rx: oneshot::Receiver<()>,
+    // gfid -> 礼物名称/价格，首次连接前加载
+    gifts: HashMap<String, DouyuGift>,
+    // 来自 cookie 的登录信息，None 时匿名登录
+    auth: Option<DouyuAuth>,
 }
 
 impl DanmakuClient {
-    pub fn new(room_id: &str, window: Window, stop_signal_rx: oneshot::Receiver<()>) -> Self {
+    pub fn new(
+        room_id: &str,
+        window: Window,
+        stop_signal_rx: oneshot::Receiver<()>,
+        delivery: Option<DanmakuChannel>,
+        listener_id: u64,
+    ) -> Self {
+        let sink = DanmakuSink::new(
+            window.app_handle().clone(),
+            SupportedPlatformRust::Douyu,
+            room_id,
+        )
+        .with_channel(delivery)
+        .with_listener_id(listener_id);
         Self {
             room_id: room_id.to_string(),
             window,
+            sink,
             stop_signal_rx,
+            gifts: HashMap::new(),
+            auth: None,
         }
     }
 
-    fn encode_msg(&self, msg: &str) -> Vec<u8> {
-        let msg_bytes = msg.as_bytes();
-        let packet_len = msg_bytes.len() + 9;
+    pub fn with_auth(mut self, auth: Option<DouyuAuth>) -> Self {
+        self.auth = auth;
+        self
+    }
 
-        let mut result = Vec::new();
-        result.extend_from_slice(&(packet_len as u32).to_le_bytes());
-        result.extend_from_slice(&(packet_len as u32).to_le_bytes());
-        result.extend_from_slice(&689u16.to_le_bytes());
-        result.push(0);
-        result.push(0);
-        result.extend_from_slice(msg_bytes);
-        result.push(0);
+    async fn load_gifts(&mut self) {
+        let http = self.window.state::<reqwest::Client>().inner().clone();
+        match fetch_gift_catalog(&http, &self.room_id).await {
+            Ok(gifts) => {
+                println!(
+                    "[Douyu Danmaku {}] Loaded {} gift definitions",
+                    self.room_id,
+                    gifts.len()
+                );
+                self.gifts = gifts;
+            }
+            Err(e) => eprintln!(
+                "[Douyu Danmaku {}] Failed to load gift list: {}",
+                self.room_id, e
+            ),
+        }
+    }
+
+    /// 持续监听直到收到停止信号；连接断开后按指数退避自动重连（每次重新 loginreq + joingroup）
+    pub async fn start(&mut self) -> SessionResult {
+        let mut stop_rx = std::mem::replace(&mut self.stop_signal_rx, oneshot::channel().1);
+        let mut backoff = Backoff::default();
 
-        result
+        loop {
+            self.sink.status(DanmakuStatus::Connecting);
+            if self.gifts.is_empty() {
+                self.load_gifts().await;
+            }
+            let started = Instant::now();
+            let result = tokio::select! {
+                _ = &mut stop_rx => {
+                    eprintln!("[Douyu Danmaku {}] Stop signal received, terminating listener.", self.room_id);
+                    break;
+                }
+                res = self.run_session() => res,
+            };
+            if started.elapsed() >= STABLE_SESSION {
+                backoff.reset();
+            }
+            let err = match result {
+                Ok(()) => "connection closed".to_string(),
+                Err(e) => e.to_string(),
+            };
+            match backoff.next_delay() {
+                Some(delay) => {
+                    eprintln!(
+                        "[Douyu Danmaku {}] Websocket closed or error: {}. Reconnecting in {:?} (attempt {})",
+                        self.room_id,
+                        err,
+                        delay,
+                        backoff.attempt()
+                    );
+                    self.sink.status(DanmakuStatus::Reconnecting {
+                        attempt: backoff.attempt(),
+                    });
+                    if wait_or_shutdown(delay, &mut stop_rx).await {
+                        break;
+                    }
+                }
+                None => {
+                    self.sink.status(DanmakuStatus::Failed {
+                        reason: err.clone(),
+                    });
+                    return Err(
+                        format!("giving up after {} attempts: {}", backoff.attempt(), err).into(),
+                    );
+                }
+            }
+        }
+        self.sink.status(DanmakuStatus::Stopped);
+        eprintln!("[Douyu Danmaku {}] Listener stopped.", self.room_id);
+        Ok(())
     }
 
-    pub async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let url = Url::parse("wss://danmuproxy.douyu.com:8506/")?;
-        let mut request = url.into_client_request()?;
-        request
-            .headers_mut()
-            .insert("Sec-WebSocket-Protocol", "binary".parse()?);
+    // 按健康度依次尝试各个端口，返回第一个连上的连接
+    async fn connect(&self, connections: &DouyuConnections) -> Result<WsStream, SessionError> {
+        let mut last_err: Option<SessionError> = None;
+        for port in connections.ordered_ports() {
+            let url = endpoint_url(port);
+            let attempt = async {
+                let mut request = Url::parse(&url)?.into_client_request()?;
+                request
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", "binary".parse()?);
+                let (ws_stream, _) = tokio::time::timeout(
+                    CONNECT_TIMEOUT,
+                    connect_async_tls_with_config(request, None, false, None),
+                )
+                .await
+                .map_err(|_| format!("connect timed out after {:?}", CONNECT_TIMEOUT))??;
+                Ok::<_, SessionError>(ws_stream)
+            };
+            match attempt.await {
+                Ok(ws_stream) => {
+                    connections.report_success(port);
+                    return Ok(ws_stream);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "[Douyu Danmaku {}] Failed to connect {}: {}",
+                        self.room_id, url, e
+                    );
+                    connections.report_failure(port);
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| "no Douyu danmaku endpoint available".into()))
+    }
 
-        let (ws_stream, _) = connect_async_tls_with_config(request, None, false, None).await?;
+    // 单次连接：登录、入组、心跳与接收，连接关闭或出错时返回
+    async fn run_session(&self) -> SessionResult {
+        let connections = self.window.state::<DouyuConnections>();
+        let ws_stream = self.connect(connections.inner()).await?;
 
         let (mut write, mut read) = ws_stream.split();
 
-        // 发送登录请求
-        let login_msg = format!("type@=loginreq/roomid@={}/", self.room_id);
-        let login_data = self.encode_msg(&login_msg);
+        // 发送登录请求；带 cookie 时以登录用户身份登录
+        let login_data = connection::login_packet(&self.room_id, self.auth.as_ref());
         write.send(Message::Binary(login_data)).await?;
 
         // 发送加入房间请求
-        let join_msg = format!("type@=joingroup/rid@={}/gid@=1/", self.room_id);
-        let join_data = self.encode_msg(&join_msg);
+        let join_data =
+            stt::encode_message(&[("type", "joingroup"), ("rid", &self.room_id), ("gid", "1")]);
         write.send(Message::Binary(join_data)).await?;
+        self.sink.status(DanmakuStatus::Connected);
 
-        // 创建消息通道
-        let (tx, mut rx) = mpsc::channel(32);
-
-        // 启动心跳任务
-        let heartbeat_msg = "type@=mrkl/";
-        let heartbeat_data = self.encode_msg(heartbeat_msg);
-        let tx_clone = tx.clone();
-
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(Duration::from_secs(45)).await;
-                if let Err(_) = tx_clone.send(Message::Binary(heartbeat_data.clone())).await {
-                    break;
-                }
+        // 已登录时登记发送通道，供 send_douyu_danmaku 发言；连接结束时自动注销
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let _attached = self.auth.as_ref().map(|_| {
+            connections.attach(&self.room_id, out_tx.clone());
+            AttachedSender {
+                connections: connections.inner(),
+                room_id: &self.room_id,
+                tx: out_tx.clone(),
             }
         });
 
-        // Keep a reference to self.stop_signal_rx to move into tasks
-        // We need to select between receiving a message from the websocket and the stop signal
-        let mut stop_rx = std::mem::replace(&mut self.stop_signal_rx, oneshot::channel().1);
-
-        // Message sending task
-        let send_task = tokio::spawn(async move {
-            while let Some(msg_to_send) = rx.recv().await {
-                if let Err(_) = write.send(msg_to_send).await {
-                    break;
+        // 心跳与发言共用写端
+        let heartbeat_data = stt::encode_message(&[("type", "mrkl")]);
+        let heartbeat = async {
+            let period = Duration::from_secs(45);
+            let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+            loop {
+                let packet = tokio::select! {
+                    _ = ticker.tick() => heartbeat_data.clone(),
+                    Some(packet) = out_rx.recv() => packet,
+                };
+                if let Err(e) = write.send(Message::Binary(packet)).await {
+                    return SessionResult::Err(e.into());
                 }
             }
-        });
-
-        let window = self.window.clone();
-        let room_id_clone = self.room_id.clone();
+        };
 
         // Processing incoming messages
-        loop {
-            tokio::select! {
-                _ = &mut stop_rx => {
-                    eprintln!("[Douyu Danmaku {}] Stop signal received, terminating listener.", room_id_clone);
-                    break;
-                }
-                msg_option = read.next() => {
-                    match msg_option {
-                        Some(Ok(Message::Binary(data))) => {
-                            if data.len() < 13 {
-                                continue;
-                            }
-
-                            let content = String::from_utf8_lossy(&data[12..data.len()-1]);
-                            let mut result = HashMap::new();
-                            for item in content.split('/') {
-                                if item.is_empty() {
-                                    continue;
-                                }
-                                if let Some((key, value)) = item.split_once("@=") {
-                                    result.insert(
-                                        key.to_string(),
-                                        value.replace("@S", "/").replace("@A", "@")
-                                    );
-                                }
-                            }
-
-                            let event_name = format!("danmaku-{}", room_id_clone);
-
-                            if result.get("type").map_or(false, |t| t == "chatmsg") {
-                                let unknown = "unknown".to_string();
-                                let empty = "".to_string();
-                                let zero = "0".to_string();
-
-                                let danmaku = serde_json::json!({
-                                    "type": "chatmsg",
-                                    "nickname": result.get("nn").unwrap_or(&unknown),
-                                    "content": result.get("txt").unwrap_or(&empty),
-                                    "level": result.get("level").unwrap_or(&zero),
-                                    "badgeName": result.get("bnn").unwrap_or(&empty),
-                                    "badgeLevel": result.get("bl").unwrap_or(&zero),
-                                    "color": result.get("col").map_or(None, |c| Some(c.to_string())),
-                                    "room_id": room_id_clone.clone()
-                                });
-
-                                let _ = window.emit(&event_name, danmaku);
-
-                                // 统一向前端发送通用弹幕事件，便于跨平台 DanmuList 使用
-                                let _ = window.emit(
-                                    "danmaku-message",
-                                    crate::platforms::common::DanmakuFrontendPayload {
-                                        room_id: room_id_clone.clone(),
-                                        user: result.get("nn").unwrap_or(&unknown).to_string(),
-                                        content: result.get("txt").unwrap_or(&empty).to_string(),
-                                        user_level: result
-                                            .get("level")
-                                            .unwrap_or(&zero)
-                                            .parse::<i64>()
-                                            .unwrap_or(0),
-                                        fans_club_level: result
-                                            .get("bl")
-                                            .unwrap_or(&zero)
-                                            .parse::<i32>()
-                                            .unwrap_or(0),
-                                    },
-                                );
-                            } else if result.get("type").map_or(false, |t| t == "uenter") {
-                                let unknown = "unknown".to_string();
-                                let empty = "".to_string();
-                                let zero = "0".to_string();
-
-                                let uenter_msg = serde_json::json!({
-                                    "type": "uenter",
-                                    "uid": result.get("uid").unwrap_or(&empty),
-                                    "nickname": result.get("nn").unwrap_or(&unknown),
-                                    "level": result.get("level").unwrap_or(&zero),
-                                    "badgeName": result.get("bnn").unwrap_or(&empty),
-                                    "badgeLevel": result.get("bl").unwrap_or(&zero),
-                                    "room_id": room_id_clone.clone()
-                                });
-                                let _ = window.emit(&event_name, uenter_msg);
-                            }
+        let receive = async {
+            // 一帧可能包含多个包，也可能只有半个包
+            let mut decoder = SttDecoder::default();
+            while let Some(msg) = read.next().await {
+                match msg? {
+                    Message::Binary(data) => {
+                        let frame = decoder.push(&data);
+                        for body in frame.bodies {
+                            self.handle_message(&stt::decode(&body));
                         }
-                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
-                            eprintln!("[Douyu Danmaku {}] Websocket closed or error, terminating listener.", room_id_clone);
-                            break;
+                        if let Some(e) = frame.error {
+                            eprintln!(
+                                "[Douyu Danmaku {}] Dropped malformed frame: {}",
+                                self.room_id, e
+                            );
                         }
-                        _ => {}
                     }
+                    Message::Close(_) => break,
+                    _ => {}
                 }
             }
+            SessionResult::Ok(())
+        };
+
+        tokio::select! {
+            res = heartbeat => res,
+            res = receive => res,
         }
-        send_task.abort();
-        eprintln!("[Douyu Danmaku {}] Listener stopped.", room_id_clone);
-        Ok(())
+    }
+
+    fn handle_message(&self, msg: &SttValue) {
+        let text = |key: &str, default: &str| msg.text(key).unwrap_or(default).to_string();
+
+        if msg.msg_type() == Some("chatmsg") {
+            let color = msg.text("col").and_then(douyu_color);
+            let event = self
+                .sink
+                .event(DanmakuEventKind::Chat, douyu_user(msg), text("txt", ""))
+                .with_color(color);
+            self.sink.emit(event);
+        } else if msg.msg_type() == Some("uenter") {
+            let user = douyu_user(msg);
+            let content = format!("{} 进入了直播间", user.nickname);
+            self.sink
+                .emit(self.sink.event(DanmakuEventKind::Enter, user, content));
+        } else if let Some(event) = self.to_event(msg) {
+            self.sink.emit(event);
+        }
+    }
+
+    // chatmsg / uenter 以外的结构化消息
+    fn to_event(&self, msg: &SttValue) -> Option<DanmakuEvent> {
+        let text = |key: &str| msg.text(key).unwrap_or_default().to_string();
+        let event = match msg.msg_type()? {
+            "dgb" => {
+                let gift_id = text("gfid");
+                let gift = self.gifts.get(&gift_id);
+                let gift_name = gift
+                    .map(|g| g.name.clone())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| format!("礼物{}", gift_id));
+                let gift_count = parse_num::<u32>(msg, "gfcnt").unwrap_or(1).max(1);
+                let content = format!("送出 {} x{}", gift_name, gift_count);
+                self.sink.event(
+                    DanmakuEventKind::Gift {
+                        gift_id: Some(gift_id),
+                        gift_name,
+                        gift_count,
+                        gift_price: gift.and_then(|g| g.price),
+                        // hits 为连击数，1 表示非连击
+                        combo_count: parse_num::<u32>(msg, "hits").filter(|h| *h > 1),
+                    },
+                    douyu_user(msg),
+                    content,
+                )
+            }
+            kind @ ("anbc" | "rnewbc") => {
+                let noble_level = parse_num::<u8>(msg, "nl").unwrap_or(0);
+                let noble_name = noble_name(noble_level);
+                let renew = kind == "rnewbc";
+                let user = DanmakuUser {
+                    nickname: text("unk"),
+                    noble_level,
+                    user_id: Some(text("uid")).filter(|v| !v.is_empty()),
+                    avatar: Some(text("uic"))
+                        .filter(|v| !v.is_empty())
+                        .map(|ic| douyu_avatar(&ic)),
+                    ..Default::default()
+                };
+                let content = format!(
+                    "{} {}了{}",
+                    user.nickname,
+                    if renew { "续费" } else { "开通" },
+                    noble_name
+                );
+                self.sink.event(
+                    DanmakuEventKind::Noble {
+                        noble_level,
+                        noble_name,
+                        renew,
+                    },
+                    user,
+                    content,
+                )
+            }
+            "rss" => {
+                // ss=1 开播，ss=0 下播
+                let live = text("ss") == "1";
+                self.sink.event(
+                    DanmakuEventKind::LiveStatus { live },
+                    DanmakuUser::default(),
+                    if live {
+                        "主播开播了"
+                    } else {
+                        "主播已下播"
+                    },
+                )
+            }
+            "frank" => {
+                let entries: Vec<RankEntry> = msg
+                    .get("list")
+                    .map(|list| list.as_list())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|item| RankEntry {
+                        nickname: item.text("nn").unwrap_or_default().to_string(),
+                        user_id: item.text("uid").map(str::to_string),
+                        avatar: item.text("ic").map(douyu_avatar),
+                        score: parse_num(item, "fim"),
+                    })
+                    .collect();
+                let content = match parse_num::<u64>(msg, "fc") {
+                    Some(fans) => format!("粉丝榜更新，粉丝团 {} 人", fans),
+                    None => "粉丝榜更新".to_string(),
+                };
+                self.sink.event(
+                    DanmakuEventKind::Rank { entries },
+                    DanmakuUser::default(),
+                    content,
+                )
+            }
+            "spbc" => {
+                let content = format!(
+                    "{} 送给 {} {} x{}",
+                    text("sn"),
+                    text("dn"),
+                    text("gn"),
+                    parse_num::<u32>(msg, "gc").unwrap_or(1)
+                );
+                self.sink.event(
+                    DanmakuEventKind::Broadcast {
+                        source_room_id: Some(text("drid")).filter(|v| !v.is_empty()),
+                    },
+                    DanmakuUser::named(text("sn")),
+                    content,
+                )
+            }
+            "upgrade" => {
+                let user = douyu_user(msg);
+                let content = format!("{} 升级到 {} 级", user.nickname, user.user_level);
+                self.sink.event(DanmakuEventKind::LevelUp, user, content)
+            }
+            _ => return None,
+        };
+        Some(event)
     }
 }
@@ -90,17 +90,17 @@ pub struct CategoriesApiResponse {
 
 // Helper structs for the transformation (intermediate step before common types)
 #[derive(Debug, Clone)]
-struct RawFrontendCate2Item {
-    id: String,
-    name: String,
-    short_name: String,
-    icon: String,
+pub(crate) struct RawFrontendCate2Item {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) short_name: String,
+    pub(crate) icon: String,
 }
 #[derive(Debug, Clone)]
-struct RawFrontendCate1Item {
-    id: String,
-    name: String,
-    cate2_list: Vec<RawFrontendCate2Item>,
+pub(crate) struct RawFrontendCate1Item {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) cate2_list: Vec<RawFrontendCate2Item>,
 }
 
 // New transformation function from Raw types to Frontend types
@@ -151,7 +151,7 @@ pub async fn fetch_categories() -> Result<CategoriesApiResponse, String> {
 }
 
 // Internal function to fetch and parse to the old frontend-specific structure
-async fn fetch_categories_douyu_raw() -> Result<Vec<RawFrontendCate1Item>, String> {
+pub(crate) async fn fetch_categories_douyu_raw() -> Result<Vec<RawFrontendCate1Item>, String> {
     let client = reqwest::Client::builder()
         .no_proxy()
         .build()
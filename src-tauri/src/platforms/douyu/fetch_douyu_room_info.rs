@@ -11,12 +11,12 @@ use crate::platforms::common::FollowHttpClient;
 // Define the structure to be returned to TypeScript
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct DouyuFollowInfo {
-    room_id: String,
-    room_name: Option<String>,
-    nickname: Option<String>,
-    avatar_url: Option<String>,
-    video_loop: Option<i64>,
-    show_status: Option<i64>,
+    pub room_id: String,
+    pub room_name: Option<String>,
+    pub nickname: Option<String>,
+    pub avatar_url: Option<String>,
+    pub video_loop: Option<i64>,
+    pub show_status: Option<i64>,
 }
 
 #[tauri::command]
@@ -0,0 +1,69 @@
+// 斗鱼礼物配置：dgb 消息只带 gfid，需要通过房间礼物列表解析名称和价格
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+const GIFT_LIST_URL: &str = "https://gift.douyucdn.cn/api/gift/v2/web/list";
+
+#[derive(Debug, Clone)]
+pub struct DouyuGift {
+    pub name: String,
+    /// 单价（元）；鱼丸等免费礼物为 None
+    pub price: Option<f64>,
+}
+
+fn value_to_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 获取房间可用的礼物列表，返回 gfid -> 礼物信息
+pub async fn fetch_gift_catalog(
+    client: &reqwest::Client,
+    room_id: &str,
+) -> Result<HashMap<String, DouyuGift>, String> {
+    let body: Value = client
+        .get(GIFT_LIST_URL)
+        .query(&[("rid", room_id)])
+        .header("Referer", format!("https://www.douyu.com/{}", room_id))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to request Douyu gift list: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Douyu gift list: {}", e))?;
+
+    if body["error"].as_i64().unwrap_or(-1) != 0 {
+        return Err(format!(
+            "Douyu gift list returned error {}: {}",
+            body["error"],
+            body["msg"].as_str().unwrap_or("")
+        ));
+    }
+
+    let mut catalog = HashMap::new();
+    for gift in body["data"]["giftList"].as_array().into_iter().flatten() {
+        let id = value_to_string(&gift["id"]);
+        if id.is_empty() {
+            continue;
+        }
+        let price_info = &gift["priceInfo"];
+        // price 单位为分；鱼丸礼物不计价值
+        let free = value_to_string(&price_info["priceType"]).contains("鱼丸");
+        let price = price_info["price"]
+            .as_f64()
+            .filter(|p| *p > 0.0 && !free)
+            .map(|p| p / 100.0);
+        catalog.insert(
+            id,
+            DouyuGift {
+                name: gift["name"].as_str().unwrap_or_default().to_string(),
+                price,
+            },
+        );
+    }
+    Ok(catalog)
+}
@@ -1,9 +1,12 @@
+pub mod connection;
 pub mod danmu_start;
 pub mod fetch_douyu_main_categories;
 pub mod fetch_douyu_room_info;
+pub mod gift;
 pub mod live_list;
 pub mod search_anchor;
 pub mod stream_url;
+pub mod stt;
 pub mod three_cate;
 pub mod types;
 
@@ -13,3 +16,4 @@ pub use live_list::*;
 pub use search_anchor::*;
 pub use stream_url::*;
 pub use three_cate::*;
+pub mod platform;
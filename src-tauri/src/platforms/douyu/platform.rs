@@ -0,0 +1,186 @@
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::platforms::common::live_platform::LivePlatform;
+use crate::platforms::common::types::LiveStreamInfo;
+use crate::platforms::common::types_rust::{
+    CommonLiveListRust, CommonPlatformCategoryRust, CommonRoomInfoRust, CommonStreamerRust,
+    SupportedPlatformRust,
+};
+use crate::platforms::common::FollowHttpClient;
+use crate::platforms::douyu::fetch_douyu_main_categories::fetch_categories_douyu_raw;
+use crate::platforms::douyu::{
+    fetch_douyu_room_info, fetch_live_list, get_stream_url_with_quality, perform_anchor_search,
+};
+
+const LIVE_LIST_PAGE_SIZE: u32 = 20;
+
+pub struct DouyuPlatform {
+    app_handle: AppHandle,
+}
+
+impl DouyuPlatform {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl LivePlatform for DouyuPlatform {
+    fn platform(&self) -> SupportedPlatformRust {
+        SupportedPlatformRust::Douyu
+    }
+
+    async fn room_info(&self, room_id: &str) -> Result<CommonRoomInfoRust, String> {
+        let follow_http = self.app_handle.state::<FollowHttpClient>();
+        let info = fetch_douyu_room_info(room_id.to_string(), follow_http).await?;
+        // show_status == 1 为开播，video_loop == 1 为轮播（视为未直播）
+        let is_live = info.show_status == Some(1) && info.video_loop != Some(1);
+        Ok(CommonRoomInfoRust {
+            platform: self.platform(),
+            room_id: info.room_id,
+            title: info.room_name,
+            anchor_name: info.nickname,
+            avatar: info.avatar_url,
+            is_live,
+        })
+    }
+
+    async fn stream_url(
+        &self,
+        room_id: &str,
+        quality: &str,
+        line: Option<&str>,
+    ) -> Result<LiveStreamInfo, String> {
+        let url = get_stream_url_with_quality(room_id, quality, line)
+            .await
+            .map_err(|e| format!("Failed to get Douyu stream URL: {}", e))?;
+        Ok(LiveStreamInfo {
+            title: None,
+            anchor_name: None,
+            avatar: None,
+            stream_url: Some(url.clone()),
+            status: Some(1),
+            error_message: None,
+            upstream_url: Some(url),
+            available_streams: None,
+            normalized_room_id: Some(room_id.to_string()),
+            web_rid: None,
+            proxy_session_id: None,
+        })
+    }
+
+    async fn search(&self, keyword: &str, page: u32) -> Result<Vec<CommonStreamerRust>, String> {
+        let text = perform_anchor_search(keyword, page)
+            .await
+            .map_err(|e| e.to_string())?;
+        let json: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse Douyu search response: {}", e))?;
+
+        let users = json
+            .get("data")
+            .and_then(|d| d.get("relateUser"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let results = users
+            .iter()
+            // type == 1 为主播
+            .filter(|u| u.get("type").and_then(|t| t.as_i64()) == Some(1))
+            .filter_map(|u| u.get("anchorInfo"))
+            .map(|a| {
+                let str_of = |key: &str| -> String {
+                    match a.get(key) {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(Value::Number(n)) => n.to_string(),
+                        _ => String::new(),
+                    }
+                };
+                let title = {
+                    let room_name = str_of("roomName");
+                    if room_name.is_empty() {
+                        str_of("description")
+                    } else {
+                        room_name
+                    }
+                };
+                let is_live = a.get("isLive").and_then(|v| v.as_i64()) == Some(1)
+                    && a.get("videoLoop").and_then(|v| v.as_i64()) != Some(1);
+                CommonStreamerRust {
+                    room_id: str_of("rid"),
+                    title,
+                    nickname: str_of("nickName"),
+                    avatar: str_of("avatar"),
+                    room_cover: str_of("roomSrc"),
+                    viewer_count_str: str_of("hot"),
+                    platform: SupportedPlatformRust::Douyu,
+                    is_live: Some(is_live),
+                }
+            })
+            .collect();
+        Ok(results)
+    }
+
+    async fn categories(&self) -> Result<Vec<CommonPlatformCategoryRust>, String> {
+        let cate1_list = fetch_categories_douyu_raw().await?;
+        let mut categories = Vec::new();
+        for cate1 in cate1_list {
+            categories.push(CommonPlatformCategoryRust {
+                id: cate1.id.clone(),
+                name: cate1.name,
+                platform: self.platform(),
+                icon_url: None,
+                parent_id: None,
+            });
+            for cate2 in cate1.cate2_list {
+                categories.push(CommonPlatformCategoryRust {
+                    id: cate2.id,
+                    name: cate2.name,
+                    platform: self.platform(),
+                    icon_url: Some(cate2.icon),
+                    parent_id: Some(cate1.id.clone()),
+                });
+            }
+        }
+        Ok(categories)
+    }
+
+    async fn live_list(
+        &self,
+        category_id: &str,
+        _parent_id: Option<&str>,
+        page: u32,
+    ) -> Result<CommonLiveListRust, String> {
+        let offset = (page - 1) * LIVE_LIST_PAGE_SIZE;
+        let response = fetch_live_list(offset, category_id.to_string(), LIVE_LIST_PAGE_SIZE).await;
+        if response.error != 0 {
+            return Err(response
+                .msg
+                .unwrap_or_else(|| format!("Douyu API error {}", response.error)));
+        }
+        let data = response
+            .data
+            .ok_or_else(|| "Douyu live list response has no data".to_string())?;
+
+        let has_more = offset + (data.list.len() as u32) < data.total;
+        let rooms = data
+            .list
+            .into_iter()
+            .map(|s| CommonStreamerRust {
+                room_id: s.rid,
+                title: s.room_name,
+                nickname: s.nickname,
+                avatar: s.avatar,
+                room_cover: s.room_src,
+                viewer_count_str: s.hn,
+                platform: SupportedPlatformRust::Douyu,
+                is_live: s.is_live,
+            })
+            .collect();
+        Ok(CommonLiveListRust {
+            rooms,
+            has_more,
+            page,
+        })
+    }
+}
@@ -8,7 +8,11 @@ use reqwest::{
 use std::time::{SystemTime, UNIX_EPOCH}; // For timestamp for did // For URL encoding keyword
 
 // Renamed from search_anchor to avoid ambiguity with Tauri command
-pub async fn perform_anchor_search(keyword: &str) -> Result<String, Box<dyn std::error::Error>> {
+// page 从 1 开始，每页 20 条
+pub async fn perform_anchor_search(
+    keyword: &str,
+    page: u32,
+) -> Result<String, Box<dyn std::error::Error>> {
     let mut default_headers = HeaderMap::new();
     default_headers.insert(
         "User-Agent",
@@ -31,8 +35,9 @@ pub async fn perform_anchor_search(keyword: &str) -> Result<String, Box<dyn std:
     let did = format!("{:x}", hasher.finalize());
 
     let url = format!(
-        "https://www.douyu.com/japi/search/api/searchUser?kw={}&page=1&pageSize=20&filterType=0",
-        percent_encode(keyword.as_bytes(), NON_ALPHANUMERIC)
+        "https://www.douyu.com/japi/search/api/searchUser?kw={}&page={}&pageSize=20&filterType=0",
+        percent_encode(keyword.as_bytes(), NON_ALPHANUMERIC),
+        page
     );
 
     let text = client
@@ -1,4 +1,3 @@
-use deno_core::{JsRuntime, RuntimeOptions};
 use md5::Digest;
 use regex::Regex;
 use reqwest::{
@@ -8,7 +7,11 @@ use reqwest::{
 };
 use serde::Deserialize;
 use serde_json::Value;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::platforms::common::js_service::JsService;
 
 #[derive(Deserialize, Debug)]
 struct RoomInfoData {
@@ -43,6 +46,26 @@ fn value_to_i32(value: &Value) -> Option<i32> {
     }
 }
 
+// 页面脚本 ub98484234() 生成的签名函数，同一房间短时间内不变，缓存后可省去抓取页面和执行混淆脚本
+const SIGN_TEMPLATE_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone)]
+struct SignTemplate {
+    source: String,
+    v: String,
+}
+
+fn sign_templates() -> &'static Mutex<HashMap<String, (Instant, SignTemplate)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (Instant, SignTemplate)>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn cached_sign_template(rid: &str) -> Option<SignTemplate> {
+    let mut cache = sign_templates().lock().unwrap();
+    cache.retain(|_, (created, _)| created.elapsed() < SIGN_TEMPLATE_TTL);
+    cache.get(rid).map(|(_, template)| template.clone())
+}
+
 struct DouYu {
     did: String,
     rid: String,
@@ -106,96 +129,46 @@ impl DouYu {
         format!("{:x}", hasher.finalize())
     }
 
-    async fn execute_js_functions(
-        &self,
+    // 执行页面中的混淆脚本，取出签名函数源码和其中的 v 参数
+    async fn extract_sign_template(
         func_ub9: &str,
-        rid: &str,
-        did: &str,
-        t10: &str,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let mut runtime = JsRuntime::new(RuntimeOptions::default());
-
-        // 执行第一个函数
-        let func_ub9_static = String::from(func_ub9);
-        runtime.execute_script("[douyu]", deno_core::FastString::from(func_ub9_static))?;
-        let js_result = runtime.execute_script(
-            "[douyu]",
-            deno_core::FastString::from(String::from("ub98484234()")),
-        )?;
-
-        // 获取 JavaScript 执行结果
-        let res = {
-            let scope = &mut runtime.handle_scope();
-            let result = js_result.open(scope);
-            result.to_rust_string_lossy(scope)
-        };
+    ) -> Result<SignTemplate, Box<dyn std::error::Error>> {
+        let source = JsService::global()
+            .eval(vec![func_ub9.to_string(), "ub98484234()".to_string()])
+            .await?;
 
         // 提取v参数
         let re = Regex::new(r"v=(\d+)")?;
         let v = re
-            .captures(&res)
+            .captures(&source)
             .ok_or("v parameter not found")?
             .get(1)
             .ok_or("No capture group")?
-            .as_str();
+            .as_str()
+            .to_string();
+
+        Ok(SignTemplate { source, v })
+    }
 
-        let rb = Self::md5(&format!("{}{}{}{}", rid, did, t10, v));
+    async fn sign_params(
+        &self,
+        template: &SignTemplate,
+        t10: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let (rid, did) = (&self.rid, &self.did);
+        let rb = Self::md5(&format!("{}{}{}{}", rid, did, t10, template.v));
 
         // 构造签名函数
-        let func_sign = res.replace("return rt;})", "return rt;}");
+        let func_sign = template.source.replace("return rt;})", "return rt;}");
         let func_sign = func_sign.replace("(function (", "function sign(");
         let func_sign = func_sign.replace("CryptoJS.MD5(cb).toString()", &format!("\"{}\"", rb));
 
-        let func_sign_static = String::from(func_sign);
-        runtime.execute_script("[douyu]", deno_core::FastString::from(func_sign_static))?;
-
         let sign_call = format!("sign(\"{}\", \"{}\", \"{}\");", rid, did, t10);
-
-        let sign_call_static = String::from(sign_call);
-        let js_params =
-            runtime.execute_script("[douyu]", deno_core::FastString::from(sign_call_static))?;
-
-        // 获取签名结果
-        let params = {
-            let scope = &mut runtime.handle_scope();
-            let result = js_params.open(scope);
-            result.to_rust_string_lossy(scope)
-        };
-
+        let params = JsService::global().eval(vec![func_sign, sign_call]).await?;
         Ok(params)
     }
 
-    async fn get_pc_js(
-        &self,
-        cdn: &str,
-        rate: i32,
-    ) -> Result<DouyuStreamResult, Box<dyn std::error::Error>> {
-        match self.check_room_status().await {
-            Ok(true) => {
-                println!(
-                    "[Douyu Stream URL] Room {} is live. Proceeding to fetch stream URL.",
-                    self.rid
-                );
-            }
-            Ok(false) => {
-                println!(
-                    "[Douyu Stream URL] Room {} is not live. Aborting stream fetch.",
-                    self.rid
-                );
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "主播未开播",
-                )));
-            }
-            Err(e) => {
-                println!("[Douyu Stream URL] Error checking room status for room {}: {}. Proceeding with caution or returning error.", self.rid, e);
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("检查房间状态失败: {}", e),
-                )));
-            }
-        }
-
+    async fn fetch_sign_template(&self) -> Result<SignTemplate, Box<dyn std::error::Error>> {
         // 获取PC网页内容（保持与 isahc 等价的头部）
         let page_url = format!("https://www.douyu.com/{}", self.rid);
         let text = self.client
@@ -240,14 +213,58 @@ impl DouYu {
         let re_eval = Regex::new(r"eval.*?;\}")?;
         let func_ub9 = re_eval.replace_all(&result, "strc;}");
 
+        Self::extract_sign_template(&func_ub9).await
+    }
+
+    async fn get_pc_js(
+        &self,
+        cdn: &str,
+        rate: i32,
+    ) -> Result<DouyuStreamResult, Box<dyn std::error::Error>> {
+        match self.check_room_status().await {
+            Ok(true) => {
+                println!(
+                    "[Douyu Stream URL] Room {} is live. Proceeding to fetch stream URL.",
+                    self.rid
+                );
+            }
+            Ok(false) => {
+                println!(
+                    "[Douyu Stream URL] Room {} is not live. Aborting stream fetch.",
+                    self.rid
+                );
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "主播未开播",
+                )));
+            }
+            Err(e) => {
+                println!("[Douyu Stream URL] Error checking room status for room {}: {}. Proceeding with caution or returning error.", self.rid, e);
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("检查房间状态失败: {}", e),
+                )));
+            }
+        }
+
+        let template = match cached_sign_template(&self.rid) {
+            Some(template) => template,
+            None => {
+                let template = self.fetch_sign_template().await?;
+                sign_templates()
+                    .lock()
+                    .unwrap()
+                    .insert(self.rid.clone(), (Instant::now(), template.clone()));
+                template
+            }
+        };
+
         let t10 = SystemTime::now()
             .duration_since(UNIX_EPOCH)?
             .as_secs()
             .to_string();
 
-        let mut params = self
-            .execute_js_functions(&func_ub9, &self.rid, &self.did, &t10)
-            .await?;
+        let mut params = self.sign_params(&template, &t10).await?;
         params.push_str(&format!("&cdn={}&rate={}", cdn, rate));
 
         // 获取真实URL
@@ -268,9 +285,11 @@ impl DouYu {
             .json::<serde_json::Value>()
             .await?;
 
-        let data = json["data"]
-            .as_object()
-            .ok_or("No data field in response")?;
+        let Some(data) = json["data"].as_object() else {
+            // 签名被拒时缓存的签名函数可能已失效，下次重新抓取
+            sign_templates().lock().unwrap().remove(&self.rid);
+            return Err("No data field in response".into());
+        };
         let rtmp_url = data["rtmp_url"].as_str().ok_or("No rtmp_url field")?;
         let rtmp_live = data["rtmp_live"].as_str().ok_or("No rtmp_live field")?;
 
@@ -0,0 +1,331 @@
+// 斗鱼弹幕协议（STT）编解码
+//
+// 包格式（小端）：
+//   len(u32) | len(u32) | msg_type(u16) | encrypt(u8) | reserved(u8) | body | '\0'
+// 其中 len = 4 + 2 + 1 + 1 + body.len() + 1，不包含第一个 len 字段本身。
+// 客户端发出的 msg_type 为 689，服务端下发为 690。一个 WebSocket 帧可能包含多个包，
+// 也可能只有半个包，所以用 SttDecoder 缓冲后再切分。
+//
+// body 为 STT 文本："key@=value/key@=value/"，值中的 '@' 转义为 "@A"，'/' 转义为 "@S"。
+// 值本身也可以是转义过的 STT 文本，从而表示嵌套的列表（"a/b/"）和字典。
+use std::sync::OnceLock;
+
+pub const CLIENT_MSG_TYPE: u16 = 689;
+pub const SERVER_MSG_TYPE: u16 = 690;
+// len 之后固定部分的长度：len(4) + msg_type(2) + encrypt(1) + reserved(1) + '\0'(1)
+const FIXED_LEN: usize = 9;
+// 单个包的上限，超过则认为数据已错位
+const MAX_PACKET_LEN: usize = 1 << 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SttError {
+    #[error("length fields mismatch: {0} != {1}")]
+    LengthMismatch(u32, u32),
+    #[error("invalid packet length {0}")]
+    InvalidLength(u32),
+    #[error("unexpected message type {0}")]
+    UnexpectedType(u16),
+}
+
+/// STT 值：保存反转义后的原文，按列表/字典访问时才解析下一层。
+/// 文本取值总是返回原文，聊天内容里的 "@"、"/" 不会因为被误当成列表而改变。
+#[derive(Debug, Clone, Default)]
+pub struct SttValue {
+    raw: String,
+    node: OnceLock<SttNode>,
+}
+
+#[derive(Debug, Clone)]
+enum SttNode {
+    Scalar,
+    List(Vec<SttValue>),
+    /// 保留字段顺序，编码时 `type` 等字段仍在最前
+    Map(Vec<(String, SttValue)>),
+}
+
+pub fn escape(s: &str) -> String {
+    s.replace('@', "@A").replace('/', "@S")
+}
+
+pub fn unescape(s: &str) -> String {
+    s.replace("@S", "/").replace("@A", "@")
+}
+
+/// 解析已反转义的 STT 文本，`decode(s).encode() == s`
+pub fn decode(s: &str) -> SttValue {
+    SttValue::new(s.to_string())
+}
+
+// 只解析一层。只有以 '/' 结尾时才当作列表或字典：各项都含 "@=" 为字典，都不含为列表，混合则视为普通文本
+fn parse_node(s: &str) -> SttNode {
+    let Some(inner) = s.strip_suffix('/') else {
+        return SttNode::Scalar;
+    };
+    let items: Vec<&str> = inner.split('/').collect();
+    let pairs = items.iter().filter(|item| item.contains("@=")).count();
+    if pairs == items.len() {
+        SttNode::Map(
+            items
+                .iter()
+                .filter_map(|item| item.split_once("@="))
+                .map(|(k, v)| (unescape(k), SttValue::new(unescape(v))))
+                .collect(),
+        )
+    } else if pairs == 0 {
+        SttNode::List(
+            items
+                .iter()
+                .map(|item| SttValue::new(unescape(item)))
+                .collect(),
+        )
+    } else {
+        SttNode::Scalar
+    }
+}
+
+impl SttValue {
+    fn new(raw: String) -> Self {
+        Self {
+            raw,
+            node: OnceLock::new(),
+        }
+    }
+
+    pub fn map<K: AsRef<str>, V: AsRef<str>>(pairs: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self::new(
+            pairs
+                .into_iter()
+                .map(|(k, v)| format!("{}@={}/", escape(k.as_ref()), escape(v.as_ref())))
+                .collect(),
+        )
+    }
+
+    fn node(&self) -> &SttNode {
+        self.node.get_or_init(|| parse_node(&self.raw))
+    }
+
+    /// 编码为 STT 文本（未转义的一层）
+    pub fn encode(&self) -> String {
+        self.raw.clone()
+    }
+
+    /// 字典取值
+    pub fn get(&self, key: &str) -> Option<&SttValue> {
+        match self.node() {
+            SttNode::Map(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// 以文本形式取值，列表/字典返回原始 STT 文本
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.get(key).map(|v| v.as_text())
+    }
+
+    pub fn as_text(&self) -> &str {
+        &self.raw
+    }
+
+    /// 把单个值也视为只有一项的列表，方便处理只有一个元素时不带 '/' 的字段
+    pub fn as_list(&self) -> &[SttValue] {
+        match self.node() {
+            SttNode::List(items) => items,
+            _ if self.raw.is_empty() => &[],
+            _ => std::slice::from_ref(self),
+        }
+    }
+
+    /// 消息类型（`type` 字段）
+    pub fn msg_type(&self) -> Option<&str> {
+        self.text("type")
+    }
+}
+
+impl PartialEq for SttValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for SttValue {}
+
+/// 编码一个客户端发出的包
+pub fn encode_packet(body: &str) -> Vec<u8> {
+    let body = body.as_bytes();
+    let packet_len = (body.len() + FIXED_LEN) as u32;
+
+    let mut result = Vec::with_capacity(body.len() + FIXED_LEN + 4);
+    result.extend_from_slice(&packet_len.to_le_bytes());
+    result.extend_from_slice(&packet_len.to_le_bytes());
+    result.extend_from_slice(&CLIENT_MSG_TYPE.to_le_bytes());
+    result.push(0);
+    result.push(0);
+    result.extend_from_slice(body);
+    result.push(0);
+    result
+}
+
+/// 编码字段列表，例如 `encode_message(&[("type", "mrkl")])`
+pub fn encode_message(pairs: &[(&str, &str)]) -> Vec<u8> {
+    encode_packet(&SttValue::map(pairs.iter().copied()).encode())
+}
+
+/// 一帧数据的解析结果。数据错位时 `error` 记录原因，错位之前已解析出的包仍保留在 `bodies` 中
+#[derive(Debug, Default)]
+pub struct DecodedFrame {
+    pub bodies: Vec<String>,
+    pub error: Option<SttError>,
+}
+
+/// 按长度前缀切分包，跨帧的半个包会留在缓冲区等待后续数据
+#[derive(Default)]
+pub struct SttDecoder {
+    buf: Vec<u8>,
+}
+
+impl SttDecoder {
+    /// 追加一帧数据，返回其中所有完整包的 body。
+    /// 遇到错位的数据时清空缓冲区（无法再定位下一个包），并在结果中记录错误。
+    pub fn push(&mut self, data: &[u8]) -> DecodedFrame {
+        self.buf.extend_from_slice(data);
+        let mut frame = DecodedFrame::default();
+        let mut offset = 0;
+        let error = loop {
+            let rest = &self.buf[offset..];
+            if rest.len() < 12 {
+                break None;
+            }
+            let len1 = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            let len2 = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]);
+            if len1 != len2 {
+                break Some(SttError::LengthMismatch(len1, len2));
+            }
+            let packet_len = len1 as usize;
+            if !(FIXED_LEN..=MAX_PACKET_LEN).contains(&packet_len) {
+                break Some(SttError::InvalidLength(len1));
+            }
+            let msg_type = u16::from_le_bytes([rest[8], rest[9]]);
+            if msg_type != SERVER_MSG_TYPE && msg_type != CLIENT_MSG_TYPE {
+                break Some(SttError::UnexpectedType(msg_type));
+            }
+            let total = packet_len + 4;
+            if rest.len() < total {
+                break None;
+            }
+            let body = &rest[12..total];
+            let body = body.strip_suffix(&[0]).unwrap_or(body);
+            frame
+                .bodies
+                .push(String::from_utf8_lossy(body).into_owned());
+            offset += total;
+        };
+        if error.is_some() {
+            self.buf.clear();
+        } else {
+            self.buf.drain(..offset);
+        }
+        frame.error = error;
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_packet(body: &str) -> Vec<u8> {
+        let mut packet = encode_packet(body);
+        packet[8..10].copy_from_slice(&SERVER_MSG_TYPE.to_le_bytes());
+        packet
+    }
+
+    #[test]
+    fn splits_multiple_packets_in_one_frame() {
+        let mut frame = server_packet("type@=chatmsg/txt@=a/");
+        frame.extend(server_packet("type@=uenter/nn@=b/"));
+
+        let mut decoder = SttDecoder::default();
+        let bodies = decoder.push(&frame).bodies;
+        assert_eq!(bodies, vec!["type@=chatmsg/txt@=a/", "type@=uenter/nn@=b/"]);
+    }
+
+    #[test]
+    fn buffers_half_packets_across_frames() {
+        let first = server_packet("type@=chatmsg/txt@=hello/");
+        let second = server_packet("type@=mrkl/");
+        let mut stream = first.clone();
+        stream.extend(&second);
+        let split = first.len() + 5;
+
+        let mut decoder = SttDecoder::default();
+        assert!(decoder.push(&stream[..6]).bodies.is_empty());
+        assert_eq!(
+            decoder.push(&stream[6..split]).bodies,
+            vec!["type@=chatmsg/txt@=hello/"]
+        );
+        assert_eq!(decoder.push(&stream[split..]).bodies, vec!["type@=mrkl/"]);
+        assert!(decoder.push(&[]).bodies.is_empty());
+    }
+
+    #[test]
+    fn rejects_mismatched_lengths() {
+        let mut packet = server_packet("type@=mrkl/");
+        packet[4] ^= 1;
+        let mut decoder = SttDecoder::default();
+        let frame = decoder.push(&packet);
+        assert!(frame.bodies.is_empty());
+        assert!(matches!(frame.error, Some(SttError::LengthMismatch(_, _))));
+        // 出错后缓冲区被清空，后续数据可以继续解析
+        let frame = decoder.push(&server_packet("type@=mrkl/"));
+        assert_eq!(frame.bodies, vec!["type@=mrkl/"]);
+        assert!(frame.error.is_none());
+    }
+
+    #[test]
+    fn keeps_packets_before_corrupt_header() {
+        let mut data = server_packet("type@=chatmsg/txt@=a/");
+        let mut corrupt = server_packet("type@=mrkl/");
+        corrupt[8..10].copy_from_slice(&1u16.to_le_bytes());
+        data.extend(corrupt);
+        let mut decoder = SttDecoder::default();
+        let frame = decoder.push(&data);
+        assert_eq!(frame.bodies, vec!["type@=chatmsg/txt@=a/"]);
+        assert!(matches!(frame.error, Some(SttError::UnexpectedType(1))));
+        assert!(decoder.push(&[]).bodies.is_empty());
+    }
+
+    #[test]
+    fn keeps_escaped_at_and_slash_in_text() {
+        for txt in ["x@y/", "a/b/", "@A@S", "k@=v/", "/"] {
+            let body = SttValue::map([("type", "chatmsg"), ("txt", txt)]).encode();
+            let msg = decode(&body);
+            assert_eq!(msg.msg_type(), Some("chatmsg"));
+            assert_eq!(msg.text("txt"), Some(txt));
+            assert_eq!(msg.encode(), body);
+        }
+    }
+
+    #[test]
+    fn decode_encode_round_trip() {
+        let list: String = ["nn@=a/uid@=1/", "nn@=b/uid@=2/"]
+            .iter()
+            .map(|item| format!("{}/", escape(item)))
+            .collect();
+        let body = format!("type@=frank/list@={}/fc@=3/", escape(&list));
+        let msg = decode(&body);
+        assert_eq!(msg.encode(), body);
+
+        let list = msg.get("list").unwrap().as_list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].text("nn"), Some("a"));
+        assert_eq!(list[1].text("uid"), Some("2"));
+        assert_eq!(msg.text("fc"), Some("3"));
+    }
+
+    #[test]
+    fn single_value_is_a_one_item_list() {
+        let msg = decode("type@=x/one@=a/empty@=/");
+        assert_eq!(msg.get("one").unwrap().as_list().len(), 1);
+        assert!(msg.get("empty").unwrap().as_list().is_empty());
+    }
+}
@@ -1,18 +1,25 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
 use futures_util::{SinkExt, StreamExt};
-use log::info;
+use log::{debug, info, trace};
 use tars_stream::prelude::*;
-use tauri::Emitter;
-use tokio::sync::mpsc as tokio_mpsc;
-use tokio::time::{sleep, Duration};
+use tauri::ipc::Channel;
+use tauri::Manager;
+use tokio::time::{sleep, Duration, Instant};
 use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
 
-const WS_URL: &str = "wss://cdnws.api.huya.com";
-// 恢复 HEARTBEAT 常量（被误删），供心跳发送使用
-const HEARTBEAT: &'static [u8] = b"\x00\x03\x1d\x00\x00\x69\x00\x00\x00\x69\x10\x03\x2c\x3c\x4c\x56\x08\x6f\x6e\x6c\x69\x6e\x65\x75\x69\x66\x0f\x4f\x6e\x55\x73\x65\x72\x48\x65\x61\x72\x74\x42\x65\x61\x74\x7d\x00\x00\x3c\x08\x00\x01\x06\x04\x74\x52\x65\x71\x1d\x00\x00\x2f\x0a\x0a\x0c\x16\x00\x26\x00\x36\x07\x61\x64\x72\x5f\x77\x61\x70\x46\x00\x0b\x12\x03\xae\xf0\x0f\x22\x03\xae\xf0\x0f\x3c\x42\x6d\x52\x02\x60\x5c\x60\x01\x7c\x82\x00\x0b\xb0\x1f\x9c\xac\x0b\x8c\x98\x0c\xa8\x0c";
-// const HEARTBEAT_BASE64: &str = "ABQdAAwsNgBM"; // same as Python
-#[allow(dead_code)]
-const HEARTBEAT_BASE64: &str = "ABQdAAwsNgBM"; // same as Python
+use crate::platforms::common::danmaku::event::rgb_to_hex;
+use crate::platforms::common::danmaku::reconnect::{wait_or_shutdown, STABLE_SESSION};
+use crate::platforms::common::danmaku::{
+    Backoff, BatchOptions, DanmakuBatch, DanmakuChannel, DanmakuEvent, DanmakuEventKind,
+    DanmakuListenerRegistry, DanmakuSink, DanmakuStatus, DanmakuUser,
+};
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+use super::tars::{self as huya_tars, WSPushMessage, WebSocketCommand};
 
+const WS_URL: &str = "wss://cdnws.api.huya.com";
 // Minimal JCE/TARS codec for required Huya structures
 
 async fn fetch_huya_ids(room_id: &str) -> Result<(i64, i64), String> {
@@ -93,224 +100,204 @@ pub async fn fetch_huya_join_params(room_id: String) -> Result<HuyaJoinParams, S
 #[tauri::command]
 pub async fn start_huya_danmaku_listener(
     payload: crate::platforms::common::GetStreamUrlPayload,
+    channel: Option<Channel<DanmakuBatch>>,
+    batch: Option<BatchOptions>,
     app_handle: tauri::AppHandle,
-    state: tauri::State<'_, crate::platforms::common::HuyaDanmakuState>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
     let room_id_or_url = payload.args.room_id_str.clone();
-    println!(
-        "[Huya Danmaku] start listener room_id_or_url={}",
-        room_id_or_url
-    );
     info!(
         "[Huya Danmaku] start listener room_id_or_url={}",
         room_id_or_url
     );
 
-    // 停止已有监听
-    let previous_tx = {
-        let mut lock = state.inner().0.lock().unwrap();
-        lock.take()
-    };
-    if let Some(tx) = previous_tx {
-        if tx.send(()).await.is_err() {
-            eprintln!("[Huya Danmaku] 旧任务关闭失败，可能已退出。");
-        }
-    }
-
-    // 创建新的关闭通道并保存到 State
-    let (tx_shutdown, mut rx_shutdown) = tokio_mpsc::channel::<()>(1);
-    {
-        let mut lock = state.inner().0.lock().unwrap();
-        *lock = Some(tx_shutdown);
-    }
+    // 注册到监听表（同房间的旧监听会被停止，其它房间不受影响）
+    let (listener_id, rx_shutdown) =
+        registry.register(SupportedPlatformRust::Huya, &room_id_or_url);
 
     let app_handle_clone = app_handle.clone();
     let room_id_clone = room_id_or_url.clone();
+    let delivery = DanmakuChannel::open(&app_handle, channel, batch);
 
     tokio::spawn(async move {
-        println!(
-            "[Huya Danmaku] spawned worker for room_id={}",
-            room_id_clone
+        let sink = DanmakuSink::new(
+            app_handle_clone.clone(),
+            SupportedPlatformRust::Huya,
+            &room_id_clone,
+        )
+        .with_channel(delivery)
+        .with_listener_id(listener_id);
+        run_huya_listener(&sink, rx_shutdown).await;
+        app_handle_clone.state::<DanmakuListenerRegistry>().finish(
+            SupportedPlatformRust::Huya,
+            &room_id_clone,
+            listener_id,
         );
-        info!(
-            "[Huya Danmaku] spawned worker for room_id={}",
-            room_id_clone
-        );
-        // 1) 获取 ws 与注册数据（与根目录 huya.rs 同步）
-        let (ws_url, reg_data) = match get_ws_info_tars(&room_id_clone).await {
-            Ok(v) => v,
-            Err(e) => {
-                let _ = app_handle_clone.emit(
-                    "danmaku-message",
-                    crate::platforms::common::DanmakuFrontendPayload {
-                        room_id: room_id_clone.clone(),
-                        user: "系统".to_string(),
-                        content: format!("Huya房间信息获取失败: {}", e),
-                        user_level: 0,
-                        fans_club_level: 0,
-                    },
-                );
-                return;
-            }
-        };
+    });
 
-        println!(
-            "[Huya Danmaku] ws_url={} reg_len={}",
-            ws_url,
-            reg_data.len()
-        );
-        info!(
-            "[Huya Danmaku] ws_url={} reg_len={}",
-            ws_url,
-            reg_data.len()
-        );
+    Ok(())
+}
 
-        // 2) 连接 WebSocket
-        println!("[Huya Danmaku] connecting to {}", ws_url);
-        info!("[Huya Danmaku] connecting to {}", ws_url);
-        let (ws_stream, _) = match connect_async(&ws_url).await {
-            Ok(v) => v,
-            Err(e) => {
-                let _ = app_handle_clone.emit(
-                    "danmaku-message",
-                    crate::platforms::common::DanmakuFrontendPayload {
-                        room_id: room_id_clone.clone(),
-                        user: "系统".to_string(),
-                        content: format!("Huya弹幕连接失败: {}", e),
-                        user_level: 0,
-                        fans_club_level: 0,
-                    },
+async fn run_huya_listener(
+    sink: &DanmakuSink,
+    mut rx_shutdown: tokio::sync::oneshot::Receiver<()>,
+) {
+    let room_id = sink.room_id();
+    info!("[Huya Danmaku] spawned worker for room_id={}", room_id);
+
+    // 每次重连都重新获取 ws 地址与注册数据
+    let mut backoff = Backoff::default();
+    loop {
+        sink.status(DanmakuStatus::Connecting);
+        let started = Instant::now();
+        let result = tokio::select! {
+            _ = &mut rx_shutdown => break,
+            res = run_huya_session(sink, room_id) => res,
+        };
+        if started.elapsed() >= STABLE_SESSION {
+            backoff.reset();
+        }
+        let err = result
+            .err()
+            .unwrap_or_else(|| anyhow::anyhow!("连接已关闭"));
+        match backoff.next_delay() {
+            Some(delay) => {
+                eprintln!(
+                    "[Huya Danmaku] room {} disconnected: {}. Reconnecting in {:?} (attempt {})",
+                    room_id,
+                    err,
+                    delay,
+                    backoff.attempt()
                 );
+                sink.status(DanmakuStatus::Reconnecting {
+                    attempt: backoff.attempt(),
+                });
+                if wait_or_shutdown(delay, &mut rx_shutdown).await {
+                    break;
+                }
+            }
+            None => {
+                eprintln!(
+                    "[Huya Danmaku] room {} giving up after {} attempts: {}",
+                    room_id,
+                    backoff.attempt(),
+                    err
+                );
+                sink.status(DanmakuStatus::Failed {
+                    reason: err.to_string(),
+                });
                 return;
             }
-        };
-
-        let (mut ws_write, mut ws_read) = ws_stream.split();
-        if let Err(e) = ws_write.send(WsMessage::Binary(reg_data)).await {
-            let _ = app_handle_clone.emit(
-                "danmaku-message",
-                crate::platforms::common::DanmakuFrontendPayload {
-                    room_id: room_id_clone.clone(),
-                    user: "系统".to_string(),
-                    content: format!("Huya注册数据发送失败: {}", e),
-                    user_level: 0,
-                    fans_club_level: 0,
-                },
-            );
-            return;
         }
+    }
+    sink.status(DanmakuStatus::Stopped);
+    println!("[Huya Danmaku] worker for room_id={} stopped", room_id);
+}
 
-        // 3) 心跳与接收
-        let hb_task = async {
-            let mut hb_seq = 0usize;
-            while let Ok(_) = ws_write.send(WsMessage::Binary(HEARTBEAT.into())).await {
-                hb_seq += 1;
-                println!("[Huya Danmaku] heartbeat sent #{}", hb_seq);
-                info!("[Huya Danmaku] heartbeat sent #{}", hb_seq);
-                sleep(Duration::from_secs(20)).await;
-            }
-            Err::<(), anyhow::Error>(anyhow::anyhow!("Huya心跳发送失败"))
-        };
+// 单次连接：获取注册数据、建立连接并收发，直到出错或断开
+async fn run_huya_session(sink: &DanmakuSink, room_id: &str) -> anyhow::Result<()> {
+    // 1) 获取 ws 地址与房间 id，据此构造注册包和心跳包
+    let info = get_ws_info_tars(room_id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Huya房间信息获取失败: {}", e))?;
+    let reg_packets = huya_tars::register_packets(info.ayyuid, info.top_sid, info.sub_sid)
+        .map_err(|e| anyhow::anyhow!("Huya注册数据编码失败: {:?}", e))?;
+    let heartbeat = huya_tars::heartbeat_packet(info.ayyuid, info.top_sid, info.sub_sid)
+        .map_err(|e| anyhow::anyhow!("Huya心跳编码失败: {:?}", e))?;
+
+    info!(
+        "[Huya Danmaku] ws_url={} ayyuid={} top_sid={} sub_sid={}",
+        info.ws_url, info.ayyuid, info.top_sid, info.sub_sid
+    );
 
-        let recv_task = async {
-            while let Some(m) = ws_read.next().await {
-                let m = match m {
-                    Ok(x) => x,
-                    Err(e) => return Err(anyhow::anyhow!(e)),
-                };
-                match m {
-                    WsMessage::Binary(bin) => {
-                        let (top_cmd, nested_cmd) = peek_cmds(&bin);
-                        println!(
-                            "[Huya Danmaku] WS msg: len={} top_cmd={:?} nested_cmd={:?}",
-                            bin.len(),
-                            top_cmd,
-                            nested_cmd
-                        );
-                        info!(
-                            "[Huya Danmaku] WS msg: len={} top_cmd={:?} nested_cmd={:?}",
-                            bin.len(),
-                            top_cmd,
-                            nested_cmd
-                        );
-                        match decode_msg_tars(&bin)? {
-                            Some((nick, text)) => {
-                                println!("[Huya Danmaku] decoded chat: {} -> {}", nick, text);
-                                info!("[Huya Danmaku] decoded chat: {} -> {}", nick, text);
-                                let _ = app_handle_clone.emit(
-                                    "danmaku-message",
-                                    crate::platforms::common::DanmakuFrontendPayload {
-                                        room_id: room_id_clone.clone(),
-                                        user: nick,
-                                        content: text,
-                                        user_level: 0,
-                                        fans_club_level: 0,
-                                    },
+    // 2) 连接 WebSocket
+    info!("[Huya Danmaku] connecting to {}", info.ws_url);
+    let (ws_stream, _) = connect_async(&info.ws_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("Huya弹幕连接失败: {}", e))?;
+
+    let (mut ws_write, mut ws_read) = ws_stream.split();
+    for packet in reg_packets {
+        ws_write
+            .send(WsMessage::Binary(packet))
+            .await
+            .map_err(|e| anyhow::anyhow!("Huya注册数据发送失败: {}", e))?;
+    }
+    sink.status(DanmakuStatus::Connected);
+
+    // 3) 心跳与接收
+    let hb_task = async {
+        let mut hb_seq = 0usize;
+        while let Ok(_) = ws_write.send(WsMessage::Binary(heartbeat.clone())).await {
+            hb_seq += 1;
+            debug!("[Huya Danmaku] heartbeat sent #{}", hb_seq);
+            sleep(Duration::from_secs(20)).await;
+        }
+        Err::<(), anyhow::Error>(anyhow::anyhow!("Huya心跳发送失败"))
+    };
+
+    let recv_task = async {
+        while let Some(m) = ws_read.next().await {
+            let m = match m {
+                Ok(x) => x,
+                Err(e) => return Err(anyhow::anyhow!(e)),
+            };
+            match m {
+                WsMessage::Binary(bin) => {
+                    let (top_cmd, nested_cmd) = peek_cmds(&bin);
+                    trace!(
+                        "[Huya Danmaku] WS msg: len={} top_cmd={:?} nested_cmd={:?}",
+                        bin.len(),
+                        top_cmd,
+                        nested_cmd
+                    );
+                    match decode_msg_tars(&bin, sink)? {
+                        Some(event) => {
+                            trace!(
+                                "[Huya Danmaku] decoded event: {} -> {}",
+                                event.user.nickname,
+                                event.content
+                            );
+                            sink.emit(event);
+                        }
+                        None => {
+                            if top_cmd == Some(huya_tars::CMD_MSG_PUSH_REQ) {
+                                trace!(
+                                    "[Huya Danmaku] non-chat or empty msg, nested={:?}",
+                                    nested_cmd
                                 );
                             }
-                            None => {
-                                if top_cmd == Some(7) {
-                                    println!(
-                                        "[Huya Danmaku] non-chat or empty msg, nested={:?}",
-                                        nested_cmd
-                                    );
-                                    info!(
-                                        "[Huya Danmaku] non-chat or empty msg, nested={:?}",
-                                        nested_cmd
-                                    );
-                                }
-                            }
                         }
                     }
-                    other => {
-                        println!("[Huya Danmaku] non-binary ws message: {:?}", other);
-                        info!("[Huya Danmaku] non-binary ws message: {:?}", other);
-                    }
                 }
-            }
-            anyhow::Ok(())
-        };
-
-        tokio::select! {
-            _ = rx_shutdown.recv() => {
-                // 主动关闭
-            }
-            it = hb_task => {
-                if let Err(e) = it { eprintln!("[Huya Danmaku] {}", e); }
-            }
-            it = recv_task => {
-                if let Err(e) = it { eprintln!("[Huya Danmaku] 接收失败: {}", e); }
+                other => {
+                    debug!("[Huya Danmaku] non-binary ws message: {:?}", other);
+                }
             }
         }
-    });
+        anyhow::Ok(())
+    };
 
-    Ok(())
+    tokio::select! {
+        it = hb_task => it,
+        it = recv_task => it.and_then(|_| Err(anyhow::anyhow!("服务器关闭了连接"))),
+    }
 }
 
 #[tauri::command]
 pub async fn stop_huya_danmaku_listener(
     room_id: String,
-    state: tauri::State<'_, crate::platforms::common::HuyaDanmakuState>,
+    registry: tauri::State<'_, DanmakuListenerRegistry>,
 ) -> Result<(), String> {
     println!(
         "[Huya Danmaku] stop_huya_danmaku_listener called for room_id={}",
         room_id
     );
 
-    // 取出当前监听的停止信号发送器
-    let tx = {
-        let mut lock = state.inner().0.lock().unwrap();
-        lock.take()
-    };
-
-    if let Some(tx) = tx {
-        if let Err(_) = tx.send(()).await {
-            println!("[Huya Danmaku] 停止信号发送失败，监听器可能已经退出");
-        } else {
-            println!("[Huya Danmaku] 停止信号已发送给 room_id={}", room_id);
-        }
+    if registry.stop(SupportedPlatformRust::Huya, &room_id) {
+        println!("[Huya Danmaku] 停止信号已发送给 room_id={}", room_id);
     } else {
-        println!("[Huya Danmaku] 没有找到活跃的监听器需要停止");
+        println!("[Huya Danmaku] 没有找到 room_id={} 的活跃监听器", room_id);
     }
 
     Ok(())
@@ -318,28 +305,67 @@ pub async fn stop_huya_danmaku_listener(
 
 // 采用 tars_stream 的实现（参考 all_in_one.rs），保留 Tauri 命令，对旧 jce 逻辑停用
 
+// SenderInfo
 struct HuyaUser {
-    _uid: i64,
+    uid: i64,
     _imid: i64,
     name: String,
     _gender: i32,
+    avatar: String,
+    noble_level: i32,
 }
 
+// BulletFormat
 struct HuyaDanmakuFmt {
     color: i32,
 }
 
+// DecorationInfo：弹幕前后缀装饰（粉丝牌、贵族标识等），vData 为对应结构体的序列化数据
+struct HuyaDecoration {
+    app_id: i32,
+    data: Vec<u8>,
+}
+
+// NobleBase：贵族信息
+#[derive(Default)]
+struct HuyaNoble {
+    uid: i64,
+    nick: String,
+    level: i32,
+    name: String,
+}
+
+// 粉丝牌装饰的 iAppId
+const DECORATION_APP_FANS: i32 = 10400;
+
+impl Default for HuyaUser {
+    fn default() -> Self {
+        HuyaUser {
+            uid: -1,
+            _imid: -1,
+            name: "".to_owned(),
+            _gender: 1,
+            avatar: "".to_owned(),
+            noble_level: 0,
+        }
+    }
+}
+
 impl StructFromTars for HuyaUser {
     fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
         let uid = decoder.read_int64(0, false, -1)?;
         let imid = decoder.read_int64(1, false, -1)?;
         let name = decoder.read_string(2, false, "".to_string())?;
         let gender = decoder.read_int32(3, false, -1)?;
+        let avatar = decoder.read_string(4, false, "".to_string())?;
+        let noble_level = decoder.read_int32(5, false, 0)?;
         Ok(HuyaUser {
-            _uid: uid,
+            uid,
             _imid: imid,
             name,
             _gender: gender,
+            avatar,
+            noble_level,
         })
     }
 }
@@ -351,17 +377,38 @@ impl StructFromTars for HuyaDanmakuFmt {
     }
 }
 
+impl StructFromTars for HuyaDecoration {
+    fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+        let app_id = decoder.read_int32(0, false, 0)?;
+        let data = decoder.read_bytes(2, false, Default::default())?;
+        Ok(HuyaDecoration {
+            app_id,
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl StructFromTars for HuyaNoble {
+    fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+        let uid = decoder.read_int64(0, false, -1)?;
+        let nick = decoder.read_string(1, false, "".to_string())?;
+        let level = decoder.read_int32(2, false, 0)?;
+        let name = decoder.read_string(3, false, "".to_string())?;
+        Ok(HuyaNoble {
+            uid,
+            nick,
+            level,
+            name,
+        })
+    }
+}
+
 fn peek_cmds(data: &[u8]) -> (Option<i32>, Option<i64>) {
-    let mut ios = TarsDecoder::from(data);
-    let top_cmd = ios.read_int32(0, false, -1).ok();
-    let nested_cmd = ios
-        .read_bytes(1, false, Default::default())
-        .ok()
-        .and_then(|b1| {
-            let mut inner = TarsDecoder::from(b1.as_ref());
-            inner.read_int32(1, false, -1).ok().map(|v| v as i64)
-        });
-    (top_cmd, nested_cmd)
+    let Ok(cmd) = WebSocketCommand::decode(data) else {
+        return (None, None);
+    };
+    let nested_cmd = WSPushMessage::decode(&cmd.data).ok().map(|push| push.uri);
+    (Some(cmd.cmd_type), nested_cmd)
 }
 
 fn find_uid_in_json(v: &serde_json::Value) -> Option<String> {
@@ -402,7 +449,14 @@ fn gen_ua() -> String {
     "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36".to_string()
 }
 
-async fn get_ws_info_tars(room_id_or_url: &str) -> Result<(String, Vec<u8>), String> {
+struct HuyaWsInfo {
+    ws_url: String,
+    ayyuid: i64,
+    top_sid: i64,
+    sub_sid: i64,
+}
+
+async fn get_ws_info_tars(room_id_or_url: &str) -> Result<HuyaWsInfo, String> {
     let url = if room_id_or_url.starts_with("http") {
         reqwest::Url::parse(room_id_or_url).map_err(|e| e.to_string())?
     } else {
@@ -413,7 +467,6 @@ async fn get_ws_info_tars(room_id_or_url: &str) -> Result<(String, Vec<u8>), Str
         .path_segments()
         .and_then(|s| s.last())
         .ok_or_else(|| "房间ID解析失败".to_string())?;
-    println!("[Huya Danmaku] get_ws_info_tars rid={}", rid);
     info!("[Huya Danmaku] get_ws_info_tars rid={}", rid);
 
     let client = reqwest::Client::builder()
@@ -430,7 +483,6 @@ async fn get_ws_info_tars(room_id_or_url: &str) -> Result<(String, Vec<u8>), Str
         .text()
         .await
         .map_err(|e| e.to_string())?;
-    println!("[Huya Danmaku] fetched room page len={}", resp_text.len());
     info!("[Huya Danmaku] fetched room page len={}", resp_text.len());
 
     // 先尝试 TT_PROFILE_INFO 提取 lp
@@ -493,96 +545,282 @@ async fn get_ws_info_tars(room_id_or_url: &str) -> Result<(String, Vec<u8>), Str
     if ayyuid.is_empty() {
         ayyuid = rid.to_string();
     }
-    println!("[Huya Danmaku] final ayyuid={}", ayyuid);
     info!("[Huya Danmaku] final ayyuid={}", ayyuid);
 
-    let mut topics = Vec::new();
-    topics.push(format!("live:{}", ayyuid));
-    topics.push(format!("chat:{}", ayyuid));
-    println!("[Huya Danmaku] topics={:?}", topics);
-    info!("[Huya Danmaku] topics={:?}", topics);
+    let ayyuid: i64 = ayyuid
+        .parse()
+        .map_err(|_| format!("主播ID无效: {}", ayyuid))?;
 
-    let mut oos = TarsEncoder::new();
-    oos.write_list(0, &topics).map_err(|e| e.to_string())?;
-    oos.write_string(1, &"".to_owned())
-        .map_err(|e| e.to_string())?;
+    // 频道号用于注册与心跳：优先取页面中的流信息，取不到再查 profileRoom
+    let channel_id = |key: &str| -> Option<i64> {
+        let re = regex::Regex::new(&format!(r#"\\?"{}\\?"\s*:\s*\\?"?(\d+)"#, key)).ok()?;
+        re.captures(&resp_text)
+            .and_then(|cap| cap[1].parse::<i64>().ok())
+            .filter(|id| *id > 0)
+    };
+    let top_sid = match channel_id("lChannelId") {
+        Some(id) => id,
+        None => fetch_huya_ids(rid).await.map(|(_, sid)| sid).unwrap_or(0),
+    };
+    let sub_sid = channel_id("lSubChannelId").unwrap_or(top_sid);
+
+    Ok(HuyaWsInfo {
+        ws_url: WS_URL.to_owned(),
+        ayyuid,
+        top_sid,
+        sub_sid,
+    })
+}
 
-    let mut wscmd = TarsEncoder::new();
-    wscmd.write_int32(0, 16).map_err(|e| e.to_string())?;
-    wscmd
-        .write_bytes(1, &oos.to_bytes())
-        .map_err(|e| e.to_string())?;
-    let b = wscmd.to_bytes();
-    println!("[Huya Danmaku] reg payload built, len={}", b.len());
-    info!("[Huya Danmaku] reg payload built, len={}", b.len());
+fn huya_uid(uid: i64) -> Option<String> {
+    Some(uid.to_string()).filter(|_| uid > 0)
+}
+
+fn nick_or_anonymous(name: String) -> String {
+    if name.is_empty() {
+        "匿名".to_string()
+    } else {
+        name
+    }
+}
+
+fn noble_name(level: i32) -> &'static str {
+    match level {
+        1 => "剑士",
+        2 => "骑士",
+        3 => "领主",
+        4 => "公爵",
+        5 => "君王",
+        6 => "帝皇",
+        7 => "超神",
+        _ => "贵族",
+    }
+}
 
-    Ok((WS_URL.to_owned(), b.as_ref().to_vec()))
+// 礼物 id -> 名称。全站横幅（6502）只带 iItemType，名称取自各房间礼物消息（6501）里见过的 sPropsName
+fn gift_names() -> &'static Mutex<HashMap<i32, String>> {
+    static NAMES: OnceLock<Mutex<HashMap<i32, String>>> = OnceLock::new();
+    NAMES.get_or_init(Default::default)
 }
 
-fn decode_msg_tars(data: &[u8]) -> anyhow::Result<Option<(String, String)>> {
-    let mut ret: Option<(String, String)> = None;
-    let mut ios = TarsDecoder::from(data);
-    let top = ios.read_int32(0, false, -1)?;
-    if top != 7 {
-        println!("[Huya Danmaku] ignore msg: top_cmd={}", top);
-        info!("[Huya Danmaku] ignore msg: top_cmd={}", top);
-        return Ok(ret);
+fn remember_gift_name(gift_id: i32, name: &str) {
+    if name.is_empty() {
+        return;
     }
-    let b1 = ios.read_bytes(1, false, Default::default())?;
-    let mut inner = TarsDecoder::from(b1.as_ref());
-    let nested = inner.read_int32(1, false, -1).unwrap_or(-1);
-    let b2 = inner.read_bytes(2, false, Default::default())?;
-    println!("[Huya Danmaku] nested={} payload_len={}", nested, b2.len());
-    info!("[Huya Danmaku] nested={} payload_len={}", nested, b2.len());
-    let mut payload = TarsDecoder::from(b2.as_ref());
-
-    if nested == 1400 {
-        let user = payload
-            .read_struct(
-                0,
-                false,
-                HuyaUser {
-                    _uid: -1,
-                    _imid: -1,
-                    name: "".to_owned(),
-                    _gender: 1,
-                },
-            )
-            .unwrap_or(HuyaUser {
-                _uid: -1,
-                _imid: -1,
-                name: "".to_owned(),
-                _gender: 1,
-            });
-        let text = payload
-            .read_string(3, false, "".to_owned())
-            .unwrap_or_default();
-        let fmt = payload
-            .read_struct(6, false, HuyaDanmakuFmt { color: 16777215 })
-            .unwrap_or(HuyaDanmakuFmt { color: 16777215 });
-        if !text.is_empty() {
-            let nick = if !user.name.is_empty() {
-                user.name
-            } else {
-                "匿名".to_string()
-            };
-            let _color_hex = format!("{:06x}", if fmt.color <= 0 { 16777215 } else { fmt.color });
-            println!(
-                "[Huya Danmaku] decoded nested=1400 nick={} text={}",
-                nick, text
-            );
-            info!(
-                "[Huya Danmaku] decoded nested=1400 nick={} text={}",
-                nick, text
-            );
-            ret = Some((nick, text));
-        } else {
-            println!("[Huya Danmaku] empty text in nested=1400");
-            info!("[Huya Danmaku] empty text in nested=1400");
+    if let Ok(mut names) = gift_names().lock() {
+        if names.get(&gift_id).map(String::as_str) != Some(name) {
+            names.insert(gift_id, name.to_string());
         }
-    } else {
-        println!("[Huya Danmaku] non-chat nested={}, skip", nested);
-        info!("[Huya Danmaku] non-chat nested={}, skip", nested);
     }
-    Ok(ret)
+}
+
+fn known_gift_name(gift_id: i32) -> String {
+    gift_names()
+        .lock()
+        .ok()
+        .and_then(|names| names.get(&gift_id).cloned())
+        .unwrap_or_else(|| format!("礼物{}", gift_id))
+}
+
+fn noble_level(level: i32) -> u8 {
+    level.clamp(0, u8::MAX as i32) as u8
+}
+
+// 从装饰列表中取粉丝牌 BadgeInfo：sBadgeName(3) / iBadgeLevel(4)
+fn fans_badge(decorations: &[HuyaDecoration]) -> Option<(String, i32)> {
+    let deco = decorations
+        .iter()
+        .find(|d| d.app_id == DECORATION_APP_FANS)?;
+    let mut badge = TarsDecoder::from(deco.data.as_slice());
+    let name = badge.read_string(3, false, "".to_owned()).ok()?;
+    let level = badge.read_int32(4, false, 0).unwrap_or(0);
+    Some((name, level)).filter(|(name, _)| !name.is_empty())
+}
+
+fn decode_msg_tars(data: &[u8], sink: &DanmakuSink) -> anyhow::Result<Option<DanmakuEvent>> {
+    let cmd = WebSocketCommand::decode(data)?;
+    if cmd.cmd_type != huya_tars::CMD_MSG_PUSH_REQ {
+        trace!("[Huya Danmaku] ignore msg: top_cmd={}", cmd.cmd_type);
+        return Ok(None);
+    }
+    let push = WSPushMessage::decode(&cmd.data)?;
+    let nested = push.uri;
+    trace!(
+        "[Huya Danmaku] nested={} payload_len={}",
+        nested,
+        push.msg.len()
+    );
+    let mut payload = TarsDecoder::from(push.msg.as_slice());
+
+    let event = match nested {
+        1400 => decode_chat(&mut payload, sink),
+        6501 => decode_gift(&mut payload, sink),
+        6502 => decode_gift_broadcast(&mut payload, sink),
+        6210 => decode_noble_enter(&mut payload, sink),
+        6110 => decode_vip_enter(&mut payload, sink),
+        _ => {
+            trace!("[Huya Danmaku] unsupported nested={}, skip", nested);
+            None
+        }
+    };
+    Ok(event)
+}
+
+// 1400 MessageNotice：聊天弹幕
+fn decode_chat(payload: &mut TarsDecoder, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+    let user = payload
+        .read_struct(0, false, HuyaUser::default())
+        .unwrap_or_default();
+    let text = payload
+        .read_string(3, false, "".to_owned())
+        .unwrap_or_default();
+    let fmt = payload
+        .read_struct(6, false, HuyaDanmakuFmt { color: 16777215 })
+        .unwrap_or(HuyaDanmakuFmt { color: 16777215 });
+    let decorations: Vec<HuyaDecoration> = payload.read_list(8, false, vec![]).unwrap_or_default();
+    if text.is_empty() {
+        trace!("[Huya Danmaku] empty text in nested=1400");
+        return None;
+    }
+    // 默认白色不下发颜色，交给前端默认样式
+    let color = if fmt.color <= 0 || fmt.color == 16777215 {
+        None
+    } else {
+        Some(rgb_to_hex(fmt.color as u32))
+    };
+    let badge = fans_badge(&decorations);
+    let nick = nick_or_anonymous(user.name);
+    trace!(
+        "[Huya Danmaku] decoded nested=1400 nick={} text={}",
+        nick,
+        text
+    );
+    let danmaku_user = DanmakuUser {
+        user_id: huya_uid(user.uid),
+        avatar: Some(user.avatar).filter(|a| !a.is_empty()),
+        // 虎牙弹幕不下发数值型用户等级，网页端以贵族等级作为等级标识
+        user_level: user.noble_level as i64,
+        fans_club_level: badge.as_ref().map(|(_, level)| *level).unwrap_or(0),
+        medal_name: badge.map(|(name, _)| name),
+        noble_level: noble_level(user.noble_level),
+        ..DanmakuUser::named(nick)
+    };
+    Some(
+        sink.event(DanmakuEventKind::Chat, danmaku_user, text)
+            .with_color(color),
+    )
+}
+
+// 6501 SendItemSubBroadcastPacket：本房间礼物
+fn decode_gift(payload: &mut TarsDecoder, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+    let gift_id = payload.read_int32(0, false, 0).unwrap_or(0);
+    let count = payload.read_int32(2, false, 1).unwrap_or(1).max(1) as u32;
+    let sender_uid = payload.read_int64(4, false, -1).unwrap_or(-1);
+    let sender_nick = payload
+        .read_string(6, false, "".to_owned())
+        .unwrap_or_default();
+    let sender_icon = payload
+        .read_string(14, false, "".to_owned())
+        .unwrap_or_default();
+    let gift_name = payload
+        .read_string(20, false, "".to_owned())
+        .unwrap_or_default();
+    let sender_noble = payload.read_int32(28, false, 0).unwrap_or(0);
+
+    let gift_name = if gift_name.is_empty() {
+        known_gift_name(gift_id)
+    } else {
+        remember_gift_name(gift_id, &gift_name);
+        gift_name
+    };
+    let content = format!("送出 {} x{}", gift_name, count);
+    let user = DanmakuUser {
+        user_id: huya_uid(sender_uid),
+        avatar: Some(sender_icon).filter(|a| !a.is_empty()),
+        user_level: sender_noble as i64,
+        noble_level: noble_level(sender_noble),
+        ..DanmakuUser::named(nick_or_anonymous(sender_nick))
+    };
+    Some(sink.event(
+        DanmakuEventKind::Gift {
+            gift_id: Some(gift_id.to_string()),
+            gift_name,
+            gift_count: count,
+            gift_price: None,
+            combo_count: None,
+        },
+        user,
+        content,
+    ))
+}
+
+// 6502 SendItemNoticeWordBroadcastPacket：全站礼物横幅
+fn decode_gift_broadcast(payload: &mut TarsDecoder, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+    let gift_id = payload.read_int32(0, false, 0).unwrap_or(0);
+    let count = payload.read_int32(1, false, 1).unwrap_or(1).max(1);
+    let sender_nick = payload
+        .read_string(4, false, "".to_owned())
+        .unwrap_or_default();
+    let presenter_nick = payload
+        .read_string(6, false, "".to_owned())
+        .unwrap_or_default();
+    let sender_nick = nick_or_anonymous(sender_nick);
+    let content = format!(
+        "{} 送给 {} {} x{}",
+        sender_nick,
+        presenter_nick,
+        known_gift_name(gift_id),
+        count
+    );
+    Some(sink.event(
+        // 横幅只带主播 uid，没有房间号
+        DanmakuEventKind::Broadcast {
+            source_room_id: None,
+        },
+        DanmakuUser::named(sender_nick),
+        content,
+    ))
+}
+
+// 6210 NobleEnterNotice：贵族进场，tNobleInfo(0) = NobleBase { lUid, sNickName, iLevel, sName }
+fn decode_noble_enter(payload: &mut TarsDecoder, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+    let HuyaNoble {
+        uid,
+        nick,
+        level,
+        name,
+    } = payload.read_struct(0, false, HuyaNoble::default()).ok()?;
+    let name = if name.is_empty() {
+        noble_name(level).to_string()
+    } else {
+        name
+    };
+    let nick = nick_or_anonymous(nick);
+    let content = format!("{} {} 进入了直播间", name, nick);
+    let user = DanmakuUser {
+        user_id: huya_uid(uid),
+        user_level: level as i64,
+        noble_level: noble_level(level),
+        ..DanmakuUser::named(nick)
+    };
+    Some(sink.event(DanmakuEventKind::Enter, user, content))
+}
+
+// 6110 VipEnterBanner：VIP 进场横幅
+fn decode_vip_enter(payload: &mut TarsDecoder, sink: &DanmakuSink) -> Option<DanmakuEvent> {
+    let uid = payload.read_int64(0, false, -1).unwrap_or(-1);
+    let nick = payload
+        .read_string(1, false, "".to_owned())
+        .unwrap_or_default();
+    let logo = payload
+        .read_string(6, false, "".to_owned())
+        .unwrap_or_default();
+    let nick = nick_or_anonymous(nick);
+    let content = format!("{} 进入了直播间", nick);
+    let user = DanmakuUser {
+        user_id: huya_uid(uid),
+        avatar: Some(logo).filter(|a| !a.is_empty()),
+        ..DanmakuUser::named(nick)
+    };
+    Some(sink.event(DanmakuEventKind::Enter, user, content))
 }
@@ -2,9 +2,11 @@ pub mod danmaku;
 pub mod live_list;
 pub mod search;
 pub mod stream_url;
+pub mod tars;
 
 #[allow(unused_imports)]
 pub use danmaku::fetch_huya_join_params;
 pub use danmaku::start_huya_danmaku_listener;
 pub use danmaku::stop_huya_danmaku_listener;
 pub use live_list::fetch_huya_live_list;
+pub mod platform;
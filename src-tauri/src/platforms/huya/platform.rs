@@ -0,0 +1,204 @@
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::platforms::common::http_client::HttpClient;
+use crate::platforms::common::live_platform::LivePlatform;
+use crate::platforms::common::types::{LiveStreamInfo, StreamVariant};
+use crate::platforms::common::types_rust::{
+    CommonLiveListRust, CommonPlatformCategoryRust, CommonRoomInfoRust, CommonStreamerRust,
+    SupportedPlatformRust,
+};
+use crate::platforms::common::FollowHttpClient;
+use crate::platforms::huya::live_list::fetch_huya_live_list;
+use crate::platforms::huya::search::search_huya_anchors;
+use crate::platforms::huya::stream_url::{fetch_room_detail, get_huya_unified_cmd};
+
+const LIVE_LIST_PAGE_SIZE: u32 = 120;
+// 1 网游 / 2 单机 / 3 手游 / 8 娱乐
+const CATEGORY_BUSS_TYPES: [(u32, &str); 4] = [
+    (1, "网游竞技"),
+    (2, "单机热游"),
+    (3, "手游休闲"),
+    (8, "娱乐天地"),
+];
+
+pub struct HuyaPlatform {
+    app_handle: AppHandle,
+}
+
+impl HuyaPlatform {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl LivePlatform for HuyaPlatform {
+    fn platform(&self) -> SupportedPlatformRust {
+        SupportedPlatformRust::Huya
+    }
+
+    async fn room_info(&self, room_id: &str) -> Result<CommonRoomInfoRust, String> {
+        let follow_http = self.app_handle.state::<FollowHttpClient>();
+        let detail = fetch_room_detail(&follow_http.0.inner, room_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(CommonRoomInfoRust {
+            platform: self.platform(),
+            room_id: room_id.to_string(),
+            title: detail.title,
+            anchor_name: detail.nick,
+            avatar: detail.avatar180,
+            is_live: detail.status,
+        })
+    }
+
+    async fn stream_url(
+        &self,
+        room_id: &str,
+        quality: &str,
+        line: Option<&str>,
+    ) -> Result<LiveStreamInfo, String> {
+        let follow_http = self.app_handle.state::<FollowHttpClient>();
+        let resp = get_huya_unified_cmd(
+            room_id.to_string(),
+            Some(quality.to_string()),
+            line.map(|l| l.to_string()),
+            follow_http,
+        )
+        .await?;
+
+        let available_streams = resp
+            .flv_tx_urls
+            .iter()
+            .map(|entry| StreamVariant {
+                url: entry.url.clone(),
+                format: Some("flv".to_string()),
+                desc: Some(entry.quality.clone()),
+                qn: Some(entry.bitRate),
+                protocol: Some("http".to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        Ok(LiveStreamInfo {
+            title: resp.title,
+            anchor_name: resp.nick,
+            avatar: resp.avatar,
+            stream_url: resp.selected_url.clone(),
+            status: Some(if resp.is_live { 1 } else { 0 }),
+            error_message: if resp.is_live && resp.selected_url.is_none() {
+                Some("未找到可用的虎牙播放地址".to_string())
+            } else {
+                None
+            },
+            upstream_url: resp.selected_url,
+            available_streams: Some(available_streams),
+            normalized_room_id: resp.profileRoom.or_else(|| Some(room_id.to_string())),
+            web_rid: None,
+            proxy_session_id: None,
+        })
+    }
+
+    async fn search(&self, keyword: &str, page: u32) -> Result<Vec<CommonStreamerRust>, String> {
+        let items = search_huya_anchors(keyword.to_string(), Some(page as usize)).await?;
+        Ok(items
+            .into_iter()
+            .map(|item| CommonStreamerRust {
+                room_id: item.room_id,
+                title: item.title,
+                nickname: item.user_name,
+                avatar: item.avatar,
+                room_cover: String::new(),
+                viewer_count_str: String::new(),
+                platform: SupportedPlatformRust::Huya,
+                is_live: Some(item.live_status),
+            })
+            .collect())
+    }
+
+    async fn categories(&self) -> Result<Vec<CommonPlatformCategoryRust>, String> {
+        let client = HttpClient::new_direct_connection()?;
+        let mut categories = Vec::new();
+        for (buss_type, group_name) in CATEGORY_BUSS_TYPES {
+            let group_id = format!("buss_{}", buss_type);
+            categories.push(CommonPlatformCategoryRust {
+                id: group_id.clone(),
+                name: group_name.to_string(),
+                platform: self.platform(),
+                icon_url: None,
+                parent_id: None,
+            });
+
+            let url = format!(
+                "https://live.cdn.huya.com/liveconfig/game/bussLive?bussType={}",
+                buss_type
+            );
+            let json: Value = client
+                .get_json(&url)
+                .await
+                .map_err(|e| format!("Failed to fetch Huya categories: {}", e))?;
+            let games = json
+                .get("data")
+                .and_then(|d| d.as_array())
+                .cloned()
+                .unwrap_or_default();
+            for game in games {
+                let gid = match game.get("gid") {
+                    Some(Value::Number(n)) => n.to_string(),
+                    Some(Value::String(s)) => s.clone(),
+                    _ => continue,
+                };
+                let name = game
+                    .get("gameFullName")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                categories.push(CommonPlatformCategoryRust {
+                    icon_url: Some(format!(
+                        "https://huyaimg.msstatic.com/cdnimage/game/{}-MS.jpg",
+                        gid
+                    )),
+                    id: gid,
+                    name,
+                    platform: self.platform(),
+                    parent_id: Some(group_id.clone()),
+                });
+            }
+        }
+        Ok(categories)
+    }
+
+    async fn live_list(
+        &self,
+        category_id: &str,
+        _parent_id: Option<&str>,
+        page: u32,
+    ) -> Result<CommonLiveListRust, String> {
+        let response =
+            fetch_huya_live_list(category_id.to_string(), page, LIVE_LIST_PAGE_SIZE).await;
+        if response.error != 0 {
+            return Err(response
+                .msg
+                .unwrap_or_else(|| format!("Huya API error {}", response.error)));
+        }
+        let list = response.data.unwrap_or_default();
+        let has_more = list.len() as u32 >= LIVE_LIST_PAGE_SIZE;
+        let rooms = list
+            .into_iter()
+            .map(|s| CommonStreamerRust {
+                room_id: s.room_id,
+                title: s.title,
+                nickname: s.nickname,
+                avatar: s.avatar,
+                room_cover: s.room_cover,
+                viewer_count_str: s.viewer_count_str,
+                platform: SupportedPlatformRust::Huya,
+                is_live: Some(true),
+            })
+            .collect();
+        Ok(CommonLiveListRust {
+            rooms,
+            has_more,
+            page,
+        })
+    }
+}
@@ -179,11 +179,11 @@ async fn check_live_status(
 }
 
 #[derive(Clone, Debug)]
-struct RoomDetail {
-    status: bool,
-    title: Option<String>,
-    nick: Option<String>,
-    avatar180: Option<String>,
+pub(crate) struct RoomDetail {
+    pub(crate) status: bool,
+    pub(crate) title: Option<String>,
+    pub(crate) nick: Option<String>,
+    pub(crate) avatar180: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -198,7 +198,7 @@ struct HuyaWebStreamData {
     candidates: Vec<WebStreamCandidate>,
 }
 
-async fn fetch_room_detail(
+pub(crate) async fn fetch_room_detail(
     client: &reqwest::Client,
     room_id: &str,
 ) -> Result<RoomDetail, Box<dyn Error + Send + Sync>> {
@@ -623,5 +623,3 @@ pub async fn get_huya_unified_cmd(
         selected_url: Some(selected_url),
     })
 }
-#[allow(dead_code)]
-const HEARTBEAT_BASE64: &str = "ABQdAAwsNgBM"; // same as Python
@@ -0,0 +1,464 @@
+// 虎牙弹幕 WebSocket 协议使用的 TARS 结构
+//
+// 所有上下行数据都包在 WebSocketCommand 里：iCmdType 区分命令，vData 为对应结构的 TARS 编码。
+// 心跳走 WUP（iCmdType=3）：vData 为 4 字节大端长度 + RequestPacket，
+// RequestPacket.sBuffer 是 map<string, bytes>，其中 "tReq" 为 UserHeartBeatReq。
+use std::collections::BTreeMap;
+
+use tars_stream::prelude::*;
+
+/// EWebSocketCommandType
+pub const CMD_REGISTER_REQ: i32 = 1;
+pub const CMD_WUP_REQ: i32 = 3;
+pub const CMD_MSG_PUSH_REQ: i32 = 7;
+pub const CMD_REGISTER_GROUP_REQ: i32 = 16;
+
+// 心跳使用的 UA，与网页端 / 手机网页端一致即可
+const HUYA_UA: &str = "webh5&1.0.0&websocket";
+const WUP_VERSION: i16 = 3;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebSocketCommand {
+    pub cmd_type: i32,
+    pub data: Vec<u8>,
+    pub request_id: i64,
+    pub trace_id: String,
+    pub encrypt_type: i32,
+}
+
+impl WebSocketCommand {
+    pub fn new(cmd_type: i32, data: Vec<u8>) -> Self {
+        Self {
+            cmd_type,
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// 编码整个 WebSocket 帧
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeErr> {
+        let mut encoder = TarsEncoder::new();
+        self._encode_to(&mut encoder)?;
+        Ok(encoder.to_bytes().to_vec())
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeErr> {
+        Self::_decode_from(&mut TarsDecoder::from(data))
+    }
+}
+
+impl StructToTars for WebSocketCommand {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_int32(0, self.cmd_type)?;
+        encoder.write_bytes(1, &self.data.clone().into())?;
+        encoder.write_int64(2, self.request_id)?;
+        encoder.write_string(3, &self.trace_id)?;
+        encoder.write_int32(4, self.encrypt_type)?;
+        Ok(())
+    }
+}
+
+impl StructFromTars for WebSocketCommand {
+    fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+        Ok(Self {
+            cmd_type: decoder.read_int32(0, false, 0)?,
+            data: decoder.read_bytes(1, false, Default::default())?.to_vec(),
+            request_id: decoder.read_int64(2, false, 0)?,
+            trace_id: decoder.read_string(3, false, String::new())?,
+            encrypt_type: decoder.read_int32(4, false, 0)?,
+        })
+    }
+}
+
+/// iCmdType=7 的下行推送
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WSPushMessage {
+    pub push_type: i32,
+    /// 业务命令号，例如 1400 聊天、6501 礼物
+    pub uri: i64,
+    pub msg: Vec<u8>,
+    pub protocol_type: i32,
+}
+
+impl StructFromTars for WSPushMessage {
+    fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+        Ok(Self {
+            push_type: decoder.read_int32(0, false, 0)?,
+            uri: decoder.read_int64(1, false, -1)?,
+            msg: decoder.read_bytes(2, false, Default::default())?.to_vec(),
+            protocol_type: decoder.read_int32(3, false, 0)?,
+        })
+    }
+}
+
+impl StructToTars for WSPushMessage {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_int32(0, self.push_type)?;
+        encoder.write_int64(1, self.uri)?;
+        encoder.write_bytes(2, &self.msg.clone().into())?;
+        encoder.write_int32(3, self.protocol_type)?;
+        Ok(())
+    }
+}
+
+impl WSPushMessage {
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeErr> {
+        Self::_decode_from(&mut TarsDecoder::from(data))
+    }
+}
+
+/// iCmdType=1 的匿名注册，按频道加入推送组
+#[derive(Debug, Clone, Default)]
+pub struct WSUserInfo {
+    pub uid: i64,
+    pub anonymous: bool,
+    pub guid: String,
+    pub token: String,
+    pub tid: i64,
+    pub sid: i64,
+    pub group_id: i64,
+    pub group_type: i64,
+    pub app_id: String,
+    pub ua: String,
+}
+
+impl WSUserInfo {
+    /// 匿名用户加入主播 `ayyuid` 所在频道
+    pub fn anonymous(ayyuid: i64, top_sid: i64, sub_sid: i64) -> Self {
+        Self {
+            anonymous: true,
+            tid: top_sid,
+            sid: sub_sid,
+            group_id: ayyuid,
+            group_type: 3,
+            ua: HUYA_UA.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl StructToTars for WSUserInfo {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_int64(0, self.uid)?;
+        encoder.write_boolean(1, self.anonymous)?;
+        encoder.write_string(2, &self.guid)?;
+        encoder.write_string(3, &self.token)?;
+        encoder.write_int64(4, self.tid)?;
+        encoder.write_int64(5, self.sid)?;
+        encoder.write_int64(6, self.group_id)?;
+        encoder.write_int64(7, self.group_type)?;
+        encoder.write_string(8, &self.app_id)?;
+        encoder.write_string(9, &self.ua)?;
+        Ok(())
+    }
+}
+
+/// iCmdType=16，按主题订阅推送（"live:{ayyuid}" / "chat:{ayyuid}"）
+#[derive(Debug, Clone, Default)]
+pub struct WSRegisterGroupReq {
+    pub group_ids: Vec<String>,
+    pub token: String,
+}
+
+impl WSRegisterGroupReq {
+    pub fn for_presenter(ayyuid: i64) -> Self {
+        Self {
+            group_ids: vec![format!("live:{}", ayyuid), format!("chat:{}", ayyuid)],
+            token: String::new(),
+        }
+    }
+}
+
+impl StructToTars for WSRegisterGroupReq {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_list(0, &self.group_ids)?;
+        encoder.write_string(1, &self.token)?;
+        Ok(())
+    }
+}
+
+/// HUYA.UserId
+#[derive(Debug, Clone, Default)]
+pub struct UserId {
+    pub uid: i64,
+    pub guid: String,
+    pub token: String,
+    pub huya_ua: String,
+    pub cookie: String,
+}
+
+impl StructToTars for UserId {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_int64(0, self.uid)?;
+        encoder.write_string(1, &self.guid)?;
+        encoder.write_string(2, &self.token)?;
+        encoder.write_string(3, &self.huya_ua)?;
+        encoder.write_string(4, &self.cookie)?;
+        Ok(())
+    }
+}
+
+/// onlineui.OnUserHeartBeat 的请求体
+#[derive(Debug, Clone, Default)]
+pub struct UserHeartBeatReq {
+    pub user: UserId,
+    pub tid: i64,
+    pub sid: i64,
+    pub short_tid: i64,
+    /// 主播 uid（ayyuid）
+    pub pid: i64,
+    pub watch_video: bool,
+    pub line_type: i32,
+    pub fps: i32,
+    pub attendee: i32,
+    pub bandwidth: i32,
+    pub last_heart_elapse_time: i32,
+}
+
+impl UserHeartBeatReq {
+    pub fn new(ayyuid: i64, top_sid: i64, sub_sid: i64) -> Self {
+        Self {
+            user: UserId {
+                huya_ua: HUYA_UA.to_string(),
+                ..Default::default()
+            },
+            tid: top_sid,
+            sid: sub_sid,
+            pid: ayyuid,
+            watch_video: true,
+            line_type: 1,
+            ..Default::default()
+        }
+    }
+}
+
+impl StructToTars for UserHeartBeatReq {
+    fn _encode_to(&self, encoder: &mut TarsEncoder) -> Result<(), EncodeErr> {
+        encoder.write_struct(0, &self.user)?;
+        encoder.write_int64(1, self.tid)?;
+        encoder.write_int64(2, self.sid)?;
+        encoder.write_int64(3, self.short_tid)?;
+        encoder.write_int64(4, self.pid)?;
+        encoder.write_boolean(5, self.watch_video)?;
+        encoder.write_int32(6, self.line_type)?;
+        encoder.write_int32(7, self.fps)?;
+        encoder.write_int32(8, self.attendee)?;
+        encoder.write_int32(9, self.bandwidth)?;
+        encoder.write_int32(10, self.last_heart_elapse_time)?;
+        Ok(())
+    }
+}
+
+fn to_bytes<T: StructToTars>(value: &T) -> Result<Vec<u8>, EncodeErr> {
+    let mut encoder = TarsEncoder::new();
+    value._encode_to(&mut encoder)?;
+    Ok(encoder.to_bytes().to_vec())
+}
+
+// WUP v3 请求：4 字节大端总长度 + RequestPacket
+fn wup_request<T: StructToTars>(
+    servant: &str,
+    func: &str,
+    name: &str,
+    req: &T,
+) -> Result<Vec<u8>, EncodeErr> {
+    let mut body = TarsEncoder::new();
+    body.write_struct(0, req)?;
+    let mut buffer = BTreeMap::new();
+    buffer.insert(name.to_string(), body.to_bytes());
+    let mut params = TarsEncoder::new();
+    params.write_map(0, &buffer)?;
+
+    let mut packet = TarsEncoder::new();
+    packet.write_int16(1, WUP_VERSION)?;
+    packet.write_int8(2, 0)?;
+    packet.write_int32(3, 0)?;
+    packet.write_int32(4, 0)?;
+    packet.write_string(5, &servant.to_string())?;
+    packet.write_string(6, &func.to_string())?;
+    packet.write_bytes(7, &params.to_bytes())?;
+    packet.write_int32(8, 0)?;
+    packet.write_map(9, &BTreeMap::<String, String>::new())?;
+    packet.write_map(10, &BTreeMap::<String, String>::new())?;
+    let packet = packet.to_bytes();
+
+    let mut out = Vec::with_capacity(packet.len() + 4);
+    out.extend_from_slice(&((packet.len() + 4) as u32).to_be_bytes());
+    out.extend_from_slice(&packet);
+    Ok(out)
+}
+
+/// 连接后依次发送的注册包：频道注册 + 主题订阅
+pub fn register_packets(
+    ayyuid: i64,
+    top_sid: i64,
+    sub_sid: i64,
+) -> Result<Vec<Vec<u8>>, EncodeErr> {
+    let user = WSUserInfo::anonymous(ayyuid, top_sid, sub_sid);
+    let group = WSRegisterGroupReq::for_presenter(ayyuid);
+    Ok(vec![
+        WebSocketCommand::new(CMD_REGISTER_REQ, to_bytes(&user)?).encode()?,
+        WebSocketCommand::new(CMD_REGISTER_GROUP_REQ, to_bytes(&group)?).encode()?,
+    ])
+}
+
+/// 根据房间的主播 uid 与频道号构造心跳包
+pub fn heartbeat_packet(ayyuid: i64, top_sid: i64, sub_sid: i64) -> Result<Vec<u8>, EncodeErr> {
+    let req = UserHeartBeatReq::new(ayyuid, top_sid, sub_sid);
+    let data = wup_request("onlineui", "OnUserHeartBeat", "tReq", &req)?;
+    WebSocketCommand::new(CMD_WUP_REQ, data).encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 改为按房间构造前使用的固定心跳包（频道 61796367，主播 1834091104）
+    const LEGACY_HEARTBEAT: &[u8] = b"\x00\x03\x1d\x00\x00\x69\x00\x00\x00\x69\x10\x03\x2c\x3c\x4c\x56\x08\x6f\x6e\x6c\x69\x6e\x65\x75\x69\x66\x0f\x4f\x6e\x55\x73\x65\x72\x48\x65\x61\x72\x74\x42\x65\x61\x74\x7d\x00\x00\x3c\x08\x00\x01\x06\x04\x74\x52\x65\x71\x1d\x00\x00\x2f\x0a\x0a\x0c\x16\x00\x26\x00\x36\x07\x61\x64\x72\x5f\x77\x61\x70\x46\x00\x0b\x12\x03\xae\xf0\x0f\x22\x03\xae\xf0\x0f\x3c\x42\x6d\x52\x02\x60\x5c\x60\x01\x7c\x82\x00\x0b\xb0\x1f\x9c\xac\x0b\x8c\x98\x0c\xa8\x0c";
+
+    #[derive(Default)]
+    struct UserIdView {
+        huya_ua: String,
+    }
+
+    impl StructFromTars for UserIdView {
+        fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+            Ok(Self {
+                huya_ua: decoder.read_string(3, false, String::new())?,
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct HeartBeatView {
+        user: UserIdView,
+        tid: i64,
+        sid: i64,
+        pid: i64,
+        line_type: i32,
+    }
+
+    impl StructFromTars for HeartBeatView {
+        fn _decode_from(decoder: &mut TarsDecoder) -> Result<Self, DecodeErr> {
+            Ok(Self {
+                user: decoder.read_struct(0, true, UserIdView::default())?,
+                tid: decoder.read_int64(1, false, 0)?,
+                sid: decoder.read_int64(2, false, 0)?,
+                pid: decoder.read_int64(4, false, 0)?,
+                line_type: decoder.read_int32(6, false, 0)?,
+            })
+        }
+    }
+
+    struct Wup {
+        version: i32,
+        servant: String,
+        func: String,
+        req: HeartBeatView,
+    }
+
+    // 拆开 WebSocketCommand -> WUP RequestPacket -> sBuffer["tReq"]
+    fn parse_heartbeat(frame: &[u8]) -> Wup {
+        let cmd = WebSocketCommand::decode(frame).unwrap();
+        assert_eq!(cmd.cmd_type, CMD_WUP_REQ);
+        let (len, packet) = cmd.data.split_at(4);
+        assert_eq!(
+            u32::from_be_bytes(len.try_into().unwrap()) as usize,
+            cmd.data.len()
+        );
+
+        let mut packet = TarsDecoder::from(packet);
+        let version = packet.read_int32(1, true, 0).unwrap();
+        let servant = packet.read_string(5, true, String::new()).unwrap();
+        let func = packet.read_string(6, true, String::new()).unwrap();
+        let params = packet.read_bytes(7, true, Default::default()).unwrap();
+
+        // sBuffer 只有一项：key "tReq"（tag 0 字符串），value 紧随其后（tag 1 字节数组）
+        let key = b"\x06\x04tReq";
+        let at = params
+            .windows(key.len())
+            .position(|w| w == key)
+            .expect("tReq key in sBuffer");
+        let body = TarsDecoder::from(&params[at + key.len()..])
+            .read_bytes(1, true, Default::default())
+            .unwrap();
+        let req = TarsDecoder::from(body.as_ref())
+            .read_struct(0, true, HeartBeatView::default())
+            .unwrap();
+        Wup {
+            version,
+            servant,
+            func,
+            req,
+        }
+    }
+
+    #[test]
+    fn heartbeat_matches_legacy_layout() {
+        let legacy = parse_heartbeat(LEGACY_HEARTBEAT);
+        assert_eq!(legacy.req.tid, 61796367);
+        assert_eq!(legacy.req.pid, 1834091104);
+
+        let packet = heartbeat_packet(1834091104, 61796367, 61796367).unwrap();
+        let wup = parse_heartbeat(&packet);
+        assert_eq!(wup.version, legacy.version);
+        assert_eq!(wup.servant, "onlineui");
+        assert_eq!(wup.servant, legacy.servant);
+        assert_eq!(wup.func, "OnUserHeartBeat");
+        assert_eq!(wup.func, legacy.func);
+        assert_eq!(wup.req.tid, legacy.req.tid);
+        assert_eq!(wup.req.sid, legacy.req.sid);
+        assert_eq!(wup.req.pid, legacy.req.pid);
+        assert_eq!(wup.req.line_type, legacy.req.line_type);
+        assert_eq!(wup.req.user.huya_ua, HUYA_UA);
+    }
+
+    #[test]
+    fn heartbeat_uses_room_ids() {
+        let wup = parse_heartbeat(&heartbeat_packet(42, 100, 200).unwrap());
+        assert_eq!(wup.req.pid, 42);
+        assert_eq!(wup.req.tid, 100);
+        assert_eq!(wup.req.sid, 200);
+    }
+
+    #[test]
+    fn websocket_command_round_trip() {
+        let cmd = WebSocketCommand {
+            cmd_type: CMD_MSG_PUSH_REQ,
+            data: vec![0, 1, 2, 0xff],
+            request_id: 7,
+            trace_id: "trace".to_string(),
+            encrypt_type: 1,
+        };
+        assert_eq!(
+            WebSocketCommand::decode(&cmd.encode().unwrap()).unwrap(),
+            cmd
+        );
+    }
+
+    #[test]
+    fn push_message_round_trip() {
+        let push = WSPushMessage {
+            push_type: 1,
+            uri: 1400,
+            msg: b"payload".to_vec(),
+            protocol_type: 2,
+        };
+        let frame = WebSocketCommand::new(CMD_MSG_PUSH_REQ, to_bytes(&push).unwrap())
+            .encode()
+            .unwrap();
+        let cmd = WebSocketCommand::decode(&frame).unwrap();
+        assert_eq!(cmd.cmd_type, CMD_MSG_PUSH_REQ);
+        assert_eq!(WSPushMessage::decode(&cmd.data).unwrap(), push);
+    }
+
+    #[test]
+    fn register_packets_cover_channel_and_groups() {
+        let packets = register_packets(42, 100, 200).unwrap();
+        let cmds: Vec<i32> = packets
+            .iter()
+            .map(|p| WebSocketCommand::decode(p).unwrap().cmd_type)
+            .collect();
+        assert_eq!(cmds, vec![CMD_REGISTER_REQ, CMD_REGISTER_GROUP_REQ]);
+    }
+}
@@ -1,22 +1,230 @@
-use actix_web::{dev::ServerHandle, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
-use futures_util::TryStreamExt;
+use actix_web::{dev::ServerHandle, web, App, HttpResponse, HttpServer, Responder};
+use futures_util::{StreamExt, TryStreamExt};
 use reqwest::Client;
 // awc removed for now due to API differences; using reqwest streaming
-use crate::StreamUrlStore;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::net::TcpStream;
-use std::sync::Mutex as StdMutex;
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
 use tauri::{AppHandle, State};
+use tokio::sync::watch;
+
+use crate::platforms::common::types_rust::SupportedPlatformRust;
+
+mod hls;
+
+const PROXY_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+// 优先使用固定端口便于调试，被占用时改用系统分配的端口
+const PREFERRED_STREAM_PORT: u16 = 34719;
+
+/// 一路被代理的直播流，播放地址为 `/stream/{session_id}.flv`（HLS 源为 `.m3u8`）
+#[derive(Serialize, Clone, Debug)]
+pub struct StreamSession {
+    pub session_id: String,
+    pub platform: SupportedPlatformRust,
+    pub room_id: String,
+    pub upstream_url: String,
+    /// 本地代理后的播放地址
+    pub url: String,
+    pub created_at: i64,
+}
+
+struct SessionEntry {
+    session: StreamSession,
+    // 注销时丢弃，正在转发的 FLV 连接随之结束
+    closed: watch::Sender<()>,
+}
+
+// actix 各 worker 共享的会话表
+#[derive(Clone, Default)]
+struct StreamSessions(Arc<StdMutex<HashMap<String, SessionEntry>>>);
 
-// Define a struct to hold the server handle in a Tauri managed state
+impl StreamSessions {
+    fn upstream(&self, session_id: &str) -> Option<(String, watch::Receiver<()>)> {
+        let sessions = self.0.lock().unwrap();
+        let entry = sessions.get(session_id)?;
+        Some((entry.session.upstream_url.clone(), entry.closed.subscribe()))
+    }
+
+    fn contains(&self, session_id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(session_id)
+    }
+}
+
+/// 多路直播流代理：服务只启动一次，每路流是一个独立会话，互不影响
 #[derive(Default)]
-pub struct ProxyServerHandle(pub StdMutex<Option<ServerHandle>>);
+pub struct StreamProxy {
+    sessions: StreamSessions,
+    // (端口, 服务句柄)
+    server: tokio::sync::Mutex<Option<(u16, ServerHandle)>>,
+}
+
+impl StreamProxy {
+    async fn ensure_server(&self) -> Result<u16, String> {
+        let mut server_state = self.server.lock().await;
+        if let Some((port, _)) = server_state.as_ref() {
+            return Ok(*port);
+        }
 
-async fn find_free_port() -> u16 {
-    // Using a fixed port as requested by the user for easier debugging
-    34719
+        let sessions = web::Data::new(self.sessions.clone());
+        let make_server = move || {
+            let sessions = sessions.clone();
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(sessions.clone())
+                    // Create reqwest::Client inside the closure for each worker thread
+                    .app_data(web::Data::new(upstream_client()))
+                    .wrap(actix_cors::Cors::permissive())
+                    .route("/stream/{session_id}.flv", web::get().to(flv_proxy_handler))
+                    .route(
+                        "/stream/{session_id}.m3u8",
+                        web::get().to(hls::session_playlist_handler),
+                    )
+                    .route(
+                        "/stream/{session_id}/live.m3u8",
+                        web::get().to(hls::playlist_handler),
+                    )
+                    .route(
+                        "/stream/{session_id}/seg/{key}/{name}",
+                        web::get().to(hls::segment_handler),
+                    )
+                    .route("/image", web::get().to(image_proxy_handler))
+            })
+            .keep_alive(Duration::from_secs(120))
+        };
+
+        let bound = match make_server().bind(("127.0.0.1", PREFERRED_STREAM_PORT)) {
+            Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                eprintln!(
+                    "[Rust/proxy.rs] Port {} in use, falling back to a random port",
+                    PREFERRED_STREAM_PORT
+                );
+                make_server().bind(("127.0.0.1", 0))
+            }
+            other => other,
+        };
+        let bound = bound.map_err(|e| {
+            let err_msg = format!("[Rust/proxy.rs] Failed to bind stream proxy: {}", e);
+            eprintln!("{}", err_msg);
+            err_msg
+        })?;
+        let port = bound
+            .addrs()
+            .first()
+            .map(|addr| addr.port())
+            .ok_or("[Rust/proxy.rs] Stream proxy has no bound address")?;
+
+        let server = bound.run();
+        *server_state = Some((port, server.handle()));
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("[Rust/proxy.rs] Proxy server run error: {}", e);
+            } else {
+                println!("[Rust/proxy.rs] Proxy server on port {} shut down.", port);
+            }
+        });
+        println!("[Rust/proxy.rs] Stream proxy listening on port {}", port);
+        Ok(port)
+    }
+
+    /// 新建一路代理会话；同一房间可以同时有多个会话（如多开观看与录制）
+    pub async fn register(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        upstream_url: &str,
+    ) -> Result<StreamSession, String> {
+        if upstream_url.is_empty() {
+            return Err("Stream URL is empty.".to_string());
+        }
+        let port = self.ensure_server().await?;
+        let session_id = format!("{:016x}", rand::random::<u64>());
+        let extension = if hls::is_hls_url(upstream_url) {
+            "m3u8"
+        } else {
+            "flv"
+        };
+        let session = StreamSession {
+            url: format!(
+                "http://127.0.0.1:{}/stream/{}.{}",
+                port, session_id, extension
+            ),
+            session_id: session_id.clone(),
+            platform,
+            room_id: room_id.to_string(),
+            upstream_url: upstream_url.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+        };
+        let (closed, _) = watch::channel(());
+        self.sessions.0.lock().unwrap().insert(
+            session_id,
+            SessionEntry {
+                session: session.clone(),
+                closed,
+            },
+        );
+        println!(
+            "[Rust/proxy.rs] Registered stream session {} for {} room {}",
+            session.session_id,
+            platform.as_str(),
+            room_id
+        );
+        Ok(session)
+    }
+
+    /// 注销该房间已有的会话后再注册，用于切换清晰度等替换当前播放流的场景
+    pub async fn replace_room(
+        &self,
+        platform: SupportedPlatformRust,
+        room_id: &str,
+        upstream_url: &str,
+    ) -> Result<StreamSession, String> {
+        self.unregister_room(platform, room_id);
+        self.register(platform, room_id, upstream_url).await
+    }
+
+    pub fn unregister(&self, session_id: &str) -> bool {
+        self.sessions.0.lock().unwrap().remove(session_id).is_some()
+    }
+
+    pub fn unregister_room(&self, platform: SupportedPlatformRust, room_id: &str) -> usize {
+        let mut sessions = self.sessions.0.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, entry| {
+            entry.session.platform != platform || entry.session.room_id != room_id
+        });
+        before - sessions.len()
+    }
+
+    pub fn list(&self) -> Vec<StreamSession> {
+        let mut sessions: Vec<StreamSession> = self
+            .sessions
+            .0
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.session.clone())
+            .collect();
+        sessions.sort_by_key(|session| session.created_at);
+        sessions
+    }
+}
+
+fn upstream_client() -> Client {
+    Client::builder()
+        .no_proxy()
+        .http1_only()
+        .gzip(false)
+        .brotli(false)
+        .no_deflate()
+        .pool_idle_timeout(None)
+        .pool_max_idle_per_host(4)
+        .tcp_keepalive(Duration::from_secs(60))
+        .timeout(Duration::from_secs(7200))
+        .build()
+        .expect("failed to build client")
 }
 
 #[derive(Deserialize)]
@@ -111,44 +319,49 @@ async fn image_proxy_handler(
     }
 }
 
-// Your actual proxy logic - this is a simplified placeholder
+// 按直播源域名补上防盗链需要的 Referer/Origin，FLV 与 HLS 共用
+fn with_platform_headers(mut req: reqwest::RequestBuilder, url: &str) -> reqwest::RequestBuilder {
+    // 如果是虎牙域名，添加必要的 Referer/Origin 头
+    if url.contains("huya.com") || url.contains("hy-cdn.com") || url.contains("huyaimg.com") {
+        req = req
+            .header("Referer", "https://www.huya.com/")
+            .header("Origin", "https://www.huya.com");
+    }
+    // 如果是B站域名，添加必要的 Referer 头
+    if url.contains("bilivideo") || url.contains("bilibili.com") || url.contains("hdslb.com") {
+        req = req.header("Referer", "https://live.bilibili.com/");
+    }
+    // 抖音 CDN（douyincdn.com 等）
+    if url.contains("douyin") {
+        req = req.header("Referer", "https://live.douyin.com/");
+    }
+    req
+}
+
+/// `/stream/{session_id}.flv`：转发该会话的 FLV 流，会话注销后连接随之结束
 async fn flv_proxy_handler(
-    _req: HttpRequest,
-    stream_url_store: web::Data<StreamUrlStore>,
+    path: web::Path<String>,
+    sessions: web::Data<StreamSessions>,
     client: web::Data<Client>,
 ) -> impl Responder {
-    let url = stream_url_store.url.lock().unwrap().clone();
-    if url.is_empty() {
-        return HttpResponse::NotFound().body("Stream URL is not set or empty.");
-    }
+    let session_id = path.into_inner();
+    let Some((url, mut closed)) = sessions.upstream(&session_id) else {
+        return HttpResponse::NotFound().body(format!("Stream session {} not found", session_id));
+    };
 
     println!(
-        "[Rust/proxy.rs handler] Incoming FLV proxy request -> {}",
-        url
+        "[Rust/proxy.rs handler] Incoming FLV proxy request for session {} -> {}",
+        session_id, url
     );
 
-    let mut req = client
+    let req = client
         .get(&url)
-        .header(
-            "User-Agent",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
-        )
+        .header("User-Agent", PROXY_USER_AGENT)
         .header("Accept", "video/x-flv,application/octet-stream,*/*")
         .header("Range", "bytes=0-")
         .header("Connection", "keep-alive");
 
-    // 如果是虎牙域名，添加必要的 Referer/Origin 头
-    if url.contains("huya.com") || url.contains("hy-cdn.com") || url.contains("huyaimg.com") {
-        req = req
-            .header("Referer", "https://www.huya.com/")
-            .header("Origin", "https://www.huya.com");
-    }
-    // 如果是B站域名，添加必要的 Referer 头
-    if url.contains("bilivideo") || url.contains("bilibili.com") || url.contains("hdslb.com") {
-        req = req.header("Referer", "https://live.bilibili.com/");
-    }
-
-    match req.send().await {
+    match with_platform_headers(req, &url).send().await {
         Ok(upstream_response) => {
             if upstream_response.status().is_success() {
                 let mut response_builder = HttpResponse::Ok();
@@ -158,16 +371,22 @@ async fn flv_proxy_handler(
                     .insert_header(("Cache-Control", "no-store"))
                     .insert_header(("Accept-Ranges", "bytes"));
 
-                let byte_stream = upstream_response.bytes_stream().map_err(|e| {
-                    eprintln!(
-                        "[Rust/proxy.rs handler] Error reading bytes from upstream: {}",
-                        e
-                    );
-                    actix_web::error::ErrorInternalServerError(format!(
-                        "Upstream stream error: {}",
-                        e
-                    ))
-                });
+                let byte_stream = upstream_response
+                    .bytes_stream()
+                    .map_err(|e| {
+                        eprintln!(
+                            "[Rust/proxy.rs handler] Error reading bytes from upstream: {}",
+                            e
+                        );
+                        actix_web::error::ErrorInternalServerError(format!(
+                            "Upstream stream error: {}",
+                            e
+                        ))
+                    })
+                    // 会话注销时 Sender 被丢弃，changed() 立即返回，流随之结束
+                    .take_until(async move {
+                        let _ = closed.changed().await;
+                    });
 
                 response_builder.streaming(byte_stream)
             } else {
@@ -205,88 +424,7 @@ async fn flv_proxy_handler(
 }
 
 #[tauri::command]
-pub async fn start_proxy(
-    _app_handle: AppHandle,
-    server_handle_state: State<'_, ProxyServerHandle>,
-    stream_url_store: State<'_, StreamUrlStore>,
-) -> Result<String, String> {
-    let port = find_free_port().await;
-    let current_stream_url = stream_url_store.url.lock().unwrap().clone();
-
-    if current_stream_url.is_empty() {
-        return Err("Stream URL is not set in store. Cannot start proxy.".to_string());
-    }
-
-    // stream_url_data_for_actix can be created once and cloned, as StreamUrlStore is Arc based and Send + Sync
-    let stream_url_data_for_actix = web::Data::new(stream_url_store.inner().clone());
-    // REMOVED: let awc_client_for_actix = web::Data::new(Client::default());
-
-    // Ensure MutexGuard is dropped before .await
-    let existing_handle_to_stop = { server_handle_state.0.lock().unwrap().take() };
-    if let Some(existing_handle) = existing_handle_to_stop {
-        existing_handle.stop(false).await;
-    }
-
-    let server = match HttpServer::new(move || {
-        let app_data_stream_url = stream_url_data_for_actix.clone();
-        // Create reqwest::Client inside the closure for each worker thread (for images)
-        let app_data_reqwest_client = web::Data::new(
-            Client::builder()
-                .no_proxy()
-                .http1_only()
-                .gzip(false)
-                .brotli(false)
-                .no_deflate()
-                .pool_idle_timeout(None)
-                .pool_max_idle_per_host(4)
-                .tcp_keepalive(Duration::from_secs(60))
-                .timeout(Duration::from_secs(7200))
-                .build()
-                .expect("failed to build client"),
-        );
-        App::new()
-            .app_data(app_data_stream_url)
-            .app_data(app_data_reqwest_client)
-            .wrap(actix_cors::Cors::permissive())
-            .route("/live.flv", web::get().to(flv_proxy_handler))
-            .route("/image", web::get().to(image_proxy_handler))
-    })
-    .keep_alive(Duration::from_secs(120))
-    .bind(("127.0.0.1", port))
-    {
-        Ok(srv) => srv,
-        Err(e) => {
-            let err_msg = format!(
-                "[Rust/proxy.rs] Failed to bind server to port {}: {}",
-                port, e
-            );
-            eprintln!("{}", err_msg);
-            return Err(err_msg);
-        }
-    }
-    .run();
-
-    let server_handle_for_state = server.handle();
-    *server_handle_state.0.lock().unwrap() = Some(server_handle_for_state);
-
-    // Use tauri::async_runtime::spawn directly
-    tauri::async_runtime::spawn(async move {
-        if let Err(e) = server.await {
-            eprintln!("[Rust/proxy.rs] Proxy server run error: {}", e);
-        } else {
-            println!("[Rust/proxy.rs] Proxy server on port {} shut down.", port);
-        }
-    });
-
-    let proxy_url = format!("http://127.0.0.1:{}/live.flv", port);
-    Ok(proxy_url)
-}
-
-#[tauri::command]
-pub async fn start_static_proxy_server(
-    _app_handle: AppHandle,
-    stream_url_store: State<'_, StreamUrlStore>,
-) -> Result<String, String> {
+pub async fn start_static_proxy_server(_app_handle: AppHandle) -> Result<String, String> {
     // Use a dedicated port for static image proxy to avoid interfering with FLV stream proxy
     let port: u16 = 34721;
 
@@ -295,29 +433,10 @@ pub async fn start_static_proxy_server(
         return Ok(format!("http://127.0.0.1:{}", port));
     }
 
-    let stream_url_data_for_actix = web::Data::new(stream_url_store.inner().clone());
-
     let server = match HttpServer::new(move || {
-        let app_data_stream_url = stream_url_data_for_actix.clone();
-        let app_data_reqwest_client = web::Data::new(
-            Client::builder()
-                .no_proxy()
-                .http1_only()
-                .gzip(false)
-                .brotli(false)
-                .no_deflate()
-                .pool_idle_timeout(None)
-                .pool_max_idle_per_host(4)
-                .tcp_keepalive(Duration::from_secs(60))
-                .timeout(Duration::from_secs(7200))
-                .build()
-                .expect("failed to build client"),
-        );
         App::new()
-            .app_data(app_data_stream_url)
-            .app_data(app_data_reqwest_client)
+            .app_data(web::Data::new(upstream_client()))
             .wrap(actix_cors::Cors::permissive())
-            .route("/live.flv", web::get().to(flv_proxy_handler))
             .route("/image", web::get().to(image_proxy_handler))
     })
     .keep_alive(Duration::from_secs(120))
@@ -343,7 +462,7 @@ pub async fn start_static_proxy_server(
     }
     .run();
 
-    // Do NOT overwrite the main proxy server handle; run static proxy independently
+    // 与直播流代理相互独立，各自运行
 
     tauri::async_runtime::spawn(async move {
         if let Err(e) = server.await {
@@ -357,15 +476,30 @@ pub async fn start_static_proxy_server(
 }
 
 #[tauri::command]
-pub async fn stop_proxy(server_handle_state: State<'_, ProxyServerHandle>) -> Result<(), String> {
-    // Ensure MutexGuard is dropped before .await
-    let handle_to_stop = { server_handle_state.0.lock().unwrap().take() };
-
-    if let Some(handle) = handle_to_stop {
-        handle.stop(false).await; // Changed to non-graceful shutdown
-        println!("[Rust/proxy.rs] stop_proxy: Initiated non-graceful shutdown.");
-    } else {
-        println!("[Rust/proxy.rs] stop_proxy command: No proxy server was running or handle already taken.");
+pub async fn register_stream(
+    platform: SupportedPlatformRust,
+    room_id: String,
+    url: String,
+    proxy: State<'_, StreamProxy>,
+) -> Result<StreamSession, String> {
+    proxy.register(platform, &room_id, &url).await
+}
+
+#[tauri::command]
+pub async fn unregister_stream(
+    session_id: String,
+    proxy: State<'_, StreamProxy>,
+) -> Result<bool, String> {
+    let removed = proxy.unregister(&session_id);
+    if removed {
+        println!("[Rust/proxy.rs] Unregistered stream session {}", session_id);
     }
-    Ok(())
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn list_proxy_streams(
+    proxy: State<'_, StreamProxy>,
+) -> Result<Vec<StreamSession>, String> {
+    Ok(proxy.list())
 }
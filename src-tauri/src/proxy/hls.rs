@@ -0,0 +1,418 @@
+// HLS 代理：改写播放列表，让子播放列表、分片、init 分片（EXT-X-MAP）和密钥都经由本代理请求，
+// 从而带上与 FLV 相同的 UA 和防盗链头。改写后的地址都挂在 `/stream/{session_id}/` 下，
+// 会话注销后这些地址一并失效。
+use std::sync::OnceLock;
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, Responder};
+use regex::{Captures, Regex};
+use reqwest::Client;
+use serde::Deserialize;
+use url::Url;
+
+use super::{with_platform_headers, StreamSessions, PROXY_USER_AGENT};
+
+const PLAYLIST_CONTENT_TYPE: &str = "application/vnd.apple.mpegurl";
+// 分片请求需要透传给播放器的响应头（fMP4 的 BYTERANGE 依赖 Range/Content-Range）
+const SEGMENT_HEADERS: [reqwest::header::HeaderName; 3] = [
+    reqwest::header::CONTENT_TYPE,
+    reqwest::header::CONTENT_RANGE,
+    reqwest::header::ACCEPT_RANGES,
+];
+
+fn uri_attr_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"URI="([^"]*)""#).unwrap())
+}
+
+pub(super) fn is_hls_url(url: &str) -> bool {
+    url.split('?')
+        .next()
+        .is_some_and(|path| path.ends_with(".m3u8"))
+}
+
+fn playlist_route(session_id: &str, target: &Url) -> String {
+    format!(
+        "/stream/{}/live.m3u8?url={}",
+        session_id,
+        urlencoding::encode(target.as_str())
+    )
+}
+
+// 分片地址用十六进制放进路径，避免 %2F 等字符被 actix 解码；末尾保留原文件名，方便播放器按扩展名识别
+fn segment_route(session_id: &str, target: &Url) -> String {
+    let name = target
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("segment");
+    format!(
+        "/stream/{}/seg/{}/{}",
+        session_id,
+        hex::encode(target.as_str()),
+        urlencoding::encode(name)
+    )
+}
+
+// 相对地址按播放列表的最终地址解析；非 http(s) 地址（如 skd://）保持原样
+fn rewrite_uri(session_id: &str, base: &Url, uri: &str, playlist: bool) -> Option<String> {
+    let target = base.join(uri).ok()?;
+    if !matches!(target.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(if playlist {
+        playlist_route(session_id, &target)
+    } else {
+        segment_route(session_id, &target)
+    })
+}
+
+// 带 URI 属性的标签：Some(true) 指向播放列表，Some(false) 指向分片/init 分片/密钥
+fn uri_tag_kind(tag: &str) -> Option<bool> {
+    let name = tag.split(':').next().unwrap_or(tag);
+    match name {
+        "#EXT-X-MEDIA" | "#EXT-X-I-FRAME-STREAM-INF" | "#EXT-X-RENDITION-REPORT" => Some(true),
+        "#EXT-X-MAP"
+        | "#EXT-X-KEY"
+        | "#EXT-X-SESSION-KEY"
+        | "#EXT-X-PART"
+        | "#EXT-X-PRELOAD-HINT" => Some(false),
+        _ => None,
+    }
+}
+
+pub(super) fn rewrite_playlist(body: &str, base: &Url, session_id: &str) -> String {
+    let mut out = String::with_capacity(body.len() * 2);
+    // 主播放列表中 EXT-X-STREAM-INF 后的一行是子播放列表
+    let mut next_is_playlist = false;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            out.push_str(line);
+        } else if trimmed.starts_with('#') {
+            if trimmed.starts_with("#EXT-X-STREAM-INF") {
+                next_is_playlist = true;
+            }
+            match uri_tag_kind(trimmed) {
+                Some(playlist) => {
+                    let rewritten = uri_attr_regex().replace(trimmed, |caps: &Captures| {
+                        match rewrite_uri(session_id, base, &caps[1], playlist) {
+                            Some(route) => format!("URI=\"{}\"", route),
+                            None => caps[0].to_string(),
+                        }
+                    });
+                    out.push_str(&rewritten);
+                }
+                None => out.push_str(trimmed),
+            }
+        } else {
+            let playlist = next_is_playlist || is_hls_url(trimmed);
+            next_is_playlist = false;
+            match rewrite_uri(session_id, base, trimmed, playlist) {
+                Some(route) => out.push_str(&route),
+                None => out.push_str(trimmed),
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn upstream_error(
+    kind: &str,
+    url: &str,
+    status: reqwest::StatusCode,
+    body: String,
+) -> HttpResponse {
+    eprintln!(
+        "[Rust/proxy.rs hls] Upstream {} request to {} failed with status: {}. Body: {}",
+        kind, url, status, body
+    );
+    let actix_status_code =
+        StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    HttpResponse::build(actix_status_code).body(format!(
+        "Error fetching HLS {} from upstream: {}. Status: {}. Details: {}",
+        kind, url, status, body
+    ))
+}
+
+#[derive(Deserialize)]
+pub(super) struct PlaylistQuery {
+    url: String,
+}
+
+/// `/stream/{session_id}.m3u8`：会话的入口播放列表
+pub(super) async fn session_playlist_handler(
+    path: web::Path<String>,
+    sessions: web::Data<StreamSessions>,
+    client: web::Data<Client>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    match sessions.upstream(&session_id) {
+        Some((url, _)) => proxy_playlist(&client, &url, &session_id).await,
+        None => session_not_found(&session_id),
+    }
+}
+
+fn session_not_found(session_id: &str) -> HttpResponse {
+    HttpResponse::NotFound().body(format!("Stream session {} not found", session_id))
+}
+
+/// `/stream/{session_id}/live.m3u8?url=`：主播放列表中引用的子播放列表
+pub(super) async fn playlist_handler(
+    path: web::Path<String>,
+    query: web::Query<PlaylistQuery>,
+    sessions: web::Data<StreamSessions>,
+    client: web::Data<Client>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    if !sessions.contains(&session_id) {
+        return session_not_found(&session_id);
+    }
+    if query.url.is_empty() {
+        return HttpResponse::BadRequest().body("Missing url query parameter");
+    }
+    proxy_playlist(&client, &query.url, &session_id).await
+}
+
+async fn proxy_playlist(client: &Client, url: &str, session_id: &str) -> HttpResponse {
+    let req = client
+        .get(url)
+        .header("User-Agent", PROXY_USER_AGENT)
+        .header(
+            "Accept",
+            "application/vnd.apple.mpegurl,application/x-mpegurl,*/*",
+        );
+    let upstream_response = match with_platform_headers(req, url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!(
+                "[Rust/proxy.rs hls] Failed to send playlist request to upstream {}: {}",
+                url, e
+            );
+            return HttpResponse::InternalServerError().body(format!(
+                "Error connecting to upstream playlist {}: {}",
+                url, e
+            ));
+        }
+    };
+
+    let status = upstream_response.status();
+    if !status.is_success() {
+        let error_text = upstream_response
+            .text()
+            .await
+            .unwrap_or_else(|e| format!("Failed to read error body from upstream: {}", e));
+        return upstream_error("playlist", url, status, error_text);
+    }
+
+    // 跟随重定向后的地址才是相对路径的基准
+    let base = upstream_response.url().clone();
+    match upstream_response.text().await {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(PLAYLIST_CONTENT_TYPE)
+            .insert_header(("Cache-Control", "no-store"))
+            .body(rewrite_playlist(&body, &base, session_id)),
+        Err(e) => {
+            eprintln!("[Rust/proxy.rs hls] Failed to read playlist {}: {}", url, e);
+            HttpResponse::InternalServerError().body(format!("Failed to read playlist: {}", e))
+        }
+    }
+}
+
+/// `/stream/{session_id}/seg/{key}/{name}`：`key` 为分片完整地址的十六进制编码，`name` 仅用于展示
+pub(super) async fn segment_handler(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    sessions: web::Data<StreamSessions>,
+    client: web::Data<Client>,
+) -> impl Responder {
+    let (session_id, key, _name) = path.into_inner();
+    if !sessions.contains(&session_id) {
+        return session_not_found(&session_id);
+    }
+    let Some(url) = hex::decode(&key)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+    else {
+        return HttpResponse::BadRequest().body("Invalid segment key");
+    };
+
+    let mut upstream_req = client
+        .get(&url)
+        .header("User-Agent", PROXY_USER_AGENT)
+        .header("Accept", "*/*");
+    // 透传 Range，EXT-X-MAP / 分片的 BYTERANGE 才能正确取到对应区间
+    if let Some(range) = req.headers().get("Range").and_then(|v| v.to_str().ok()) {
+        upstream_req = upstream_req.header("Range", range);
+    }
+    let upstream_response = match with_platform_headers(upstream_req, &url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!(
+                "[Rust/proxy.rs hls] Failed to send segment request to upstream {}: {}",
+                url, e
+            );
+            return HttpResponse::InternalServerError().body(format!(
+                "Error connecting to upstream segment {}: {}",
+                url, e
+            ));
+        }
+    };
+
+    let status = upstream_response.status();
+    if !status.is_success() {
+        let error_text = upstream_response
+            .text()
+            .await
+            .unwrap_or_else(|e| format!("Failed to read error body from upstream: {}", e));
+        return upstream_error("segment", &url, status, error_text);
+    }
+
+    let mut response_builder =
+        HttpResponse::build(StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK));
+    response_builder.insert_header(("Cache-Control", "no-store"));
+    for name in SEGMENT_HEADERS {
+        if let Some(value) = upstream_response
+            .headers()
+            .get(&name)
+            .and_then(|v| v.to_str().ok())
+        {
+            response_builder.insert_header((name.as_str(), value));
+        }
+    }
+
+    // 分片体积小，与图片代理一样一次性读取，避免 Windows 下 chunked 传输的 Early-EOF
+    match upstream_response.bytes().await {
+        Ok(bytes) => response_builder.body(bytes),
+        Err(e) => {
+            eprintln!("[Rust/proxy.rs hls] Failed to read segment {}: {}", url, e);
+            HttpResponse::InternalServerError().body(format!("Failed to read segment: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = "s1";
+
+    fn base() -> Url {
+        Url::parse("https://cdn.example.com/live/room/index.m3u8?token=1").unwrap()
+    }
+
+    fn playlist(url: &str) -> String {
+        playlist_route(SESSION, &Url::parse(url).unwrap())
+    }
+
+    fn segment(url: &str) -> String {
+        segment_route(SESSION, &Url::parse(url).unwrap())
+    }
+
+    fn rewrite(body: &str, base: &Url) -> Vec<String> {
+        rewrite_playlist(body, base, SESSION)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn rewrites_master_playlist_variants_as_playlists() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1280000\n\
+            hd/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=640000\n\
+            https://other.example.com/sd.php?id=1\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aud\",URI=\"audio/a.m3u8\"\n";
+        assert_eq!(
+            rewrite(body, &base()),
+            vec![
+                "#EXTM3U".to_string(),
+                "#EXT-X-STREAM-INF:BANDWIDTH=1280000".to_string(),
+                playlist("https://cdn.example.com/live/room/hd/index.m3u8"),
+                "#EXT-X-STREAM-INF:BANDWIDTH=640000".to_string(),
+                // STREAM-INF 后一行即使没有 .m3u8 后缀也是子播放列表
+                playlist("https://other.example.com/sd.php?id=1"),
+                format!(
+                    "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aud\",URI=\"{}\"",
+                    playlist("https://cdn.example.com/live/room/audio/a.m3u8")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrites_relative_and_absolute_segments() {
+        let body = "#EXTM3U\n#EXTINF:4.0,\nseg1.ts?t=1\n#EXTINF:4.0,\n/abs/seg2.ts\n\
+            #EXTINF:4.0,\nhttps://edge.example.com/seg3.ts\n";
+        assert_eq!(
+            rewrite(body, &base()),
+            vec![
+                "#EXTM3U".to_string(),
+                "#EXTINF:4.0,".to_string(),
+                segment("https://cdn.example.com/live/room/seg1.ts?t=1"),
+                "#EXTINF:4.0,".to_string(),
+                segment("https://cdn.example.com/abs/seg2.ts"),
+                "#EXTINF:4.0,".to_string(),
+                segment("https://edge.example.com/seg3.ts"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrites_map_and_key_uris_but_keeps_skd() {
+        let body = "#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"keys/k.bin\",IV=0x1\n\
+            #EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://key-id\",KEYFORMAT=\"com.apple.streamingkeys\"\n";
+        assert_eq!(
+            rewrite(body, &base()),
+            vec![
+                format!(
+                    "#EXT-X-MAP:URI=\"{}\",BYTERANGE=\"720@0\"",
+                    segment("https://cdn.example.com/live/room/init.mp4")
+                ),
+                format!(
+                    "#EXT-X-KEY:METHOD=AES-128,URI=\"{}\",IV=0x1",
+                    segment("https://cdn.example.com/live/room/keys/k.bin")
+                ),
+                "#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://key-id\",KEYFORMAT=\"com.apple.streamingkeys\""
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_against_redirected_base() {
+        // proxy_playlist 传入的是跟随重定向后的地址
+        let redirected = Url::parse("https://edge2.example.com/r/abc/index.m3u8").unwrap();
+        assert_eq!(
+            rewrite("seg.ts\n", &redirected),
+            vec![segment("https://edge2.example.com/r/abc/seg.ts")]
+        );
+    }
+
+    #[test]
+    fn classifies_uri_tags() {
+        assert_eq!(uri_tag_kind("#EXT-X-MEDIA:TYPE=AUDIO"), Some(true));
+        assert_eq!(
+            uri_tag_kind("#EXT-X-I-FRAME-STREAM-INF:URI=\"i.m3u8\""),
+            Some(true)
+        );
+        assert_eq!(uri_tag_kind("#EXT-X-MAP:URI=\"init.mp4\""), Some(false));
+        assert_eq!(uri_tag_kind("#EXT-X-KEY:METHOD=NONE"), Some(false));
+        assert_eq!(uri_tag_kind("#EXT-X-STREAM-INF:BANDWIDTH=1"), None);
+        assert_eq!(uri_tag_kind("#EXTINF:4.0,"), None);
+    }
+
+    #[test]
+    fn segment_route_encodes_full_url_and_keeps_file_name() {
+        let url = "https://cdn.example.com/a%2Fb/seg-1.ts?k=v";
+        let route = segment(url);
+        let parts: Vec<&str> = route.split('/').collect();
+        assert_eq!(parts[..4], ["", "stream", SESSION, "seg"]);
+        assert_eq!(hex::decode(parts[4]).unwrap(), url.as_bytes());
+        assert_eq!(parts[5], "seg-1.ts");
+        assert!(segment("https://cdn.example.com/dir/").ends_with("/segment"));
+    }
+}
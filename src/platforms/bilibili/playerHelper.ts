@@ -3,7 +3,10 @@ import { listen, type Event as TauriEvent } from '@tauri-apps/api/event';
 import type { LiveStreamInfo, StreamVariant } from '../common/types';
 import type { Ref } from 'vue';
 import type { DanmakuMessage, DanmuOverlayInstance, DanmuRenderOptions } from '../../components/player/types';
-import { v4 as uuidv4 } from 'uuid';
+import { shouldSendToOverlay, toDanmakuMessage, type UnifiedRustDanmakuPayload } from '../common/danmakuEvents';
+
+// 各房间当前播放所用的代理会话（roomId -> sessionId），停止时按房间注销，多开互不影响
+const bilibiliProxySessions = new Map<string, string>();
 
 export async function getBilibiliStreamConfig(
   roomId: string,
@@ -21,6 +24,12 @@ export async function getBilibiliStreamConfig(
     quality,
     cookie: effectiveCookie || null,
   });
+  // 后端已替换掉该房间之前的代理会话，这里只记录最新的
+  if (result.proxy_session_id) {
+    bilibiliProxySessions.set(roomId, result.proxy_session_id);
+  } else {
+    bilibiliProxySessions.delete(roomId);
+  }
 
   // 若后端返回错误，统一按“未开播”处理（除非明确包含未开播字样）
   if (result.error_message) {
@@ -57,14 +66,15 @@ export async function getBilibiliStreamConfig(
   let streamType: string | undefined;
   const streamUrlLower = result.stream_url.toLowerCase();
 
-  if (
+  // 本地代理的 HLS 会话地址为 /stream/{id}.m3u8，需先于 127.0.0.1 判断
+  if (streamUrlLower.includes('.m3u8')) {
+    streamType = 'hls';
+  } else if (
     streamUrlLower.startsWith('http://127.0.0.1') ||
     streamUrlLower.includes('/live.flv') ||
     streamUrlLower.includes('.flv')
   ) {
     streamType = 'flv';
-  } else if (streamUrlLower.includes('.m3u8')) {
-    streamType = 'hls';
   }
 
   if (!streamType && result.available_streams && Array.isArray(result.available_streams)) {
@@ -109,14 +119,6 @@ export async function getBilibiliStreamConfig(
   return { streamUrl: result.stream_url, streamType };
 }
 
-// 统一的 Rust 弹幕事件负载（与 Douyin/Douyu/Huya 保持一致）
-interface UnifiedRustDanmakuPayload {
-  room_id: string;
-  user: string;
-  content: string;
-  user_level: number;
-  fans_club_level: number;
-}
 
 export async function startBilibiliDanmakuListener(
   roomId: string,
@@ -136,22 +138,16 @@ export async function startBilibiliDanmakuListener(
   const unlisten = await listen<UnifiedRustDanmakuPayload>(eventName, (event: TauriEvent<UnifiedRustDanmakuPayload>) => {
     if (!event.payload || event.payload.room_id !== roomId) return;
 
-    const frontendDanmaku: DanmakuMessage = {
-      id: uuidv4(),
-      nickname: event.payload.user || '未知用户',
-      content: event.payload.content,
-      level: String(event.payload.user_level ?? 0),
-      badgeLevel: event.payload.fans_club_level != null ? String(event.payload.fans_club_level) : undefined,
-      room_id: roomId,
-    };
+    const frontendDanmaku = toDanmakuMessage(event.payload, roomId);
+    if (!frontendDanmaku) return;
 
     const shouldDisplay = renderOptions?.shouldDisplay ? renderOptions.shouldDisplay() : true;
 
-    if (shouldDisplay && danmuOverlay?.sendComment) {
+    if (shouldDisplay && shouldSendToOverlay(frontendDanmaku) && danmuOverlay?.sendComment) {
       try {
         const commentOptions = renderOptions?.buildCommentOptions?.(frontendDanmaku) ?? {};
         const styleFromOptions = commentOptions.style ?? {};
-        const preferredColor = styleFromOptions.color || (frontendDanmaku as any).color || '#FFFFFF';
+        const preferredColor = styleFromOptions.color || frontendDanmaku.color || '#FFFFFF';
         danmuOverlay.sendComment({
           id: frontendDanmaku.id,
           txt: frontendDanmaku.content,
@@ -183,3 +179,19 @@ export async function stopBilibiliDanmaku(currentUnlistenFn: (() => void) | null
     await invoke('stop_bilibili_danmaku_listener');
   } catch {}
 }
+
+export async function stopBilibiliProxy(roomId: string | null | undefined): Promise<void> {
+  if (!roomId) {
+    return;
+  }
+  const sessionId = bilibiliProxySessions.get(roomId);
+  if (!sessionId) {
+    return;
+  }
+  bilibiliProxySessions.delete(roomId);
+  try {
+    await invoke('unregister_stream', { sessionId });
+  } catch (e) {
+    console.error('[BilibiliPlayerHelper] Error unregistering proxy stream:', e);
+  }
+}
@@ -0,0 +1,54 @@
+import type { DanmakuMessage } from '../../components/player/types';
+import { v4 as uuidv4 } from 'uuid';
+
+// Rust 端统一弹幕事件（danmaku-message）的负载，`type` 区分聊天、礼物、进场等
+export interface UnifiedRustDanmakuPayload {
+  room_id?: string;
+  type?: string;
+  user: string;
+  user_id?: string | null;
+  content: string;
+  user_level: number;
+  fans_club_level: number;
+  medal_name?: string | null;
+  color?: string | null;
+}
+
+// overlay：弹幕层 + 列表；list：只进列表；hidden：不展示
+export type DanmakuDisplay = 'overlay' | 'list' | 'hidden';
+
+// 进场、点赞等高频提示和榜单快照不进聊天列表，否则热门房间会被刷屏
+const HIDDEN_TYPES = new Set(['enter', 'like', 'rank', 'room_stats']);
+const OVERLAY_TYPES = new Set(['chat', 'super_chat']);
+
+export function danmakuDisplayOf(type?: string): DanmakuDisplay {
+  // 旧版后端没有 type 字段，按聊天处理
+  if (!type || OVERLAY_TYPES.has(type)) return 'overlay';
+  if (HIDDEN_TYPES.has(type)) return 'hidden';
+  return 'list';
+}
+
+/** 转换为弹幕列表条目；不需要展示的事件返回 null */
+export function toDanmakuMessage(payload: UnifiedRustDanmakuPayload, roomId: string): DanmakuMessage | null {
+  const display = danmakuDisplayOf(payload.type);
+  if (display === 'hidden') return null;
+  return {
+    id: uuidv4(),
+    type: payload.type,
+    // 礼物、上舰等以系统消息样式展示，避免混同普通聊天
+    isSystem: display === 'list',
+    uid: payload.user_id ?? undefined,
+    nickname: payload.user || (display === 'list' ? '系统' : '未知用户'),
+    content: payload.content || '',
+    level: String(payload.user_level || 0),
+    badgeName: payload.medal_name ?? undefined,
+    badgeLevel: payload.fans_club_level > 0 ? String(payload.fans_club_level) : undefined,
+    color: payload.color ?? undefined,
+    room_id: payload.room_id || roomId,
+  };
+}
+
+/** 只有聊天和醒目留言需要飘到弹幕层 */
+export function shouldSendToOverlay(message: DanmakuMessage): boolean {
+  return danmakuDisplayOf(message.type) === 'overlay';
+}
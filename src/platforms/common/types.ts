@@ -133,5 +133,7 @@ export interface LiveStreamInfo {
   normalized_room_id?: string | null;
   // 新增：抖音直播间的 web_rid（关注列表以 web_id 为主键）
   web_rid?: string | null;
+  // 本地代理会话 id，停止播放时用于 unregister_stream
+  proxy_session_id?: string | null;
 }
 // Potentially other platform-specific fields if not covered by StreamRoomDetails
@@ -4,16 +4,10 @@ import { Ref } from 'vue';
 import { Platform } from '../common/types';
 import type { DanmakuMessage, DanmuOverlayInstance, DanmuRenderOptions, RustGetStreamUrlPayload } from '../../components/player/types';
 import type { LiveStreamInfo } from '../common/types';
-import { v4 as uuidv4 } from 'uuid';
+import { shouldSendToOverlay, toDanmakuMessage, type UnifiedRustDanmakuPayload } from '../common/danmakuEvents';
 
-
-export interface DouyinRustDanmakuPayload {
-  room_id?: string; 
-  user: string;      // Nickname from Rust's DanmakuFrontendPayload
-  content: string;
-  user_level: number; // from Rust's i64
-  fans_club_level: number; // from Rust's i32
-}
+// 各房间当前播放所用的代理会话（roomId -> sessionId），只有 HLS 经过代理；停止时按房间注销
+const douyinProxySessions = new Map<string, string>();
 
 export async function fetchAndPrepareDouyinStreamConfig(roomId: string, quality: string = '原画'): Promise<{ 
   streamUrl: string | null;
@@ -36,6 +30,12 @@ export async function fetchAndPrepareDouyinStreamConfig(roomId: string, quality:
       payload: payloadData,
       quality: backendQuality 
     });
+    // 后端已替换掉该房间之前的代理会话（FLV 直连时没有会话），这里只记录最新的
+    if (result.proxy_session_id) {
+      douyinProxySessions.set(roomId, result.proxy_session_id);
+    } else {
+      douyinProxySessions.delete(roomId);
+    }
 
     if (result.error_message) {
       console.error(`[DouyinPlayerHelper] Error from backend for room ${roomId}: ${result.error_message}`);
@@ -55,14 +55,15 @@ export async function fetchAndPrepareDouyinStreamConfig(roomId: string, quality:
     let uiMessage: string | null = null; 
 
     const rawStreamUrl = result.stream_url ?? null;
-    const sanitizedStreamUrl = streamAvailable && rawStreamUrl ? enforceHttps(rawStreamUrl) : null;
+    // 本地代理地址（HLS 会话 /stream/{id}.m3u8）保持 http
+    const isLocalProxy = !!rawStreamUrl && rawStreamUrl.startsWith('http://127.0.0.1');
+    const sanitizedStreamUrl = streamAvailable && rawStreamUrl
+      ? (isLocalProxy ? rawStreamUrl : enforceHttps(rawStreamUrl))
+      : null;
 
     if (streamAvailable && rawStreamUrl) {
-      if (rawStreamUrl.startsWith('http://127.0.0.1') && rawStreamUrl.endsWith('/live.flv')) {
-        streamType = 'flv';
-      } else if (rawStreamUrl.includes('pull-hls') || rawStreamUrl.endsWith('.m3u8')) {
-        console.warn(`[DouyinPlayerHelper] Received HLS-like stream URL (${rawStreamUrl}), but expected flv. Overriding to flv.`);
-        streamType = 'flv';
+      if (rawStreamUrl.includes('pull-hls') || rawStreamUrl.split('?')[0].endsWith('.m3u8')) {
+        streamType = 'hls';
       } else if (rawStreamUrl.includes('pull-flv') || rawStreamUrl.includes('.flv')) {
         streamType = 'flv';
       } else {
@@ -125,21 +126,15 @@ export async function startDouyinDanmakuListener(
   
   const eventName = 'danmaku-message';
 
-  const unlisten = await listen<DouyinRustDanmakuPayload>(eventName, (event: TauriEvent<DouyinRustDanmakuPayload>) => {
+  const unlisten = await listen<UnifiedRustDanmakuPayload>(eventName, (event: TauriEvent<UnifiedRustDanmakuPayload>) => {
     if (event.payload) {
       const rustP = event.payload;
-      const frontendDanmaku: DanmakuMessage = {
-        id: uuidv4(),
-        nickname: rustP.user || '未知用户',
-        content: rustP.content || '',
-        level: String(rustP.user_level || 0),
-        badgeLevel: rustP.fans_club_level > 0 ? String(rustP.fans_club_level) : undefined,
-        room_id: rustP.room_id || roomId, // Ensure room_id is present
-      };
+      const frontendDanmaku = toDanmakuMessage(rustP, roomId);
+      if (!frontendDanmaku) return;
 
       const shouldDisplay = renderOptions?.shouldDisplay ? renderOptions.shouldDisplay() : true;
 
-      if (shouldDisplay && danmuOverlay?.sendComment) {
+      if (shouldDisplay && shouldSendToOverlay(frontendDanmaku) && danmuOverlay?.sendComment) {
         try {
           const commentOptions = renderOptions?.buildCommentOptions?.(frontendDanmaku) ?? {};
           const styleFromOptions = commentOptions.style ?? {};
@@ -182,6 +177,22 @@ export async function stopDouyinDanmaku(currentUnlistenFn: (() => void) | null):
   }
 }
 
+export async function stopDouyinProxy(roomId: string | null | undefined): Promise<void> {
+  if (!roomId) {
+    return;
+  }
+  const sessionId = douyinProxySessions.get(roomId);
+  if (!sessionId) {
+    return;
+  }
+  douyinProxySessions.delete(roomId);
+  try {
+    await invoke('unregister_stream', { sessionId });
+  } catch (e) {
+    console.error('[DouyinPlayerHelper] Error unregistering proxy stream:', e);
+  }
+}
+
 function enforceHttps(url: string): string {
   if (!url) {
     return url;
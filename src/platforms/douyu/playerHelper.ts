@@ -2,18 +2,15 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, type Event as TauriEvent } from '@tauri-apps/api/event';
 import { Ref } from 'vue';
 import type { DanmakuMessage, DanmuOverlayInstance, DanmuRenderOptions } from '../../components/player/types';
-import { v4 as uuidv4 } from 'uuid';
-
-// 统一的 Rust 弹幕事件负载（与 Douyin/Huya 保持一致）
-export interface UnifiedRustDanmakuPayload {
-  room_id?: string;
-  user: string;
-  content: string;
-  user_level: number;
-  fans_club_level: number;
-}
+import { shouldSendToOverlay, toDanmakuMessage, type UnifiedRustDanmakuPayload } from '../common/danmakuEvents';
+
+// 各房间当前播放所用的代理会话（roomId -> sessionId），停止时按房间注销，多开互不影响
+const douyuProxySessions = new Map<string, string>();
 
-let douyuProxyActive = false;
+interface ProxyStreamSession {
+  session_id: string;
+  url: string;
+}
 
 export async function getDouyuStreamConfig(
   roomId: string,
@@ -70,10 +67,15 @@ export async function getDouyuStreamConfig(
   }
 
   try {
-    await invoke('set_stream_url_cmd', { url: finalStreamUrl });
-    const proxyUrl = await invoke<string>('start_proxy');
-    douyuProxyActive = true;
-    return { streamUrl: proxyUrl, streamType };
+    // 只替换本房间的旧会话（如切换清晰度），其它房间的播放不受影响
+    await stopDouyuProxy(roomId);
+    const session = await invoke<ProxyStreamSession>('register_stream', {
+      platform: 'douyu',
+      roomId,
+      url: finalStreamUrl,
+    });
+    douyuProxySessions.set(roomId, session.session_id);
+    return { streamUrl: session.url, streamType };
   } catch (e: any) {
     throw new Error(`设置斗鱼代理失败: ${e.message}`);
   }
@@ -97,18 +99,12 @@ export async function startDouyuDanmakuListener(
       // 仅处理当前 roomId 的消息，避免跨房间干扰
       if (rustP.room_id && rustP.room_id !== roomId) return;
 
-      const frontendDanmaku: DanmakuMessage = {
-        id: uuidv4(),
-        nickname: rustP.user || '未知用户',
-        content: rustP.content || '',
-        level: String(rustP.user_level || 0),
-        badgeLevel: rustP.fans_club_level > 0 ? String(rustP.fans_club_level) : undefined,
-        room_id: rustP.room_id || roomId,
-      };
+      const frontendDanmaku = toDanmakuMessage(rustP, roomId);
+      if (!frontendDanmaku) return;
 
       const shouldDisplay = renderOptions?.shouldDisplay ? renderOptions.shouldDisplay() : true;
 
-      if (shouldDisplay && danmuOverlay?.sendComment) {
+      if (shouldDisplay && shouldSendToOverlay(frontendDanmaku) && danmuOverlay?.sendComment) {
         try {
           const commentOptions = renderOptions?.buildCommentOptions?.(frontendDanmaku) ?? {};
           const styleFromOptions = commentOptions.style ?? {};
@@ -151,16 +147,19 @@ export async function stopDouyuDanmaku(roomId: string, currentUnlistenFn: (() =>
   }
 }
 
-export async function stopDouyuProxy(): Promise<void> {
-  if (!douyuProxyActive) {
+export async function stopDouyuProxy(roomId: string | null | undefined): Promise<void> {
+  if (!roomId) {
+    return;
+  }
+  const sessionId = douyuProxySessions.get(roomId);
+  if (!sessionId) {
     return;
   }
+  douyuProxySessions.delete(roomId);
   try {
-    await invoke('stop_proxy');
-    douyuProxyActive = false;
+    await invoke('unregister_stream', { sessionId });
   } catch (e) {
-    console.error('[DouyuPlayerHelper] Error stopping proxy server:', e);
-    douyuProxyActive = false;
+    console.error('[DouyuPlayerHelper] Error unregistering proxy stream:', e);
   }
 }
 
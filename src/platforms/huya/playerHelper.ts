@@ -2,7 +2,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, type Event as TauriEvent } from '@tauri-apps/api/event';
 import { Ref } from 'vue';
 import type { DanmakuMessage, DanmuOverlayInstance, DanmuRenderOptions } from '../../components/player/types';
-import { v4 as uuidv4 } from 'uuid';
+import { shouldSendToOverlay, toDanmakuMessage, type UnifiedRustDanmakuPayload } from '../common/danmakuEvents';
 
 export interface HuyaUnifiedEntry { quality: string; bitRate: number; url: string; }
 
@@ -40,14 +40,6 @@ export async function getHuyaStreamConfig(
   }
 }
 
-// 统一的 Rust 弹幕事件负载（与 Douyin/Douyu 保持一致）
-interface UnifiedRustDanmakuPayload {
-  room_id: string;
-  user: string;
-  content: string;
-  user_level: number;
-  fans_club_level: number;
-}
 let currentHuyaRoomId: string | null = null;
 
 export async function startHuyaDanmakuListener(
@@ -79,22 +71,16 @@ export async function startHuyaDanmakuListener(
       return;
     }
 
-    const frontendDanmaku: DanmakuMessage = {
-      id: uuidv4(),
-      nickname: event.payload.user || '未知用户',
-      content: event.payload.content,
-      level: String(event.payload.user_level ?? 0),
-      badgeLevel: event.payload.fans_club_level != null ? String(event.payload.fans_club_level) : undefined,
-      room_id: roomId,
-    };
+    const frontendDanmaku = toDanmakuMessage(event.payload, roomId);
+    if (!frontendDanmaku) return;
 
     const shouldDisplay = renderOptions?.shouldDisplay ? renderOptions.shouldDisplay() : true;
 
-    if (shouldDisplay && danmuOverlay?.sendComment) {
+    if (shouldDisplay && shouldSendToOverlay(frontendDanmaku) && danmuOverlay?.sendComment) {
       try {
         const commentOptions = renderOptions?.buildCommentOptions?.(frontendDanmaku) ?? {};
         const styleFromOptions = commentOptions.style ?? {};
-        const preferredColor = styleFromOptions.color || (frontendDanmaku as any).color || '#FFFFFF';
+        const preferredColor = styleFromOptions.color || frontendDanmaku.color || '#FFFFFF';
         danmuOverlay.sendComment({
           id: frontendDanmaku.id,
           txt: frontendDanmaku.content,